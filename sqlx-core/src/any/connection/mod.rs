@@ -156,6 +156,10 @@ impl Connection for AnyConnection {
         delegate_to_mut!(self.ping())
     }
 
+    fn reset(&mut self) -> BoxFuture<'_, Result<(), Error>> {
+        delegate_to_mut!(self.reset())
+    }
+
     fn begin(&mut self) -> BoxFuture<'_, Result<Transaction<'_, Self::Database>, Error>>
     where
         Self: Sized,
@@ -29,6 +29,23 @@ pub trait Connection: Send {
     /// Checks if a connection to the database is still valid.
     fn ping(&mut self) -> BoxFuture<'_, Result<(), Error>>;
 
+    /// Reset the session state of this connection so that it looks as if it was newly opened.
+    ///
+    /// This discards session variables, temporary tables, prepared statements and other state
+    /// left behind by previous users of the connection, using the driver's reset primitive:
+    ///
+    /// * Postgres: `DISCARD ALL`
+    /// * MySQL: `COM_RESET_CONNECTION`
+    /// * MSSQL: the `RESETCONNECTION` flag on the next TDS request
+    ///
+    /// The connection's statement cache is cleared to match. SQLite has no equivalent, so this
+    /// does nothing there.
+    ///
+    /// Used by [`PoolOptions::reset_on_release`][crate::pool::PoolOptions::reset_on_release].
+    fn reset(&mut self) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move { Ok(()) })
+    }
+
     /// Begin a new transaction or establish a savepoint within the active transaction.
     ///
     /// Returns a [`Transaction`] for controlling and tracking the new transaction.
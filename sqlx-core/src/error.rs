@@ -96,6 +96,16 @@ pub enum Error {
     #[error("attempted to acquire a connection on a closed pool")]
     PoolClosed,
 
+    /// [`Pool::acquire`] needed to open a new connection but the pool's circuit breaker is open
+    /// after repeated failures to connect to the database.
+    ///
+    /// See [`PoolOptions::circuit_breaker_threshold`].
+    ///
+    /// [`Pool::acquire`]: crate::pool::Pool::acquire
+    /// [`PoolOptions::circuit_breaker_threshold`]: crate::pool::PoolOptions::circuit_breaker_threshold
+    #[error("pool is unavailable after repeated failures to connect to the database")]
+    PoolUnavailable,
+
     /// A background worker has crashed.
     #[error("attempted to communicate with a crashed background worker")]
     WorkerCrashed,
@@ -73,12 +73,32 @@ where
     }
 
     pub async fn read_raw(&mut self, cnt: usize) -> Result<BytesMut, Error> {
-        read_raw_into(&mut self.stream, &mut self.rbuf, cnt).await?;
+        self.fill(cnt).await?;
         let buf = self.rbuf.split_to(cnt);
 
         Ok(buf)
     }
 
+    /// Wait until at least `cnt` bytes are buffered and return them without consuming them.
+    ///
+    /// Nothing is lost if this is cancelled, so reading a length prefix with `peek` and then
+    /// the whole frame with `read_raw` is cancel-safe.
+    pub async fn peek(&mut self, cnt: usize) -> Result<&[u8], Error> {
+        self.fill(cnt).await?;
+
+        Ok(&self.rbuf[..cnt])
+    }
+
+    async fn fill(&mut self, cnt: usize) -> Result<(), Error> {
+        // a cancelled read may have left part of the requested bytes in the buffer
+        if self.rbuf.len() < cnt {
+            let remaining = cnt - self.rbuf.len();
+            read_raw_into(&mut self.stream, &mut self.rbuf, remaining).await?;
+        }
+
+        Ok(())
+    }
+
     pub async fn read_raw_into(&mut self, buf: &mut BytesMut, cnt: usize) -> Result<(), Error> {
         read_raw_into(&mut self.stream, buf, cnt).await
     }
@@ -64,6 +64,19 @@ impl Connection for MssqlConnection {
         self.execute("/* SQLx ping */").map_ok(|_| ()).boxed()
     }
 
+    fn reset(&mut self) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            // the reset is carried out by the server before it processes our next request
+            self.stream.reset_on_next_request = true;
+            self.execute("/* SQLx reset */").await?;
+
+            // temporary tables may have been dropped so the cached metadata could be stale
+            self.cache_statement.clear();
+
+            Ok(())
+        })
+    }
+
     fn begin(&mut self) -> BoxFuture<'_, Result<Transaction<'_, Self::Database>, Error>>
     where
         Self: Sized,
@@ -34,6 +34,9 @@ pub(crate) struct MssqlStream {
     pub(crate) transaction_descriptor: u64,
     pub(crate) transaction_depth: usize,
 
+    // set the RESETCONNECTION flag on the next SQL batch, RPC or transaction manager request
+    pub(crate) reset_on_next_request: bool,
+
     // current TabularResult from the server that we are iterating over
     response: Option<(PacketHeader, Bytes)>,
 
@@ -57,6 +60,7 @@ impl MssqlStream {
             pending_done_count: 0,
             transaction_descriptor: 0,
             transaction_depth: 0,
+            reset_on_next_request: false,
         })
     }
 
@@ -69,11 +73,23 @@ impl MssqlStream {
         // write out the packet header, leaving room for setting the packet length later
 
         let mut len_offset = 0;
+        let mut status = Status::END_OF_MESSAGE;
+
+        // the flag is only honored on requests, and must be in the first packet of the message
+        if self.reset_on_next_request
+            && matches!(
+                ty,
+                PacketType::SqlBatch | PacketType::Rpc | PacketType::TransactionManagerRequest
+            )
+        {
+            status |= Status::RESET_CONN;
+            self.reset_on_next_request = false;
+        }
 
         self.inner.write_with(
             PacketHeader {
                 r#type: ty,
-                status: Status::END_OF_MESSAGE,
+                status,
                 length: 0,
                 server_process_id: 0,
                 packet_id: 1,
@@ -1,8 +1,9 @@
 use crate::common::StatementCache;
 use crate::connection::{Connection, LogSettings};
 use crate::error::Error;
+use crate::executor::Executor;
 use crate::mysql::protocol::statement::StmtClose;
-use crate::mysql::protocol::text::{Ping, Quit};
+use crate::mysql::protocol::text::{Ping, Quit, ResetConnection};
 use crate::mysql::statement::MySqlStatementMetadata;
 use crate::mysql::{MySql, MySqlConnectOptions};
 use crate::transaction::Transaction;
@@ -42,6 +43,47 @@ impl Debug for MySqlConnection {
     }
 }
 
+impl MySqlConnection {
+    // configure the session the way the rest of the driver expects it; run when the connection
+    // is established and again after `COM_RESET_CONNECTION` restores the server defaults
+    pub(crate) async fn configure_session(&mut self) -> Result<(), Error> {
+        // https://mariadb.com/kb/en/sql-mode/
+
+        // PIPES_AS_CONCAT - Allows using the pipe character (ASCII 124) as string concatenation operator.
+        //                   This means that "A" || "B" can be used in place of CONCAT("A", "B").
+
+        // NO_ENGINE_SUBSTITUTION - If not set, if the available storage engine specified by a CREATE TABLE is
+        //                          not available, a warning is given and the default storage
+        //                          engine is used instead.
+
+        // NO_ZERO_DATE - Don't allow '0000-00-00'. This is invalid in Rust.
+
+        // NO_ZERO_IN_DATE - Don't allow 'YYYY-00-00'. This is invalid in Rust.
+
+        // --
+
+        // Setting the time zone allows us to assume that the output
+        // from a TIMESTAMP field is UTC
+
+        // --
+
+        // https://mathiasbynens.be/notes/mysql-utf8mb4
+
+        let mut options = String::new();
+        options.push_str(r#"SET sql_mode=(SELECT CONCAT(@@sql_mode, ',PIPES_AS_CONCAT,NO_ENGINE_SUBSTITUTION')),"#);
+        options.push_str(r#"time_zone='+00:00',"#);
+        options.push_str(&format!(
+            r#"NAMES {} COLLATE {};"#,
+            self.stream.charset.as_str(),
+            self.stream.collation.as_str()
+        ));
+
+        self.execute(&*options).await?;
+
+        Ok(())
+    }
+}
+
 impl Connection for MySqlConnection {
     type Database = MySql;
 
@@ -73,6 +115,21 @@ impl Connection for MySqlConnection {
         })
     }
 
+    fn reset(&mut self) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            self.stream.wait_until_ready().await?;
+            self.stream.send_packet(ResetConnection).await?;
+            self.stream.recv_ok().await?;
+
+            // the server deallocates all prepared statements and rolls back any transaction
+            self.cache_statement.clear();
+            self.transaction_depth = 0;
+
+            // the reset also restored the server's default `sql_mode`, time zone and charset
+            self.configure_session().await
+        })
+    }
+
     #[doc(hidden)]
     fn flush(&mut self) -> BoxFuture<'_, Result<(), Error>> {
         self.stream.wait_until_ready().boxed()
@@ -0,0 +1,13 @@
+use crate::io::Encode;
+use crate::mysql::protocol::Capabilities;
+
+// https://dev.mysql.com/doc/internals/en/com-reset-connection.html
+
+#[derive(Debug)]
+pub(crate) struct ResetConnection;
+
+impl Encode<'_, Capabilities> for ResetConnection {
+    fn encode_with(&self, buf: &mut Vec<u8>, _: Capabilities) {
+        buf.push(0x1f); // COM_RESET_CONNECTION
+    }
+}
@@ -3,6 +3,7 @@ use rustls::{
     client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier},
     ClientConfig, Error as TlsError, OwnedTrustAnchor, RootCertStore, ServerName,
 };
+use rustls_pemfile::Item;
 use std::io::Cursor;
 use std::sync::Arc;
 use std::time::SystemTime;
@@ -13,13 +14,13 @@ pub async fn configure_tls_connector(
     accept_invalid_certs: bool,
     accept_invalid_hostnames: bool,
     root_cert_path: Option<&CertificateInput>,
+    client_cert_path: Option<&CertificateInput>,
+    client_key_path: Option<&CertificateInput>,
 ) -> Result<sqlx_rt::TlsConnector, Error> {
     let config = ClientConfig::builder().with_safe_defaults();
 
-    let config = if accept_invalid_certs {
-        config
-            .with_custom_certificate_verifier(Arc::new(DummyTlsVerifier))
-            .with_no_client_auth()
+    let verifier: Arc<dyn ServerCertVerifier> = if accept_invalid_certs {
+        Arc::new(DummyTlsVerifier)
     } else {
         let mut cert_store = RootCertStore::empty();
         cert_store.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|ta| {
@@ -43,22 +44,81 @@ pub async fn configure_tls_connector(
             }
         }
 
-        if accept_invalid_hostnames {
-            let verifier = WebPkiVerifier::new(cert_store, None);
+        let verifier = WebPkiVerifier::new(cert_store, None);
 
-            config
-                .with_custom_certificate_verifier(Arc::new(NoHostnameTlsVerifier { verifier }))
-                .with_no_client_auth()
+        if accept_invalid_hostnames {
+            Arc::new(NoHostnameTlsVerifier { verifier })
         } else {
+            Arc::new(verifier)
+        }
+    };
+
+    let config = config.with_custom_certificate_verifier(verifier);
+
+    let config = match (client_cert_path, client_key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            let cert_chain = certs_from_pem(cert_path).await?;
+            let key_der = private_key_from_pem(key_path).await?;
+
             config
-                .with_root_certificates(cert_store)
-                .with_no_client_auth()
+                .with_single_cert(cert_chain, key_der)
+                .map_err(|err| Error::Tls(err.into()))?
+        }
+
+        (Some(_), None) | (None, Some(_)) => {
+            return Err(Error::Tls(
+                "a client certificate and its key must be given together".into(),
+            ));
         }
+
+        (None, None) => config.with_no_client_auth(),
     };
 
     Ok(Arc::new(config).into())
 }
 
+async fn certs_from_pem(input: &CertificateInput) -> Result<Vec<rustls::Certificate>, Error> {
+    let data = input.data().await?;
+    let mut cursor = Cursor::new(data);
+
+    let certs = rustls_pemfile::certs(&mut cursor)
+        .map_err(|_| Error::Tls(format!("Invalid certificate {}", input).into()))?;
+
+    if certs.is_empty() {
+        return Err(Error::Tls(
+            format!("No certificate found in {}", input).into(),
+        ));
+    }
+
+    Ok(certs.into_iter().map(rustls::Certificate).collect())
+}
+
+async fn private_key_from_pem(input: &CertificateInput) -> Result<rustls::PrivateKey, Error> {
+    let data = input.data().await?;
+    let mut cursor = Cursor::new(data);
+
+    // the first private key in the file, in any of the formats rustls understands
+    loop {
+        match rustls_pemfile::read_one(&mut cursor) {
+            Ok(Some(Item::RSAKey(key) | Item::PKCS8Key(key) | Item::ECKey(key))) => {
+                return Ok(rustls::PrivateKey(key));
+            }
+
+            Ok(Some(_)) => {}
+
+            Ok(None) => {
+                return Err(Error::Tls(
+                    format!("No private key found in {}", input).into(),
+                ));
+            }
+
+            Err(_) => {
+                return Err(Error::Tls(format!("Invalid private key {}", input).into()));
+            }
+        }
+    }
+}
+
 struct DummyTlsVerifier;
 
 impl ServerCertVerifier for DummyTlsVerifier {
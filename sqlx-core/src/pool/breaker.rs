@@ -0,0 +1,210 @@
+//! A circuit breaker guarding connection establishment in [`PoolInner`][super::inner::PoolInner].
+//!
+//! After [`PoolOptions::circuit_breaker_threshold`][super::PoolOptions::circuit_breaker_threshold]
+//! consecutive failures to open a connection, the circuit "opens" and further attempts fail
+//! immediately with [`Error::PoolUnavailable`] until the cooldown has elapsed. A single task is
+//! then allowed through to probe the database; if it succeeds the circuit closes again,
+//! otherwise the cooldown is doubled (up to the configured maximum) and the cycle repeats.
+use std::cmp;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+
+#[derive(Debug)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant, cooldown: Duration },
+    HalfOpen { cooldown: Duration },
+}
+
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    threshold: Option<u32>,
+    cooldown: Duration,
+    max_cooldown: Duration,
+    state: Mutex<State>,
+    jitter_seed: RandomState,
+}
+
+/// Permission to attempt a connection, obtained from [`CircuitBreaker::try_attempt()`].
+///
+/// The outcome must be reported with [`succeeded()`][Self::succeeded]; dropping the attempt
+/// without doing so (e.g. if it errored or timed out) counts as a failure.
+#[must_use]
+pub(crate) struct ConnectAttempt<'a> {
+    breaker: &'a CircuitBreaker,
+    is_probe: bool,
+    finished: bool,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(threshold: Option<u32>, cooldown: Duration, max_cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            max_cooldown: cmp::max(max_cooldown, cooldown),
+            state: Mutex::new(State::Closed { failures: 0 }),
+            jitter_seed: RandomState::new(),
+        }
+    }
+
+    /// Returns `Err(Error::PoolUnavailable)` if the circuit is open, or a probe is in flight.
+    pub(crate) fn try_attempt(&self) -> Result<ConnectAttempt<'_>, Error> {
+        if self.threshold.is_none() {
+            return Ok(self.attempt(false));
+        }
+
+        let mut state = self.lock();
+
+        match *state {
+            State::Closed { .. } => Ok(self.attempt(false)),
+            State::Open { until, cooldown } if Instant::now() >= until => {
+                log::info!("attempting to reconnect to the database after cooldown");
+
+                *state = State::HalfOpen { cooldown };
+                Ok(self.attempt(true))
+            }
+            State::Open { .. } | State::HalfOpen { .. } => Err(Error::PoolUnavailable),
+        }
+    }
+
+    /// `true` if connection attempts are currently being rejected.
+    #[cfg(test)]
+    fn is_open(&self) -> bool {
+        !matches!(*self.lock(), State::Closed { .. })
+    }
+
+    fn attempt(&self, is_probe: bool) -> ConnectAttempt<'_> {
+        ConnectAttempt {
+            breaker: self,
+            is_probe,
+            finished: false,
+        }
+    }
+
+    fn record_success(&self) {
+        if self.threshold.is_none() {
+            return;
+        }
+
+        let mut state = self.lock();
+
+        if !matches!(*state, State::Closed { .. }) {
+            log::info!("connection to the database restored; closing circuit breaker");
+        }
+
+        *state = State::Closed { failures: 0 };
+    }
+
+    fn record_failure(&self, is_probe: bool) {
+        let threshold = match self.threshold {
+            Some(threshold) => threshold,
+            None => return,
+        };
+
+        let mut state = self.lock();
+
+        let cooldown = match *state {
+            State::Closed { failures } if failures + 1 < threshold => {
+                *state = State::Closed {
+                    failures: failures + 1,
+                };
+                return;
+            }
+            State::Closed { .. } => self.cooldown,
+            // only the probe gets to decide what happens next
+            State::HalfOpen { cooldown } if is_probe => cmp::min(cooldown * 2, self.max_cooldown),
+            // an attempt that started before the circuit opened
+            State::HalfOpen { .. } | State::Open { .. } => return,
+        };
+
+        let delay = self.jitter(cooldown);
+
+        log::warn!(
+            "unable to connect to the database; rejecting new connections for {:?}",
+            delay
+        );
+
+        *state = State::Open {
+            until: Instant::now() + delay,
+            cooldown,
+        };
+    }
+
+    /// Pick a random duration between 50% and 100% of `cooldown` so that several pools
+    /// pointed at the same database don't all come back at once.
+    fn jitter(&self, cooldown: Duration) -> Duration {
+        let hash = self.jitter_seed.hash_one(Instant::now());
+
+        let factor = 0.5 + (hash as f64 / u64::MAX as f64) / 2.0;
+        cooldown.mul_f64(factor)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // The state is always consistent so it's fine to ignore poisoning.
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl ConnectAttempt<'_> {
+    pub(crate) fn succeeded(&mut self) {
+        self.finished = true;
+        self.breaker.record_success();
+    }
+}
+
+impl Drop for ConnectAttempt<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.breaker.record_failure(self.is_probe);
+        }
+    }
+}
+
+#[test]
+fn test_circuit_breaker() {
+    let breaker = CircuitBreaker::new(Some(2), Duration::ZERO, Duration::ZERO);
+
+    drop(breaker.try_attempt().unwrap());
+    assert!(!breaker.is_open());
+    drop(breaker.try_attempt().unwrap());
+    assert!(breaker.is_open());
+
+    // The cooldown has elapsed, so exactly one probe is let through.
+    let mut probe = breaker.try_attempt().unwrap();
+    assert!(matches!(breaker.try_attempt(), Err(Error::PoolUnavailable)));
+
+    probe.succeeded();
+    assert!(!breaker.is_open());
+}
+
+#[test]
+fn test_circuit_breaker_backoff() {
+    let breaker = CircuitBreaker::new(Some(1), Duration::from_secs(10), Duration::from_secs(15));
+
+    drop(breaker.try_attempt().unwrap());
+    assert!(matches!(breaker.try_attempt(), Err(Error::PoolUnavailable)));
+
+    // Pretend the cooldown elapsed and the probe failed.
+    *breaker.lock() = State::Open {
+        until: Instant::now(),
+        cooldown: Duration::from_secs(10),
+    };
+    drop(breaker.try_attempt().unwrap());
+
+    let state = breaker.lock();
+
+    match *state {
+        State::Open { until, cooldown } => {
+            // Doubled, but capped by `max_cooldown`.
+            assert_eq!(cooldown, Duration::from_secs(15));
+            assert!(until >= Instant::now() + Duration::from_secs(7));
+        }
+        ref state => panic!("expected open circuit, got {:?}", state),
+    }
+}
@@ -11,6 +11,7 @@ use crate::error::Error;
 
 use super::inner::{DecrementSizeGuard, PoolInner};
 use crate::pool::options::PoolConnectionMetadata;
+use crate::pool::{CloseReason, PoolMetricsObserver};
 use std::future::Future;
 
 /// A connection managed by a [`Pool`][crate::pool::Pool].
@@ -19,11 +20,14 @@ use std::future::Future;
 pub struct PoolConnection<DB: Database> {
     live: Option<Live<DB>>,
     pub(crate) pool: Arc<PoolInner<DB>>,
+    /// The ID of this checkout in `PoolInner::checkouts`.
+    checkout_id: u64,
 }
 
 pub(super) struct Live<DB: Database> {
     pub(super) raw: DB::Connection,
     pub(super) created_at: Instant,
+    pub(super) generation: u64,
 }
 
 pub(super) struct Idle<DB: Database> {
@@ -115,6 +119,8 @@ impl<DB: Database> PoolConnection<DB> {
 
         let pool = self.pool.clone();
 
+        pool.checkouts.unregister(self.checkout_id);
+
         async move {
             let returned_to_pool = if let Some(floating) = floating {
                 floating.return_to_pool().await
@@ -132,8 +138,10 @@ impl<DB: Database> PoolConnection<DB> {
 /// Returns the connection to the [`Pool`][crate::pool::Pool] it was checked-out from.
 impl<DB: Database> Drop for PoolConnection<DB> {
     fn drop(&mut self) {
+        self.pool.checkouts.unregister(self.checkout_id);
+
         // We still need to spawn a task to maintain `min_connections`.
-        if self.live.is_some() || self.pool.options.min_connections > 0 {
+        if self.live.is_some() || self.pool.min_connections() > 0 {
             #[cfg(not(feature = "_rt-async-std"))]
             if let Ok(handle) = sqlx_rt::Handle::try_current() {
                 handle.spawn(self.return_to_pool());
@@ -182,6 +190,7 @@ impl<DB: Database> Floating<DB, Live<DB>> {
             inner: Live {
                 raw: conn,
                 created_at: Instant::now(),
+                generation: guard.pool.generation(),
             },
             guard,
         }
@@ -192,10 +201,13 @@ impl<DB: Database> Floating<DB, Live<DB>> {
 
         let pool = Arc::clone(&guard.pool);
 
+        let checkout_id = pool.checkouts.register();
+
         guard.cancel();
         PoolConnection {
             live: Some(inner),
             pool,
+            checkout_id,
         }
     }
 
@@ -209,7 +221,18 @@ impl<DB: Database> Floating<DB, Live<DB>> {
     async fn return_to_pool(mut self) -> bool {
         // Immediately close the connection.
         if self.guard.pool.is_closed() {
-            self.close().await;
+            self.close(CloseReason::PoolClosed).await;
+            return false;
+        }
+
+        // The pool was shrunk while this connection was checked out.
+        if self.guard.pool.try_shrink() {
+            self.close_surplus().await;
+            return false;
+        }
+
+        if self.guard.pool.is_stale(&self) {
+            self.close(CloseReason::Recycled).await;
             return false;
         }
 
@@ -218,14 +241,14 @@ impl<DB: Database> Floating<DB, Live<DB>> {
             match (test)(&mut self.inner.raw, meta).await {
                 Ok(true) => (),
                 Ok(false) => {
-                    self.close().await;
+                    self.close(CloseReason::AfterRelease).await;
                     return false;
                 }
                 Err(e) => {
                     log::warn!("error from after_release: {}", e);
                     // Connection is broken, don't try to gracefully close as
                     // something weird might happen.
-                    self.close_hard().await;
+                    self.close_hard(CloseReason::AfterRelease).await;
                     return false;
                 }
             }
@@ -238,33 +261,65 @@ impl<DB: Database> Floating<DB, Live<DB>> {
         // returned to the pool; also of course, if it was dropped due to an error
         // this is simply a band-aid as SQLx-next connections should be able
         // to recover from cancellations
-        if let Err(e) = self.raw.ping().await {
+        if self.guard.pool.options.reset_on_release {
+            // the reset is a round-trip as well so there's no need to ping afterwards
+            if let Err(e) = self.raw.reset().await {
+                log::warn!(
+                    "error occurred while resetting the connection on-release: {}",
+                    e
+                );
+
+                // Connection is in an unknown state, don't try to gracefully close.
+                self.close_hard(CloseReason::ResetFailed).await;
+                return false;
+            }
+        } else if let Err(e) = self.raw.ping().await {
             log::warn!(
                 "error occurred while testing the connection on-release: {}",
                 e
             );
 
             // Connection is broken, don't try to gracefully close.
-            self.close_hard().await;
-            false
-        } else {
-            // if the connection is still viable, release it to the pool
-            self.release();
-            true
+            self.close_hard(CloseReason::PingFailed).await;
+            return false;
         }
+
+        // if the connection is still viable, release it to the pool
+        self.guard.pool.metrics.connection_released(self.metadata());
+        self.release();
+        true
     }
 
-    pub async fn close(self) {
+    pub async fn close(self, reason: CloseReason) {
+        self.guard.pool.metrics.connection_closed(reason);
+
         // This isn't used anywhere that we care about the return value
         let _ = self.inner.raw.close().await;
 
         // `guard` is dropped as intended
     }
 
-    pub async fn close_hard(self) {
+    pub async fn close_hard(self, reason: CloseReason) {
+        self.guard.pool.metrics.connection_closed(reason);
+
         let _ = self.inner.raw.close_hard().await;
     }
 
+    /// Close a connection whose size was already given up by `PoolInner::try_shrink()`.
+    pub async fn close_surplus(self) {
+        let Floating { inner, guard } = self;
+
+        guard
+            .pool
+            .metrics
+            .connection_closed(CloseReason::PoolResized);
+
+        // release the permit first so cancelling the close can't decrement the size twice
+        guard.release_permit();
+
+        let _ = inner.raw.close().await;
+    }
+
     pub fn detach(self) -> DB::Connection {
         self.inner.raw
     }
@@ -307,14 +362,18 @@ impl<DB: Database> Floating<DB, Idle<DB>> {
         }
     }
 
-    pub async fn close(self) -> DecrementSizeGuard<DB> {
+    pub async fn close(self, reason: CloseReason) -> DecrementSizeGuard<DB> {
+        self.guard.pool.metrics.connection_closed(reason);
+
         if let Err(e) = self.inner.live.raw.close().await {
             log::debug!("error occurred while closing the pool connection: {}", e);
         }
         self.guard
     }
 
-    pub async fn close_hard(self) -> DecrementSizeGuard<DB> {
+    pub async fn close_hard(self, reason: CloseReason) -> DecrementSizeGuard<DB> {
+        self.guard.pool.metrics.connection_closed(reason);
+
         let _ = self.inner.live.raw.close_hard().await;
 
         self.guard
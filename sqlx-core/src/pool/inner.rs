@@ -1,17 +1,21 @@
+use super::breaker::CircuitBreaker;
 use super::connection::{Floating, Idle, Live};
 use crate::connection::ConnectOptions;
 use crate::connection::Connection;
 use crate::database::Database;
 use crate::error::Error;
-use crate::pool::{deadline_as_timeout, CloseEvent, PoolOptions};
-use crossbeam_queue::ArrayQueue;
+use crate::pool::leak::CheckoutRegistry;
+use crate::pool::metrics::PoolMetrics;
+use crate::pool::{deadline_as_timeout, CloseEvent, CloseReason, PoolMetricsObserver, PoolOptions};
+use crossbeam_queue::SegQueue;
 
 use futures_intrusive::sync::{Semaphore, SemaphoreReleaser};
+use futures_util::future::{self, Either};
 
 use std::cmp;
 use std::future::Future;
-use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
 
 use crate::pool::options::PoolConnectionMetadata;
 use std::time::{Duration, Instant};
@@ -24,13 +28,33 @@ const WAKE_ALL_PERMITS: usize = usize::MAX / 2;
 
 pub(crate) struct PoolInner<DB: Database> {
     pub(super) connect_options: <DB::Connection as Connection>::Options,
-    pub(super) idle_conns: ArrayQueue<Idle<DB>>,
+    /// Options returned by the provider which haven't been used to open a connection yet.
+    provided_options: Mutex<Option<<DB::Connection as Connection>::Options>>,
+    /// Incremented by `Pool::recycle_connections()`; connections opened in a previous
+    /// generation are closed instead of being reused.
+    generation: AtomicU64,
+    pub(super) idle_conns: SegQueue<Idle<DB>>,
     pub(super) semaphore: Semaphore,
     pub(super) size: AtomicU32,
     pub(super) num_idle: AtomicUsize,
     is_closed: AtomicBool,
     pub(super) on_closed: event_listener::Event,
+    pub(super) metrics: PoolMetrics,
+    pub(super) checkouts: CheckoutRegistry,
+    breaker: CircuitBreaker,
     pub(super) options: PoolOptions<DB>,
+    // The following are initialized from `options` but may be changed at runtime
+    // through `Pool::set_*()`, so they must always be read from here.
+    max_connections: AtomicU32,
+    min_connections: AtomicU32,
+    max_lifetime: AtomicOptionDuration,
+    idle_timeout: AtomicOptionDuration,
+    /// The number of semaphore permits in circulation.
+    ///
+    /// This lags behind `max_connections` while the pool is shrinking, as permits held by
+    /// checked-out connections can only be retired once they're returned.
+    capacity: AtomicU32,
+    on_settings_changed: event_listener::Event,
 }
 
 impl<DB: Database> PoolInner<DB> {
@@ -48,12 +72,27 @@ impl<DB: Database> PoolInner<DB> {
 
         let pool = Self {
             connect_options,
-            idle_conns: ArrayQueue::new(capacity),
+            provided_options: Mutex::new(None),
+            generation: AtomicU64::new(0),
+            idle_conns: SegQueue::new(),
             semaphore: Semaphore::new(options.fair, capacity),
             size: AtomicU32::new(0),
             num_idle: AtomicUsize::new(0),
             is_closed: AtomicBool::new(false),
             on_closed: event_listener::Event::new(),
+            metrics: PoolMetrics::new(options.metrics_observer.clone()),
+            checkouts: CheckoutRegistry::new(options.leak_detection_threshold.is_some()),
+            breaker: CircuitBreaker::new(
+                options.circuit_breaker_threshold,
+                options.circuit_breaker_cooldown,
+                options.circuit_breaker_max_cooldown,
+            ),
+            max_connections: AtomicU32::new(options.max_connections),
+            min_connections: AtomicU32::new(options.min_connections),
+            max_lifetime: AtomicOptionDuration::new(options.max_lifetime),
+            idle_timeout: AtomicOptionDuration::new(options.idle_timeout),
+            capacity: AtomicU32::new(options.max_connections),
+            on_settings_changed: event_listener::Event::new(),
             options,
         };
 
@@ -61,6 +100,10 @@ impl<DB: Database> PoolInner<DB> {
 
         spawn_maintenance_tasks(&pool);
 
+        if let Some(threshold) = pool.options.leak_detection_threshold {
+            spawn_leak_detector(&pool, threshold);
+        }
+
         pool
     }
 
@@ -81,8 +124,177 @@ impl<DB: Database> PoolInner<DB> {
         self.is_closed.load(Ordering::Acquire)
     }
 
+    pub(super) fn max_connections(&self) -> u32 {
+        self.max_connections.load(Ordering::Acquire)
+    }
+
+    pub(super) fn min_connections(&self) -> u32 {
+        self.min_connections.load(Ordering::Acquire)
+    }
+
+    pub(super) fn max_lifetime(&self) -> Option<Duration> {
+        self.max_lifetime.load()
+    }
+
+    pub(super) fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout.load()
+    }
+
+    pub(super) fn set_max_connections(self: &Arc<Self>, max: u32) {
+        if self.is_closed() {
+            return;
+        }
+
+        let prev = self.max_connections.swap(max, Ordering::AcqRel);
+
+        if max > prev {
+            let added = max - prev;
+
+            let capacity = self.capacity.fetch_add(added, Ordering::AcqRel) + added;
+
+            // same check as in `new_arc()`
+            let _ = (capacity as usize)
+                .checked_add(WAKE_ALL_PERMITS)
+                .expect("max_connections exceeds max capacity of the pool");
+
+            self.semaphore.release(added as usize);
+        } else if max < prev {
+            self.spawn_retire_permits(prev - max);
+        }
+
+        self.on_settings_changed.notify(usize::MAX);
+    }
+
+    pub(super) fn set_min_connections(&self, min: u32) {
+        self.min_connections.store(min, Ordering::Release);
+        self.on_settings_changed.notify(usize::MAX);
+    }
+
+    pub(super) fn set_max_lifetime(&self, lifetime: Option<Duration>) {
+        self.max_lifetime.store(lifetime);
+        self.on_settings_changed.notify(usize::MAX);
+    }
+
+    pub(super) fn set_idle_timeout(&self, timeout: Option<Duration>) {
+        self.idle_timeout.store(timeout);
+        self.on_settings_changed.notify(usize::MAX);
+    }
+
+    pub(super) fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` if the connection was opened before the last call to
+    /// `Pool::recycle_connections()`.
+    pub(super) fn is_stale(&self, live: &Live<DB>) -> bool {
+        live.generation < self.generation()
+    }
+
+    pub(super) fn recycle_connections(self: &Arc<Self>) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+
+        // Close idle connections now rather than waiting for them to be acquired.
+        let pool = Arc::clone(self);
+
+        sqlx_rt::spawn(async move {
+            pool.close_stale_idle().await;
+            pool.min_connections_maintenance(None).await;
+        });
+    }
+
+    /// Close every stale idle connection, regardless of `min_connections`.
+    async fn close_stale_idle(self: &Arc<Self>) {
+        let conns = (0..self.num_idle())
+            .filter_map(|_| self.try_acquire())
+            .collect::<Vec<_>>();
+
+        let mut stale = Vec::new();
+
+        for conn in conns {
+            if self.is_stale(&conn) {
+                stale.push(conn);
+            } else {
+                self.release(conn.into_live());
+            }
+        }
+
+        for conn in stale {
+            let _ = conn.close(CloseReason::Recycled).await;
+        }
+    }
+
+    /// Returns `true` if the pool has more connections open than it's currently allowed.
+    pub(super) fn is_over_capacity(&self) -> bool {
+        self.size() > self.max_connections()
+    }
+
+    /// Try to atomically give up one connection's worth of size if the pool is over capacity.
+    ///
+    /// On success, the caller must close its connection with `Floating::close_surplus()`
+    /// so the size isn't decremented twice.
+    pub(super) fn try_shrink(&self) -> bool {
+        self.size
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |size| {
+                (size > self.max_connections()).then(|| size - 1)
+            })
+            .is_ok()
+    }
+
+    /// Take `count` permits out of circulation in the background after `max_connections`
+    /// was lowered.
+    ///
+    /// Permits held by checked-out connections can't be retired until they're returned,
+    /// so this waits in line with every other task calling `acquire()`.
+    fn spawn_retire_permits(self: &Arc<Self>, count: u32) {
+        let pool = Arc::clone(self);
+
+        sqlx_rt::spawn(async move {
+            // If the pool is closed, all permits are retired anyway.
+            let _ = pool
+                .close_event()
+                .do_until(async {
+                    pool.close_surplus_idle().await;
+
+                    for _ in 0..count {
+                        let mut permit = pool.semaphore.acquire(1).await;
+                        permit.disarm();
+                        pool.capacity.fetch_sub(1, Ordering::SeqCst);
+
+                        // `close()` may have read the capacity before we lowered it, in which
+                        // case it waits for this permit too, so give it back
+                        if pool.is_closed.load(Ordering::SeqCst) {
+                            pool.semaphore.release(1);
+                            return;
+                        }
+
+                        pool.close_surplus_idle().await;
+                    }
+                })
+                .await;
+        });
+    }
+
+    /// Close idle connections until the pool size is within `max_connections`.
+    async fn close_surplus_idle(self: &Arc<Self>) {
+        while self.is_over_capacity() {
+            let conn = match self.try_acquire() {
+                Some(conn) => conn.into_live(),
+                None => break,
+            };
+
+            if self.try_shrink() {
+                conn.close_surplus().await;
+            } else {
+                // a returning connection beat us to it
+                conn.release();
+                break;
+            }
+        }
+    }
+
     pub(super) fn close<'a>(self: &'a Arc<Self>) -> impl Future<Output = ()> + 'a {
-        let already_closed = self.is_closed.swap(true, Ordering::AcqRel);
+        // `SeqCst` pairs with `spawn_retire_permits()`, see there
+        let already_closed = self.is_closed.swap(true, Ordering::SeqCst);
 
         if !already_closed {
             // if we were the one to mark this closed, release enough permits to wake all waiters
@@ -95,18 +307,26 @@ impl<DB: Database> PoolInner<DB> {
         async move {
             // Close any currently idle connections in the pool.
             while let Some(idle) = self.idle_conns.pop() {
-                let _ = idle.live.float((*self).clone()).close().await;
+                let _ = idle
+                    .live
+                    .float((*self).clone())
+                    .close(CloseReason::PoolClosed)
+                    .await;
             }
 
             // Wait for all permits to be released.
             let _permits = self
                 .semaphore
-                .acquire(WAKE_ALL_PERMITS + (self.options.max_connections as usize))
+                .acquire(WAKE_ALL_PERMITS + (self.capacity.load(Ordering::SeqCst) as usize))
                 .await;
 
             // Clean up any remaining connections.
             while let Some(idle) = self.idle_conns.pop() {
-                let _ = idle.live.float((*self).clone()).close().await;
+                let _ = idle
+                    .live
+                    .float((*self).clone())
+                    .close(CloseReason::PoolClosed)
+                    .await;
             }
         }
     }
@@ -144,9 +364,7 @@ impl<DB: Database> PoolInner<DB> {
 
         let Floating { inner: idle, guard } = floating.into_idle();
 
-        if !self.idle_conns.push(idle).is_ok() {
-            panic!("BUG: connection queue overflow in release()");
-        }
+        self.idle_conns.push(idle);
 
         // NOTE: we need to make sure we drop the permit *after* we push to the idle queue
         // don't decrease the size
@@ -164,7 +382,7 @@ impl<DB: Database> PoolInner<DB> {
             .size
             .fetch_update(Ordering::AcqRel, Ordering::Acquire, |size| {
                 size.checked_add(1)
-                    .filter(|size| size <= &self.options.max_connections)
+                    .filter(|size| size <= &self.max_connections())
             }) {
             // we successfully incremented the size
             Ok(_) => Ok(DecrementSizeGuard::from_permit((*self).clone(), permit)),
@@ -174,11 +392,32 @@ impl<DB: Database> PoolInner<DB> {
     }
 
     pub(super) async fn acquire(self: &Arc<Self>) -> Result<Floating<DB, Live<DB>>, Error> {
+        let started_at = Instant::now();
+
+        self.metrics.acquire_started();
+
+        let guard = AcquireMetricsGuard {
+            metrics: &self.metrics,
+            started_at,
+            finished: false,
+        };
+
+        let res = self.acquire_inner(started_at).await;
+
+        guard.finish(&res);
+
+        res
+    }
+
+    async fn acquire_inner(
+        self: &Arc<Self>,
+        started_at: Instant,
+    ) -> Result<Floating<DB, Live<DB>>, Error> {
         if self.is_closed() {
             return Err(Error::PoolClosed);
         }
 
-        let deadline = Instant::now() + self.options.acquire_timeout;
+        let deadline = started_at + self.options.acquire_timeout;
 
         sqlx_rt::timeout(
             self.options.acquire_timeout,
@@ -194,7 +433,7 @@ impl<DB: Database> PoolInner<DB> {
                     let guard = match self.pop_idle(permit) {
 
                         // Then, check that we can use it...
-                        Ok(conn) => match check_idle_conn(conn, &self.options).await {
+                        Ok(conn) => match check_idle_conn(conn, self).await {
 
                             // All good!
                             Ok(live) => return Ok(live),
@@ -236,11 +475,17 @@ impl<DB: Database> PoolInner<DB> {
         loop {
             let timeout = deadline_as_timeout::<DB>(deadline)?;
 
+            // fails fast with `PoolUnavailable` if the database is known to be down
+            let mut attempt = self.breaker.try_attempt()?;
+
             // result here is `Result<Result<C, Error>, TimeoutError>`
             // if this block does not return, sleep for the backoff timeout and try again
-            match sqlx_rt::timeout(timeout, self.connect_options.connect()).await {
+            match sqlx_rt::timeout(timeout, self.open_connection()).await {
                 // successfully established connection
                 Ok(Ok(mut raw)) => {
+                    attempt.succeeded();
+                    self.metrics.connection_opened();
+
                     // See comment on `PoolOptions::after_connect`
                     let meta = PoolConnectionMetadata {
                         age: Duration::ZERO,
@@ -257,6 +502,7 @@ impl<DB: Database> PoolInner<DB> {
                         Ok(()) => return Ok(Floating::new_live(raw, guard)),
                         Err(e) => {
                             log::error!("error returned from after_connect: {:?}", e);
+                            self.metrics.connection_closed(CloseReason::AfterConnect);
                             // The connection is broken, don't try to close nicely.
                             let _ = raw.close_hard().await;
 
@@ -279,6 +525,9 @@ impl<DB: Database> PoolInner<DB> {
                 Err(_) => return Err(Error::PoolTimedOut),
             }
 
+            // record the failure (if any) before we sleep
+            drop(attempt);
+
             // If the connection is refused, wait in exponentially
             // increasing steps for the server to come up,
             // capped by a factor of the remaining time until the deadline
@@ -287,6 +536,38 @@ impl<DB: Database> PoolInner<DB> {
         }
     }
 
+    /// Hand options already returned by the provider to the next connection to be opened,
+    /// instead of asking the provider again.
+    pub(super) fn set_provided_options(&self, options: <DB::Connection as Connection>::Options) {
+        *self.lock_provided_options() = Some(options);
+    }
+
+    /// Open a new connection using fresh options from the provider if set.
+    async fn open_connection(&self) -> Result<DB::Connection, Error> {
+        match &self.options.connect_options_provider {
+            Some(provider) => {
+                let provided = self.lock_provided_options().take();
+
+                let connect_options = match provided {
+                    Some(options) => options,
+                    None => provider().await?,
+                };
+
+                connect_options.connect().await
+            }
+            None => self.connect_options.connect().await,
+        }
+    }
+
+    fn lock_provided_options(
+        &self,
+    ) -> MutexGuard<'_, Option<<DB::Connection as Connection>::Options>> {
+        // The slot is always consistent so it's fine to ignore poisoning.
+        self.provided_options
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
     /// Try to maintain `min_connections`, returning any errors (including `PoolTimedOut`).
     pub async fn try_min_connections(self: &Arc<Self>, deadline: Instant) -> Result<(), Error> {
         macro_rules! unwrap_or_return {
@@ -298,7 +579,7 @@ impl<DB: Database> PoolInner<DB> {
             };
         }
 
-        while self.size() < self.options.min_connections {
+        while self.size() < self.min_connections() {
             // Don't wait for a semaphore permit.
             //
             // If no extra permits are available then we shouldn't be trying to spin up
@@ -334,28 +615,32 @@ impl<DB: Database> PoolInner<DB> {
     }
 }
 
-/// Returns `true` if the connection has exceeded `options.max_lifetime` if set, `false` otherwise.
-fn is_beyond_max_lifetime<DB: Database>(live: &Live<DB>, options: &PoolOptions<DB>) -> bool {
-    options
-        .max_lifetime
+/// Returns `true` if the connection has exceeded `max_lifetime` if set, `false` otherwise.
+fn is_beyond_max_lifetime<DB: Database>(live: &Live<DB>, pool: &PoolInner<DB>) -> bool {
+    pool.max_lifetime()
         .map_or(false, |max| live.created_at.elapsed() > max)
 }
 
-/// Returns `true` if the connection has exceeded `options.idle_timeout` if set, `false` otherwise.
-fn is_beyond_idle_timeout<DB: Database>(idle: &Idle<DB>, options: &PoolOptions<DB>) -> bool {
-    options
-        .idle_timeout
+/// Returns `true` if the connection has exceeded `idle_timeout` if set, `false` otherwise.
+fn is_beyond_idle_timeout<DB: Database>(idle: &Idle<DB>, pool: &PoolInner<DB>) -> bool {
+    pool.idle_timeout()
         .map_or(false, |timeout| idle.idle_since.elapsed() > timeout)
 }
 
 async fn check_idle_conn<DB: Database>(
     mut conn: Floating<DB, Idle<DB>>,
-    options: &PoolOptions<DB>,
+    pool: &PoolInner<DB>,
 ) -> Result<Floating<DB, Live<DB>>, DecrementSizeGuard<DB>> {
+    let options = &pool.options;
+
     // If the connection we pulled has expired, close the connection and
     // immediately create a new connection
-    if is_beyond_max_lifetime(&conn, options) {
-        return Err(conn.close().await);
+    if is_beyond_max_lifetime(&conn, pool) {
+        return Err(conn.close(CloseReason::MaxLifetime).await);
+    }
+
+    if pool.is_stale(&conn) {
+        return Err(conn.close(CloseReason::Recycled).await);
     }
 
     if options.test_before_acquire {
@@ -366,7 +651,7 @@ async fn check_idle_conn<DB: Database>(
             // the error itself here isn't necessarily unexpected so WARN is too strong
             log::info!("ping on idle connection returned error: {}", e);
             // connection is broken so don't try to close nicely
-            return Err(conn.close_hard().await);
+            return Err(conn.close_hard(CloseReason::PingFailed).await);
         }
     }
 
@@ -375,13 +660,13 @@ async fn check_idle_conn<DB: Database>(
         match test(&mut conn.live.raw, meta).await {
             Ok(false) => {
                 // connection was rejected by user-defined hook, close nicely
-                return Err(conn.close().await);
+                return Err(conn.close(CloseReason::BeforeAcquire).await);
             }
 
             Err(error) => {
                 log::warn!("error from `before_acquire`: {}", error);
                 // connection is broken so don't try to close nicely
-                return Err(conn.close_hard().await);
+                return Err(conn.close_hard(CloseReason::BeforeAcquire).await);
             }
 
             Ok(true) => {}
@@ -395,35 +680,41 @@ async fn check_idle_conn<DB: Database>(
 fn spawn_maintenance_tasks<DB: Database>(pool: &Arc<PoolInner<DB>>) {
     let pool = Arc::clone(&pool);
 
-    let period = match (pool.options.max_lifetime, pool.options.idle_timeout) {
-        (Some(it), None) | (None, Some(it)) => it,
-
-        (Some(a), Some(b)) => cmp::min(a, b),
-
-        (None, None) => {
-            if pool.options.min_connections > 0 {
-                sqlx_rt::spawn(async move {
-                    pool.min_connections_maintenance(None).await;
-                });
-            }
-
-            return;
-        }
-    };
-
     sqlx_rt::spawn(async move {
         // Immediately cancel this task if the pool is closed.
         let _ = pool
             .close_event()
             .do_until(async {
                 while !pool.is_closed() {
+                    // Listen before reading the settings so we can't miss a change.
+                    let settings_changed = pool.on_settings_changed.listen();
+
+                    let period = match (pool.max_lifetime(), pool.idle_timeout()) {
+                        (Some(it), None) | (None, Some(it)) => it,
+
+                        (Some(a), Some(b)) => cmp::min(a, b),
+
+                        (None, None) => {
+                            // Nothing to reap, so just maintain `min_connections`
+                            // until the settings change.
+                            pool.min_connections_maintenance(None).await;
+                            settings_changed.await;
+                            continue;
+                        }
+                    };
+
                     let next_run = Instant::now() + period;
 
                     pool.min_connections_maintenance(Some(next_run)).await;
 
                     if let Some(duration) = next_run.checked_duration_since(Instant::now()) {
                         // `async-std` doesn't have a `sleep_until()`
-                        sqlx_rt::sleep(duration).await;
+                        let sleep = Box::pin(sqlx_rt::sleep(duration));
+
+                        // Wake early if the settings change as the period may have shrunk.
+                        if let Either::Right(_) = future::select(sleep, settings_changed).await {
+                            continue;
+                        }
                     } else {
                         sqlx_rt::yield_now().await;
                     }
@@ -438,26 +729,73 @@ fn spawn_maintenance_tasks<DB: Database>(pool: &Arc<PoolInner<DB>>) {
     });
 }
 
+fn spawn_leak_detector<DB: Database>(pool: &Arc<PoolInner<DB>>, threshold: Duration) {
+    let pool = Arc::clone(pool);
+
+    // Check often enough that a leak is reported reasonably soon after crossing the threshold.
+    let period = cmp::min(threshold / 2, Duration::from_secs(1));
+
+    sqlx_rt::spawn(async move {
+        let _ = pool
+            .close_event()
+            .do_until(async {
+                loop {
+                    sqlx_rt::sleep(period).await;
+
+                    for leaked in pool.checkouts.take_leaked(threshold) {
+                        log::warn!(
+                            "connection has been checked out of the pool for {:?}, \
+                             longer than the leak detection threshold of {:?}; \
+                             acquired at:\n{}",
+                            leaked.age(),
+                            threshold,
+                            leaked
+                                .backtrace()
+                                .map_or_else(String::new, ToString::to_string),
+                        );
+
+                        pool.metrics.connection_leaked(&leaked);
+                    }
+                }
+            })
+            .await;
+    });
+}
+
 async fn do_reap<DB: Database>(pool: &Arc<PoolInner<DB>>) {
     // reap at most the current size minus the minimum idle
-    let max_reaped = pool.size().saturating_sub(pool.options.min_connections);
+    let max_reaped = pool.size().saturating_sub(pool.min_connections());
+
+    let reap_reason = |conn: &Floating<DB, Idle<DB>>| {
+        if pool.is_stale(conn) {
+            Some(CloseReason::Recycled)
+        } else if is_beyond_idle_timeout(conn, pool) {
+            Some(CloseReason::IdleTimeout)
+        } else if is_beyond_max_lifetime(conn, pool) {
+            Some(CloseReason::MaxLifetime)
+        } else {
+            None
+        }
+    };
 
     // collect connections to reap
-    let (reap, keep) = (0..max_reaped)
+    let conns = (0..max_reaped)
         // only connections waiting in the queue
         .filter_map(|_| pool.try_acquire())
-        .partition::<Vec<_>, _>(|conn| {
-            is_beyond_idle_timeout(conn, &pool.options)
-                || is_beyond_max_lifetime(conn, &pool.options)
-        });
+        .collect::<Vec<_>>();
 
-    for conn in keep {
-        // return valid connections to the pool first
-        pool.release(conn.into_live());
+    let mut reap = Vec::new();
+
+    for conn in conns {
+        match reap_reason(&conn) {
+            Some(reason) => reap.push((conn, reason)),
+            // return valid connections to the pool first
+            None => pool.release(conn.into_live()),
+        }
     }
 
-    for conn in reap {
-        let _ = conn.close().await;
+    for (conn, reason) in reap {
+        let _ = conn.close(reason).await;
     }
 }
 
@@ -486,7 +824,7 @@ impl<DB: Database> DecrementSizeGuard<DB> {
     }
 
     /// Release the semaphore permit without decreasing the pool size.
-    fn release_permit(self) {
+    pub fn release_permit(self) {
         self.pool.semaphore.release(1);
         self.cancel();
     }
@@ -506,3 +844,67 @@ impl<DB: Database> Drop for DecrementSizeGuard<DB> {
         }
     }
 }
+
+/// Balances `PoolMetrics::acquire_started()`, reporting the acquire as cancelled if the
+/// future is dropped before it completes.
+struct AcquireMetricsGuard<'a> {
+    metrics: &'a PoolMetrics,
+    started_at: Instant,
+    finished: bool,
+}
+
+impl AcquireMetricsGuard<'_> {
+    fn finish<T>(mut self, res: &Result<T, Error>) {
+        let wait = self.started_at.elapsed();
+
+        match res {
+            Ok(_) => self.metrics.acquire_finished(wait),
+            Err(Error::PoolTimedOut) => self.metrics.acquire_timed_out(wait),
+            Err(_) => self.metrics.acquire_failed(wait),
+        }
+
+        self.finished = true;
+    }
+}
+
+impl Drop for AcquireMetricsGuard<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.metrics.acquire_cancelled(self.started_at.elapsed());
+        }
+    }
+}
+
+/// An `Option<Duration>` that can be changed while the pool is running.
+///
+/// Stored as nanoseconds, with `u64::MAX` representing `None`.
+/// Durations that don't fit (over 584 years) are clamped.
+struct AtomicOptionDuration(AtomicU64);
+
+impl AtomicOptionDuration {
+    const NONE: u64 = u64::MAX;
+
+    fn new(duration: Option<Duration>) -> Self {
+        Self(AtomicU64::new(Self::encode(duration)))
+    }
+
+    fn load(&self) -> Option<Duration> {
+        match self.0.load(Ordering::Acquire) {
+            Self::NONE => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        }
+    }
+
+    fn store(&self, duration: Option<Duration>) {
+        self.0.store(Self::encode(duration), Ordering::Release);
+    }
+
+    fn encode(duration: Option<Duration>) -> u64 {
+        duration.map_or(Self::NONE, |duration| {
+            cmp::min(
+                u64::try_from(duration.as_nanos()).unwrap_or(Self::NONE - 1),
+                Self::NONE - 1,
+            )
+        })
+    }
+}
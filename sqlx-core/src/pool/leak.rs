@@ -0,0 +1,167 @@
+//! Tracking of checked-out connections for [`Pool::checked_out()`][crate::pool::Pool::checked_out]
+//! and leak detection.
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// A connection currently checked out of a [`Pool`][crate::pool::Pool].
+///
+/// Returned by [`Pool::checked_out()`][crate::pool::Pool::checked_out] and passed to
+/// [`PoolMetricsObserver::connection_leaked()`][crate::pool::PoolMetricsObserver::connection_leaked].
+#[derive(Clone)]
+pub struct CheckedOutConnection {
+    id: u64,
+    acquired_at: Instant,
+    backtrace: Option<Arc<Backtrace>>,
+}
+
+impl CheckedOutConnection {
+    /// An identifier for this checkout, unique within its pool.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// How long the connection has been checked out.
+    pub fn age(&self) -> Duration {
+        self.acquired_at.elapsed()
+    }
+
+    /// The backtrace of the call that acquired the connection.
+    ///
+    /// This is `None` if the backtrace couldn't be captured on this platform.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_deref()
+    }
+}
+
+impl Debug for CheckedOutConnection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CheckedOutConnection")
+            .field("id", &self.id)
+            .field("age", &self.age())
+            .field("has_backtrace", &self.backtrace.is_some())
+            .finish()
+    }
+}
+
+struct Checkout {
+    info: CheckedOutConnection,
+    reported: bool,
+}
+
+/// The ID given to checkouts when tracking is disabled.
+const UNTRACKED: u64 = u64::MAX;
+
+/// The set of connections currently checked out of a pool.
+///
+/// If tracking is disabled, nothing is recorded so that acquire and release don't take a
+/// pool-global lock.
+pub(crate) struct CheckoutRegistry {
+    enabled: bool,
+    next_id: AtomicU64,
+    checkouts: Mutex<HashMap<u64, Checkout>>,
+}
+
+impl CheckoutRegistry {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            next_id: AtomicU64::new(0),
+            checkouts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record a new checkout, returning its ID.
+    pub(crate) fn register(&self) -> u64 {
+        if !self.enabled {
+            return UNTRACKED;
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        let backtrace = Backtrace::force_capture();
+
+        let info = CheckedOutConnection {
+            id,
+            acquired_at: Instant::now(),
+            backtrace: (backtrace.status() == BacktraceStatus::Captured)
+                .then(|| Arc::new(backtrace)),
+        };
+
+        self.lock().insert(
+            id,
+            Checkout {
+                info,
+                reported: false,
+            },
+        );
+
+        id
+    }
+
+    pub(crate) fn unregister(&self, id: u64) {
+        if id != UNTRACKED {
+            self.lock().remove(&id);
+        }
+    }
+
+    /// All current checkouts, oldest first.
+    pub(crate) fn snapshot(&self) -> Vec<CheckedOutConnection> {
+        let mut checkouts: Vec<_> = self
+            .lock()
+            .values()
+            .map(|checkout| checkout.info.clone())
+            .collect();
+
+        checkouts.sort_by_key(|checkout| (checkout.acquired_at, checkout.id));
+        checkouts
+    }
+
+    /// Checkouts older than `threshold` which haven't already been reported.
+    pub(crate) fn take_leaked(&self, threshold: Duration) -> Vec<CheckedOutConnection> {
+        self.lock()
+            .values_mut()
+            .filter(|checkout| !checkout.reported && checkout.info.age() >= threshold)
+            .map(|checkout| {
+                checkout.reported = true;
+                checkout.info.clone()
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<u64, Checkout>> {
+        // The map is always left in a consistent state so it's fine to ignore poisoning.
+        self.checkouts
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[test]
+fn test_checkout_registry() {
+    let registry = CheckoutRegistry::new(true);
+
+    let first = registry.register();
+    let second = registry.register();
+
+    let checked_out = registry.snapshot();
+    assert_eq!(checked_out.len(), 2);
+    assert_eq!(checked_out[0].id(), first);
+    assert!(checked_out[1].backtrace().is_some());
+
+    assert_eq!(registry.take_leaked(Duration::ZERO).len(), 2);
+    // Each checkout is only reported once.
+    assert!(registry.take_leaked(Duration::ZERO).is_empty());
+
+    registry.unregister(second);
+    assert_eq!(registry.snapshot().len(), 1);
+
+    let disabled = CheckoutRegistry::new(false);
+
+    let id = disabled.register();
+    assert!(disabled.snapshot().is_empty());
+    disabled.unregister(id);
+}
@@ -0,0 +1,537 @@
+//! Instrumentation for [`Pool`][crate::pool::Pool].
+//!
+//! The pool always maintains a cheap set of atomic counters and an acquire latency histogram
+//! which can be read at any time with [`Pool::metrics()`][crate::pool::Pool::metrics].
+//!
+//! For finer-grained instrumentation, such as pushing events into a tracing system, an
+//! implementation of [`PoolMetricsObserver`] can be set with
+//! [`PoolOptions::metrics_observer()`][crate::pool::PoolOptions::metrics_observer].
+use std::fmt::{self, Debug, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::pool::{CheckedOutConnection, PoolConnectionMetadata};
+
+/// Upper bounds of the buckets in the acquire latency histogram, in milliseconds.
+///
+/// Observations greater than the last bound are only counted in the implicit `+Inf` bucket,
+/// i.e. [`AcquireWaitHistogram::count`].
+const WAIT_BUCKETS_MS: [u64; 14] = [
+    1, 2, 5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000,
+];
+
+/// Callbacks invoked by [`Pool`][crate::pool::Pool] as connections are acquired, opened,
+/// released and closed.
+///
+/// All methods have empty default implementations so you only need to override the events
+/// you're interested in.
+///
+/// These are called synchronously from within the pool, sometimes while it's in the middle of
+/// handing out a connection, so implementations should return quickly and must not block.
+/// Updating atomics or pushing into a channel is fine; performing I/O is not.
+///
+/// Only calls to [`Pool::acquire()`][crate::pool::Pool::acquire] (and the APIs built on it, such
+/// as using `&Pool` as an `Executor`) are observed as acquires.
+/// [`Pool::try_acquire()`][crate::pool::Pool::try_acquire] never waits and so is not reported.
+pub trait PoolMetricsObserver: Send + Sync + 'static {
+    /// A task has started waiting for a connection.
+    fn acquire_started(&self) {}
+
+    /// A task has been handed a connection after waiting for `wait`.
+    fn acquire_finished(&self, wait: Duration) {
+        let _ = wait;
+    }
+
+    /// A task gave up waiting for a connection after
+    /// [`acquire_timeout`][crate::pool::PoolOptions::acquire_timeout] elapsed.
+    fn acquire_timed_out(&self, wait: Duration) {
+        let _ = wait;
+    }
+
+    /// Acquiring a connection failed for a reason other than timing out, such as the pool
+    /// being closed or an error while opening a new connection.
+    fn acquire_failed(&self, wait: Duration) {
+        let _ = wait;
+    }
+
+    /// A task stopped waiting for a connection because the `acquire()` future was dropped,
+    /// e.g. by a timeout or `select!` on the caller's side.
+    fn acquire_cancelled(&self, wait: Duration) {
+        let _ = wait;
+    }
+
+    /// A new connection to the database was successfully established.
+    fn connection_opened(&self) {}
+
+    /// A connection was closed by the pool.
+    fn connection_closed(&self, reason: CloseReason) {
+        let _ = reason;
+    }
+
+    /// A checked-out connection was returned to the idle queue.
+    fn connection_released(&self, meta: PoolConnectionMetadata) {
+        let _ = meta;
+    }
+
+    /// A connection has been checked out for longer than
+    /// [`leak_detection_threshold`][crate::pool::PoolOptions::leak_detection_threshold].
+    ///
+    /// This is reported at most once per checkout.
+    fn connection_leaked(&self, checkout: &CheckedOutConnection) {
+        let _ = checkout;
+    }
+}
+
+/// Allows sharing one observer between several pools, or keeping a handle to it for yourself.
+impl<T: PoolMetricsObserver + ?Sized> PoolMetricsObserver for Arc<T> {
+    fn acquire_started(&self) {
+        (**self).acquire_started()
+    }
+
+    fn acquire_finished(&self, wait: Duration) {
+        (**self).acquire_finished(wait)
+    }
+
+    fn acquire_timed_out(&self, wait: Duration) {
+        (**self).acquire_timed_out(wait)
+    }
+
+    fn acquire_failed(&self, wait: Duration) {
+        (**self).acquire_failed(wait)
+    }
+
+    fn acquire_cancelled(&self, wait: Duration) {
+        (**self).acquire_cancelled(wait)
+    }
+
+    fn connection_opened(&self) {
+        (**self).connection_opened()
+    }
+
+    fn connection_closed(&self, reason: CloseReason) {
+        (**self).connection_closed(reason)
+    }
+
+    fn connection_released(&self, meta: PoolConnectionMetadata) {
+        (**self).connection_released(meta)
+    }
+
+    fn connection_leaked(&self, checkout: &CheckedOutConnection) {
+        (**self).connection_leaked(checkout)
+    }
+}
+
+/// The reason a connection was closed by the pool.
+///
+/// Passed to [`PoolMetricsObserver::connection_closed()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CloseReason {
+    /// The connection was older than [`max_lifetime`][crate::pool::PoolOptions::max_lifetime].
+    MaxLifetime,
+
+    /// The connection sat in the idle queue longer than
+    /// [`idle_timeout`][crate::pool::PoolOptions::idle_timeout].
+    IdleTimeout,
+
+    /// The connection failed the liveness check performed by
+    /// [`test_before_acquire`][crate::pool::PoolOptions::test_before_acquire]
+    /// or when it was being returned to the pool.
+    PingFailed,
+
+    /// [`after_connect`][crate::pool::PoolOptions::after_connect] returned an error.
+    AfterConnect,
+
+    /// [`before_acquire`][crate::pool::PoolOptions::before_acquire] returned `Ok(false)`
+    /// or an error.
+    BeforeAcquire,
+
+    /// [`after_release`][crate::pool::PoolOptions::after_release] returned `Ok(false)`
+    /// or an error.
+    AfterRelease,
+
+    /// The pool was closed.
+    PoolClosed,
+
+    /// The connection was surplus to requirements after
+    /// [`Pool::set_max_connections()`][crate::pool::Pool::set_max_connections] lowered the limit.
+    PoolResized,
+
+    /// The connection was opened before the last call to
+    /// [`Pool::recycle_connections()`][crate::pool::Pool::recycle_connections].
+    Recycled,
+
+    /// Resetting the session state with
+    /// [`reset_on_release`][crate::pool::PoolOptions::reset_on_release] failed.
+    ResetFailed,
+}
+
+impl CloseReason {
+    const ALL: [CloseReason; 10] = [
+        CloseReason::MaxLifetime,
+        CloseReason::IdleTimeout,
+        CloseReason::PingFailed,
+        CloseReason::AfterConnect,
+        CloseReason::BeforeAcquire,
+        CloseReason::AfterRelease,
+        CloseReason::PoolClosed,
+        CloseReason::PoolResized,
+        CloseReason::Recycled,
+        CloseReason::ResetFailed,
+    ];
+
+    fn index(self) -> usize {
+        match self {
+            CloseReason::MaxLifetime => 0,
+            CloseReason::IdleTimeout => 1,
+            CloseReason::PingFailed => 2,
+            CloseReason::AfterConnect => 3,
+            CloseReason::BeforeAcquire => 4,
+            CloseReason::AfterRelease => 5,
+            CloseReason::PoolClosed => 6,
+            CloseReason::PoolResized => 7,
+            CloseReason::Recycled => 8,
+            CloseReason::ResetFailed => 9,
+        }
+    }
+
+    /// A short, stable, `snake_case` name for this reason, suitable for use as a metric label.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CloseReason::MaxLifetime => "max_lifetime",
+            CloseReason::IdleTimeout => "idle_timeout",
+            CloseReason::PingFailed => "ping_failed",
+            CloseReason::AfterConnect => "after_connect",
+            CloseReason::BeforeAcquire => "before_acquire",
+            CloseReason::AfterRelease => "after_release",
+            CloseReason::PoolClosed => "pool_closed",
+            CloseReason::PoolResized => "pool_resized",
+            CloseReason::Recycled => "recycled",
+            CloseReason::ResetFailed => "reset_failed",
+        }
+    }
+}
+
+impl fmt::Display for CloseReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A point-in-time copy of the metrics maintained by a [`Pool`][crate::pool::Pool].
+///
+/// Returned by [`Pool::metrics()`][crate::pool::Pool::metrics].
+///
+/// All counters are monotonic over the lifetime of the pool, so they map directly onto
+/// Prometheus counters. Because each counter is read individually, a snapshot taken while the
+/// pool is busy may be very slightly inconsistent (e.g. `acquire_started` may have been read
+/// before a concurrent `acquire_finished` was incremented).
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct PoolMetricsSnapshot {
+    /// The number of connections currently open, including idle connections.
+    pub size: u32,
+
+    /// The number of connections currently in the idle queue.
+    pub num_idle: usize,
+
+    /// The number of calls to `acquire()` that have started.
+    pub acquire_started: u64,
+
+    /// The number of calls to `acquire()` that returned a connection.
+    pub acquire_finished: u64,
+
+    /// The number of calls to `acquire()` that returned [`Error::PoolTimedOut`][crate::error::Error::PoolTimedOut].
+    pub acquire_timed_out: u64,
+
+    /// The number of calls to `acquire()` that failed for any other reason.
+    pub acquire_failed: u64,
+
+    /// The number of calls to `acquire()` that were dropped before they completed.
+    pub acquire_cancelled: u64,
+
+    /// The number of connections opened by the pool.
+    pub connections_opened: u64,
+
+    /// The number of connections returned to the idle queue after being checked out.
+    pub connections_released: u64,
+
+    /// The number of checkouts that exceeded
+    /// [`leak_detection_threshold`][crate::pool::PoolOptions::leak_detection_threshold].
+    pub connections_leaked: u64,
+
+    /// Distribution of time spent waiting in successful calls to `acquire()`.
+    pub acquire_wait: AcquireWaitHistogram,
+
+    connections_closed: [u64; CloseReason::ALL.len()],
+}
+
+impl PoolMetricsSnapshot {
+    /// The number of calls to `acquire()` currently waiting for a connection.
+    pub fn acquire_waiting(&self) -> u64 {
+        self.acquire_started.saturating_sub(
+            self.acquire_finished
+                + self.acquire_timed_out
+                + self.acquire_failed
+                + self.acquire_cancelled,
+        )
+    }
+
+    /// The total number of connections closed by the pool, for any reason.
+    pub fn connections_closed(&self) -> u64 {
+        self.connections_closed.iter().sum()
+    }
+
+    /// The number of connections closed by the pool for the given reason.
+    pub fn connections_closed_by(&self, reason: CloseReason) -> u64 {
+        self.connections_closed[reason.index()]
+    }
+
+    /// Iterate over the number of connections closed for every [`CloseReason`].
+    pub fn connections_closed_by_reason(&self) -> impl Iterator<Item = (CloseReason, u64)> + '_ {
+        CloseReason::ALL
+            .iter()
+            .map(move |reason| (*reason, self.connections_closed_by(*reason)))
+    }
+}
+
+/// A histogram of time spent waiting in `Pool::acquire()`.
+///
+/// The layout follows the Prometheus convention so it can be exported as-is:
+/// buckets are cumulative and there is an implicit `+Inf` bucket equal to [`count`][Self::count].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct AcquireWaitHistogram {
+    /// Pairs of `(upper_bound, cumulative_count)`, in ascending order of bound.
+    ///
+    /// `cumulative_count` is the number of observations less than or equal to `upper_bound`.
+    pub buckets: Vec<(Duration, u64)>,
+
+    /// The total number of observations.
+    pub count: u64,
+
+    /// The sum of all observations.
+    pub sum: Duration,
+}
+
+/// The internal, always-on counters backing [`PoolMetricsSnapshot`].
+///
+/// This also forwards every event to the user's [`PoolMetricsObserver`] if one was set.
+pub(crate) struct PoolMetrics {
+    observer: Option<Arc<dyn PoolMetricsObserver>>,
+    acquire_started: AtomicU64,
+    acquire_finished: AtomicU64,
+    acquire_timed_out: AtomicU64,
+    acquire_failed: AtomicU64,
+    acquire_cancelled: AtomicU64,
+    connections_opened: AtomicU64,
+    connections_released: AtomicU64,
+    connections_leaked: AtomicU64,
+    connections_closed: [AtomicU64; CloseReason::ALL.len()],
+    wait_buckets: [AtomicU64; WAIT_BUCKETS_MS.len()],
+    wait_count: AtomicU64,
+    wait_sum_micros: AtomicU64,
+}
+
+impl PoolMetrics {
+    pub(crate) fn new(observer: Option<Arc<dyn PoolMetricsObserver>>) -> Self {
+        Self {
+            observer,
+            acquire_started: AtomicU64::new(0),
+            acquire_finished: AtomicU64::new(0),
+            acquire_timed_out: AtomicU64::new(0),
+            acquire_failed: AtomicU64::new(0),
+            acquire_cancelled: AtomicU64::new(0),
+            connections_opened: AtomicU64::new(0),
+            connections_released: AtomicU64::new(0),
+            connections_leaked: AtomicU64::new(0),
+            connections_closed: Default::default(),
+            wait_buckets: Default::default(),
+            wait_count: AtomicU64::new(0),
+            wait_sum_micros: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn snapshot(&self, size: u32, num_idle: usize) -> PoolMetricsSnapshot {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+
+        // Convert the per-bucket counts into cumulative counts.
+        let mut cumulative = 0;
+        let buckets = WAIT_BUCKETS_MS
+            .iter()
+            .zip(&self.wait_buckets)
+            .map(|(bound, count)| {
+                cumulative += load(count);
+                (Duration::from_millis(*bound), cumulative)
+            })
+            .collect();
+
+        let mut connections_closed = [0; CloseReason::ALL.len()];
+
+        for (dest, src) in connections_closed.iter_mut().zip(&self.connections_closed) {
+            *dest = load(src);
+        }
+
+        PoolMetricsSnapshot {
+            size,
+            num_idle,
+            acquire_started: load(&self.acquire_started),
+            acquire_finished: load(&self.acquire_finished),
+            acquire_timed_out: load(&self.acquire_timed_out),
+            acquire_failed: load(&self.acquire_failed),
+            acquire_cancelled: load(&self.acquire_cancelled),
+            connections_opened: load(&self.connections_opened),
+            connections_released: load(&self.connections_released),
+            connections_leaked: load(&self.connections_leaked),
+            acquire_wait: AcquireWaitHistogram {
+                buckets,
+                count: load(&self.wait_count),
+                sum: Duration::from_micros(load(&self.wait_sum_micros)),
+            },
+            connections_closed,
+        }
+    }
+
+    fn record_wait(&self, wait: Duration) {
+        let millis = wait.as_millis();
+
+        // Observations beyond the last bucket are only counted in `+Inf`.
+        if let Some(i) = WAIT_BUCKETS_MS
+            .iter()
+            .position(|bound| millis <= u128::from(*bound))
+        {
+            self.wait_buckets[i].fetch_add(1, Ordering::Relaxed);
+        }
+
+        self.wait_count.fetch_add(1, Ordering::Relaxed);
+        self.wait_sum_micros.fetch_add(
+            u64::try_from(wait.as_micros()).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+}
+
+impl PoolMetricsObserver for PoolMetrics {
+    fn acquire_started(&self) {
+        self.acquire_started.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(observer) = &self.observer {
+            observer.acquire_started();
+        }
+    }
+
+    fn acquire_finished(&self, wait: Duration) {
+        self.record_wait(wait);
+        self.acquire_finished.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(observer) = &self.observer {
+            observer.acquire_finished(wait);
+        }
+    }
+
+    fn acquire_timed_out(&self, wait: Duration) {
+        self.acquire_timed_out.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(observer) = &self.observer {
+            observer.acquire_timed_out(wait);
+        }
+    }
+
+    fn acquire_failed(&self, wait: Duration) {
+        self.acquire_failed.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(observer) = &self.observer {
+            observer.acquire_failed(wait);
+        }
+    }
+
+    fn acquire_cancelled(&self, wait: Duration) {
+        self.acquire_cancelled.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(observer) = &self.observer {
+            observer.acquire_cancelled(wait);
+        }
+    }
+
+    fn connection_opened(&self) {
+        self.connections_opened.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(observer) = &self.observer {
+            observer.connection_opened();
+        }
+    }
+
+    fn connection_closed(&self, reason: CloseReason) {
+        self.connections_closed[reason.index()].fetch_add(1, Ordering::Relaxed);
+
+        if let Some(observer) = &self.observer {
+            observer.connection_closed(reason);
+        }
+    }
+
+    fn connection_released(&self, meta: PoolConnectionMetadata) {
+        self.connections_released.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(observer) = &self.observer {
+            observer.connection_released(meta);
+        }
+    }
+
+    fn connection_leaked(&self, checkout: &CheckedOutConnection) {
+        self.connections_leaked.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(observer) = &self.observer {
+            observer.connection_leaked(checkout);
+        }
+    }
+}
+
+impl Debug for PoolMetrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoolMetrics")
+            .field("has_observer", &self.observer.is_some())
+            .finish()
+    }
+}
+
+#[test]
+fn test_acquire_wait_histogram() {
+    let metrics = PoolMetrics::new(None);
+
+    metrics.acquire_finished(Duration::from_micros(500));
+    metrics.acquire_finished(Duration::from_millis(1));
+    metrics.acquire_finished(Duration::from_millis(7));
+    metrics.acquire_finished(Duration::from_secs(60));
+
+    let snapshot = metrics.snapshot(0, 0);
+    let wait = &snapshot.acquire_wait;
+
+    assert_eq!(wait.count, 4);
+    assert_eq!(snapshot.acquire_finished, 4);
+    assert_eq!(wait.buckets[0], (Duration::from_millis(1), 2));
+    assert_eq!(wait.buckets[2], (Duration::from_millis(5), 2));
+    assert_eq!(wait.buckets[3], (Duration::from_millis(10), 3));
+    // The 60 second observation only lands in the implicit `+Inf` bucket.
+    assert_eq!(wait.buckets.last().unwrap().1, 3);
+    assert_eq!(
+        wait.sum,
+        Duration::from_micros(500) + Duration::from_millis(8) + Duration::from_secs(60)
+    );
+}
+
+#[test]
+fn test_connections_closed_by_reason() {
+    let metrics = PoolMetrics::new(None);
+
+    metrics.connection_closed(CloseReason::IdleTimeout);
+    metrics.connection_closed(CloseReason::IdleTimeout);
+    metrics.connection_closed(CloseReason::PingFailed);
+
+    let snapshot = metrics.snapshot(0, 0);
+
+    assert_eq!(snapshot.connections_closed(), 3);
+    assert_eq!(snapshot.connections_closed_by(CloseReason::IdleTimeout), 2);
+    assert_eq!(snapshot.connections_closed_by(CloseReason::PingFailed), 1);
+    assert_eq!(snapshot.connections_closed_by(CloseReason::MaxLifetime), 0);
+}
@@ -85,13 +85,22 @@ mod executor;
 #[macro_use]
 mod maybe;
 
+mod breaker;
 mod connection;
 mod inner;
+mod leak;
+mod metrics;
 mod options;
+mod routed;
 
 pub use self::connection::PoolConnection;
+pub use self::leak::CheckedOutConnection;
 pub(crate) use self::maybe::MaybePoolConnection;
+pub use self::metrics::{
+    AcquireWaitHistogram, CloseReason, PoolMetricsObserver, PoolMetricsSnapshot,
+};
 pub use self::options::{PoolConnectionMetadata, PoolOptions};
+pub use self::routed::{ReplicaSelection, RoutedPool, RoutedPoolOptions};
 
 /// An asynchronous pool of SQLx database connections.
 ///
@@ -489,12 +498,93 @@ impl<DB: Database> Pool<DB> {
         self.0.num_idle()
     }
 
+    /// Take a snapshot of the metrics the pool maintains, such as the number of connections
+    /// opened and closed and how long tasks have spent waiting in [`Pool::acquire`].
+    ///
+    /// This is cheap to call, and is intended to be polled periodically for export to a metrics
+    /// system. To be notified of every event as it happens instead, see
+    /// [`PoolOptions::metrics_observer`].
+    pub fn metrics(&self) -> PoolMetricsSnapshot {
+        self.0.metrics.snapshot(self.0.size(), self.0.num_idle())
+    }
+
+    /// List the connections currently checked out of this pool, oldest first.
+    ///
+    /// This is useful for finding out who is holding on to connections when the pool is
+    /// starved. Checkouts are only tracked if [`PoolOptions::leak_detection_threshold`] is set;
+    /// otherwise this is always empty.
+    pub fn checked_out(&self) -> Vec<CheckedOutConnection> {
+        self.0.checkouts.snapshot()
+    }
+
+    /// Change the maximum number of connections this pool may maintain.
+    ///
+    /// If the limit is raised, tasks waiting in [`Pool::acquire`] are woken immediately and
+    /// may open new connections.
+    ///
+    /// If the limit is lowered, surplus idle connections are closed right away. Connections that
+    /// are checked out are never interrupted; the pool waits for them to be returned and then
+    /// closes them instead of putting them back into the idle queue, until it is within the new
+    /// limit. In the meantime, no new connections will be opened in excess of the new limit.
+    ///
+    /// See [`PoolOptions::max_connections`] for details.
+    pub fn set_max_connections(&self, max: u32) {
+        self.0.set_max_connections(max)
+    }
+
+    /// Change the minimum number of connections this pool should maintain.
+    ///
+    /// If the minimum is raised, new connections are opened in the background.
+    ///
+    /// See [`PoolOptions::min_connections`] for details.
+    pub fn set_min_connections(&self, min: u32) {
+        self.0.set_min_connections(min)
+    }
+
+    /// Change the maximum lifetime of individual connections.
+    ///
+    /// This applies to all connections, including those that are already open.
+    ///
+    /// See [`PoolOptions::max_lifetime`] for details.
+    pub fn set_max_lifetime(&self, lifetime: impl Into<Option<Duration>>) {
+        self.0.set_max_lifetime(lifetime.into())
+    }
+
+    /// Change the maximum idle duration for individual connections.
+    ///
+    /// This applies to all connections, including those that are already idle.
+    ///
+    /// See [`PoolOptions::idle_timeout`] for details.
+    pub fn set_idle_timeout(&self, timeout: impl Into<Option<Duration>>) {
+        self.0.set_idle_timeout(timeout.into())
+    }
+
+    /// Close every connection that is currently open once it is no longer in use, so that all
+    /// subsequent checkouts use newly opened connections.
+    ///
+    /// Idle connections are closed immediately and checked-out connections are closed when
+    /// they're returned, instead of going back into the idle queue. Connections are never
+    /// interrupted while in use.
+    ///
+    /// This is intended to be called when the credentials returned by the provider passed to
+    /// [`PoolOptions::connect_with_provider`] rotate, so that connections authenticated with the old
+    /// credentials are retired promptly, but can be used any time the pool should start fresh.
+    pub fn recycle_connections(&self) {
+        self.0.recycle_connections()
+    }
+
     /// Get the connection options for this pool
+    ///
+    /// If the pool was created with [`PoolOptions::connect_with_provider`], these are the
+    /// options used to open the first connection.
     pub fn connect_options(&self) -> &<DB::Connection as Connection>::Options {
         &self.0.connect_options
     }
 
     /// Get the options for this pool
+    ///
+    /// These are the options the pool was created with; changes made with
+    /// [`set_max_connections`][Self::set_max_connections] and the like are not reflected here.
     pub fn options(&self) -> &PoolOptions<DB> {
         &self.0.options
     }
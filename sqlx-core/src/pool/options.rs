@@ -2,7 +2,7 @@ use crate::connection::Connection;
 use crate::database::Database;
 use crate::error::Error;
 use crate::pool::inner::PoolInner;
-use crate::pool::Pool;
+use crate::pool::{Pool, PoolMetricsObserver};
 use futures_core::future::BoxFuture;
 use std::fmt::{self, Debug, Formatter};
 use std::sync::Arc;
@@ -41,7 +41,6 @@ use std::time::{Duration, Instant};
 /// parameter everywhere, and `Box` is in the prelude so it doesn't need to be manually imported,
 /// so having the closure return `Pin<Box<dyn Future>` directly is the path of least resistance from
 /// the perspectives of both API designer and consumer.
-#[derive(Clone)]
 pub struct PoolOptions<DB: Database> {
     pub(crate) test_before_acquire: bool,
     pub(crate) after_connect: Option<
@@ -74,11 +73,25 @@ pub struct PoolOptions<DB: Database> {
                 + Sync,
         >,
     >,
+    pub(crate) connect_options_provider: Option<
+        Arc<
+            dyn Fn() -> BoxFuture<'static, Result<<DB::Connection as Connection>::Options, Error>>
+                + 'static
+                + Send
+                + Sync,
+        >,
+    >,
+    pub(crate) metrics_observer: Option<Arc<dyn PoolMetricsObserver>>,
     pub(crate) max_connections: u32,
     pub(crate) acquire_timeout: Duration,
     pub(crate) min_connections: u32,
     pub(crate) max_lifetime: Option<Duration>,
     pub(crate) idle_timeout: Option<Duration>,
+    pub(crate) leak_detection_threshold: Option<Duration>,
+    pub(crate) reset_on_release: bool,
+    pub(crate) circuit_breaker_threshold: Option<u32>,
+    pub(crate) circuit_breaker_cooldown: Duration,
+    pub(crate) circuit_breaker_max_cooldown: Duration,
     pub(crate) fair: bool,
 }
 
@@ -98,6 +111,31 @@ pub struct PoolConnectionMetadata {
     pub idle_for: Duration,
 }
 
+// Manually implemented so that we don't require `DB: Clone`.
+impl<DB: Database> Clone for PoolOptions<DB> {
+    fn clone(&self) -> Self {
+        Self {
+            test_before_acquire: self.test_before_acquire,
+            after_connect: self.after_connect.clone(),
+            before_acquire: self.before_acquire.clone(),
+            after_release: self.after_release.clone(),
+            connect_options_provider: self.connect_options_provider.clone(),
+            metrics_observer: self.metrics_observer.clone(),
+            max_connections: self.max_connections,
+            acquire_timeout: self.acquire_timeout,
+            min_connections: self.min_connections,
+            max_lifetime: self.max_lifetime,
+            idle_timeout: self.idle_timeout,
+            leak_detection_threshold: self.leak_detection_threshold,
+            reset_on_release: self.reset_on_release,
+            circuit_breaker_threshold: self.circuit_breaker_threshold,
+            circuit_breaker_cooldown: self.circuit_breaker_cooldown,
+            circuit_breaker_max_cooldown: self.circuit_breaker_max_cooldown,
+            fair: self.fair,
+        }
+    }
+}
+
 impl<DB: Database> Default for PoolOptions<DB> {
     fn default() -> Self {
         Self::new()
@@ -117,6 +155,8 @@ impl<DB: Database> PoolOptions<DB> {
             after_connect: None,
             before_acquire: None,
             after_release: None,
+            connect_options_provider: None,
+            metrics_observer: None,
             test_before_acquire: true,
             // A production application will want to set a higher limit than this.
             max_connections: 10,
@@ -124,6 +164,11 @@ impl<DB: Database> PoolOptions<DB> {
             acquire_timeout: Duration::from_secs(30),
             idle_timeout: Some(Duration::from_secs(10 * 60)),
             max_lifetime: Some(Duration::from_secs(30 * 60)),
+            leak_detection_threshold: None,
+            reset_on_release: false,
+            circuit_breaker_threshold: None,
+            circuit_breaker_cooldown: Duration::from_secs(5),
+            circuit_breaker_max_cooldown: Duration::from_secs(60),
             fair: true,
         }
     }
@@ -212,6 +257,83 @@ impl<DB: Database> PoolOptions<DB> {
         self
     }
 
+    /// Warn about connections that have been checked out of the pool for longer than this.
+    ///
+    /// A task that holds on to a connection indefinitely, e.g. by keeping a `Transaction` alive
+    /// across an `.await` in a long-running loop, will eventually starve the pool and cause
+    /// every other task to fail with [`Error::PoolTimedOut`]. When this is set, such connections
+    /// are logged at `WARN` level along with the backtrace of the call that acquired them,
+    /// and reported to [`PoolMetricsObserver::connection_leaked()`].
+    ///
+    /// The connection is not closed or taken away from its holder; this is only a diagnostic.
+    /// Each checkout is reported at most once.
+    ///
+    /// Setting this captures a backtrace on every acquire, which is relatively expensive, so it
+    /// is disabled by default. The checkouts and their backtraces are also available from
+    /// [`Pool::checked_out()`].
+    pub fn leak_detection_threshold(mut self, threshold: impl Into<Option<Duration>>) -> Self {
+        self.leak_detection_threshold = threshold.into();
+        self
+    }
+
+    /// If true, the session state of a connection is reset with [`Connection::reset`] before
+    /// it's returned to the idle queue.
+    ///
+    /// Without this, anything a task changes about its session is seen by the next task to
+    /// check out the same connection: `SET` variables, temporary tables, `search_path`,
+    /// manually prepared statements, held advisory locks and so on.
+    ///
+    /// The reset takes the place of the liveness check normally performed on release. If it
+    /// fails, the connection is closed. This runs after [`after_release`][Self::after_release].
+    ///
+    /// Defaults to `false`.
+    pub fn reset_on_release(mut self, reset: bool) -> Self {
+        self.reset_on_release = reset;
+        self
+    }
+
+    /// Stop trying to open new connections after this many consecutive failures.
+    ///
+    /// When the database is down, every call to [`Pool::acquire()`] that needs a new connection
+    /// would otherwise keep retrying until [`acquire_timeout`][Self::acquire_timeout] elapses,
+    /// which can pile up a huge number of waiting tasks. With this set, once the threshold is
+    /// reached the pool "opens the circuit": attempts to open a connection fail immediately
+    /// with [`Error::PoolUnavailable`] for the [cooldown][Self::circuit_breaker_cooldown].
+    ///
+    /// After the cooldown, a single connection attempt is let through to probe the database.
+    /// If it succeeds, the pool goes back to normal. If it fails, the cooldown is doubled,
+    /// up to [`circuit_breaker_max_cooldown`][Self::circuit_breaker_max_cooldown].
+    /// Cooldowns are randomly shortened by up to half so that many clients don't all
+    /// reconnect at once.
+    ///
+    /// Idle connections can still be acquired while the circuit is open.
+    ///
+    /// Defaults to `None` (disabled).
+    pub fn circuit_breaker_threshold(mut self, threshold: impl Into<Option<u32>>) -> Self {
+        self.circuit_breaker_threshold = threshold.into();
+        self
+    }
+
+    /// How long the circuit breaker stays open before probing the database again.
+    ///
+    /// See [`circuit_breaker_threshold`][Self::circuit_breaker_threshold].
+    ///
+    /// Defaults to 5 seconds.
+    pub fn circuit_breaker_cooldown(mut self, cooldown: Duration) -> Self {
+        self.circuit_breaker_cooldown = cooldown;
+        self
+    }
+
+    /// The longest the circuit breaker will stay open after repeatedly failed probes.
+    ///
+    /// See [`circuit_breaker_threshold`][Self::circuit_breaker_threshold].
+    ///
+    /// Defaults to 60 seconds.
+    pub fn circuit_breaker_max_cooldown(mut self, max_cooldown: Duration) -> Self {
+        self.circuit_breaker_max_cooldown = max_cooldown;
+        self
+    }
+
     /// If true, the health of a connection will be verified by a call to [`Connection::ping`]
     /// before returning the connection.
     ///
@@ -400,6 +522,19 @@ impl<DB: Database> PoolOptions<DB> {
         self
     }
 
+    /// Set an observer to be notified of events in the pool, such as connections being
+    /// acquired, opened and closed.
+    ///
+    /// The pool always maintains a basic set of counters which can be read with
+    /// [`Pool::metrics()`]; this is only necessary if you want to be notified of each event
+    /// as it happens, e.g. to record them in your own metrics system.
+    ///
+    /// See [`PoolMetricsObserver`] for details.
+    pub fn metrics_observer(mut self, observer: impl PoolMetricsObserver) -> Self {
+        self.metrics_observer = Some(Arc::new(observer));
+        self
+    }
+
     /// Create a new pool from this `PoolOptions` and immediately open at least one connection.
     ///
     /// This ensures the configuration is correct.
@@ -428,20 +563,72 @@ impl<DB: Database> PoolOptions<DB> {
         // Don't take longer than `acquire_timeout` starting from when this is called.
         let deadline = Instant::now() + self.acquire_timeout;
 
-        let inner = PoolInner::new_arc(self, options);
+        Self::open_initial_connections(PoolInner::new_arc(self, options), deadline).await
+    }
 
-        if inner.options.min_connections > 0 {
-            // If the idle reaper is spawned then this will race with the call from that task
-            // and may not report any connection errors.
-            inner.try_min_connections(deadline).await?;
-        }
+    /// Create a new pool from this `PoolOptions` which asks `provider` for fresh
+    /// `ConnectOptions` every time it opens a new connection, and immediately open at least
+    /// one connection.
+    ///
+    /// This is useful when connecting with short-lived credentials, such as IAM authentication
+    /// tokens or dynamic secrets from Vault. Connections which are already open are not affected
+    /// when the credentials expire; to retire them promptly anyway, call
+    /// [`Pool::recycle_connections`] when the credentials are rotated.
+    ///
+    /// The provider is called every time the pool opens a new connection, within the deadline
+    /// set by [`acquire_timeout`][Self::acquire_timeout]. If it returns an error, that error is
+    /// returned from [`Pool::acquire`]. The options it returns first are also the ones returned
+    /// by [`Pool::connect_options`].
+    ///
+    /// Like [`connect_with`][Self::connect_with], this immediately opens at least one
+    /// connection, and the provider is called once for every connection opened.
+    ///
+    /// # Example
+    /// This example is written for Postgres but can be trivially adapted to other databases.
+    ///
+    /// ```no_run
+    /// # async fn f() -> Result<(), Box<dyn std::error::Error>> {
+    /// # async fn fetch_token() -> Result<String, sqlx::Error> { unimplemented!() }
+    /// use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+    ///
+    /// let pool = PgPoolOptions::new()
+    ///     .connect_with_provider(|| Box::pin(async move {
+    ///         let token = fetch_token().await?;
+    ///
+    ///         Ok(PgConnectOptions::new()
+    ///             .host("db.example.com")
+    ///             .username("app")
+    ///             .password(&token))
+    ///     }))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// For a discussion on why `Box::pin()` is required, see [the type-level docs][Self].
+    pub async fn connect_with_provider<F>(mut self, provider: F) -> Result<Pool<DB>, Error>
+    where
+        F: Fn() -> BoxFuture<'static, Result<<DB::Connection as Connection>::Options, Error>>
+            + 'static
+            + Send
+            + Sync,
+    {
+        // Don't take longer than `acquire_timeout` starting from when this is called.
+        let deadline = Instant::now() + self.acquire_timeout;
 
-        // If `min_connections` is nonzero then we'll likely just pull a connection
-        // from the idle queue here, but it should at least get tested first.
-        let conn = inner.acquire().await?;
-        inner.release(conn);
+        let options = sqlx_rt::timeout(self.acquire_timeout, provider())
+            .await
+            .map_err(|_| Error::PoolTimedOut)??;
 
-        Ok(Pool(inner))
+        self.connect_options_provider = Some(Arc::new(provider));
+
+        let inner = PoolInner::new_arc(self, options.clone());
+
+        // The options were needed to create the pool, so use them for the first connection
+        // rather than asking the provider again.
+        inner.set_provided_options(options);
+
+        Self::open_initial_connections(inner, deadline).await
     }
 
     /// Create a new pool from this `PoolOptions`, but don't open any connections right now.
@@ -467,6 +654,24 @@ impl<DB: Database> PoolOptions<DB> {
         // `min_connections` is guaranteed by the idle reaper now.
         Pool(PoolInner::new_arc(self, options))
     }
+
+    async fn open_initial_connections(
+        inner: Arc<PoolInner<DB>>,
+        deadline: Instant,
+    ) -> Result<Pool<DB>, Error> {
+        if inner.min_connections() > 0 {
+            // If the idle reaper is spawned then this will race with the call from that task
+            // and may not report any connection errors.
+            inner.try_min_connections(deadline).await?;
+        }
+
+        // If `min_connections` is nonzero then we'll likely just pull a connection
+        // from the idle queue here, but it should at least get tested first.
+        let conn = inner.acquire().await?;
+        inner.release(conn);
+
+        Ok(Pool(inner))
+    }
 }
 
 impl<DB: Database> Debug for PoolOptions<DB> {
@@ -477,7 +682,10 @@ impl<DB: Database> Debug for PoolOptions<DB> {
             .field("connect_timeout", &self.acquire_timeout)
             .field("max_lifetime", &self.max_lifetime)
             .field("idle_timeout", &self.idle_timeout)
+            .field("leak_detection_threshold", &self.leak_detection_threshold)
             .field("test_before_acquire", &self.test_before_acquire)
+            .field("reset_on_release", &self.reset_on_release)
+            .field("circuit_breaker_threshold", &self.circuit_breaker_threshold)
             .finish()
     }
 }
@@ -0,0 +1,512 @@
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use either::Either;
+use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
+use futures_util::TryStreamExt;
+
+use crate::acquire::Acquire;
+use crate::connection::Connection;
+use crate::database::{Database, HasStatement};
+use crate::describe::Describe;
+use crate::error::Error;
+use crate::executor::{Execute, Executor};
+use crate::pool::{MaybePoolConnection, Pool, PoolConnection, PoolOptions};
+use crate::transaction::Transaction;
+
+/// A pair of a primary [`Pool`] and zero or more read replica pools, which routes read-only
+/// queries to the replicas and everything else to the primary.
+///
+/// Create one with [`RoutedPool::new()`] from pools you've already built, or with
+/// [`RoutedPoolOptions`] to connect to all the servers using the same [`PoolOptions`].
+///
+/// ### Routing
+/// When `&RoutedPool` is used as an [`Executor`], the SQL of each query is inspected:
+///
+/// * Single statements beginning with `SELECT`, `VALUES`, `TABLE` or `SHOW` that don't lock rows
+///   (`FOR UPDATE`, `FOR SHARE`, etc.) or create tables (`SELECT ... INTO`) are sent to a replica.
+/// * Everything else, including anything this doesn't recognise, is sent to the primary.
+///
+/// Note that this cannot detect a `SELECT` calling a user-defined function that writes, and
+/// of course a query sent to a replica may not observe writes that were just made on the
+/// primary because of replication lag. Use [`.primary()`][Self::primary] directly when either
+/// of those matters.
+///
+/// [`begin()`][Self::begin], [`acquire()`][Self::acquire] and the [`Acquire`] impl always use
+/// the primary.
+///
+/// ### Replica Failover
+/// If acquiring a connection from a replica fails because a connection to it could not be
+/// opened, that replica is taken out of rotation for
+/// [`replica_retry_after`][RoutedPoolOptions::replica_retry_after] and the query is retried on
+/// the next replica. Once that time has elapsed it is given another chance, and stays in rotation
+/// once a connection is acquired from it again. If no replicas are usable, reads go to the primary.
+///
+/// Like `Pool`, `RoutedPool` is a cheap, reference-counted handle and can be cloned freely.
+pub struct RoutedPool<DB: Database>(Arc<RoutedPoolInner<DB>>);
+
+struct RoutedPoolInner<DB: Database> {
+    primary: Pool<DB>,
+    replicas: Vec<Replica<DB>>,
+    selection: ReplicaSelection,
+    retry_after: Duration,
+    next_replica: AtomicUsize,
+}
+
+struct Replica<DB: Database> {
+    pool: Pool<DB>,
+    down_until: Mutex<Option<Instant>>,
+}
+
+/// The strategy used by [`RoutedPool`] to choose a replica for each read.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReplicaSelection {
+    /// Cycle through the replicas in order.
+    RoundRobin,
+
+    /// Choose the replica with the fewest connections currently checked out.
+    LeastBusy,
+}
+
+/// Configuration options for [`RoutedPool`].
+pub struct RoutedPoolOptions<DB: Database> {
+    pool_options: PoolOptions<DB>,
+    selection: ReplicaSelection,
+    retry_after: Duration,
+}
+
+impl<DB: Database> Default for RoutedPoolOptions<DB> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<DB: Database> RoutedPoolOptions<DB> {
+    /// Returns a default configuration which uses [`PoolOptions::new()`] for every pool,
+    /// [`ReplicaSelection::RoundRobin`] and takes replicas out of rotation for 5 seconds when
+    /// connecting to them fails.
+    pub fn new() -> Self {
+        Self {
+            pool_options: PoolOptions::new(),
+            selection: ReplicaSelection::RoundRobin,
+            retry_after: Duration::from_secs(5),
+        }
+    }
+
+    /// Set the [`PoolOptions`] used for the primary and each replica pool.
+    ///
+    /// This is only used by the `connect*()` methods.
+    pub fn pool_options(mut self, options: PoolOptions<DB>) -> Self {
+        self.pool_options = options;
+        self
+    }
+
+    /// Set the strategy used to choose a replica for each read.
+    pub fn replica_selection(mut self, selection: ReplicaSelection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Set how long a replica is taken out of rotation after connecting to it fails.
+    pub fn replica_retry_after(mut self, retry_after: Duration) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+
+    /// Create a `RoutedPool` from the given connection URLs.
+    ///
+    /// A connection to the primary is opened immediately to ensure the configuration is correct,
+    /// but the replica pools are created lazily so that a replica being down does not prevent
+    /// the application from starting.
+    pub async fn connect(
+        self,
+        primary_url: &str,
+        replica_urls: &[&str],
+    ) -> Result<RoutedPool<DB>, Error> {
+        let replicas = replica_urls
+            .iter()
+            .map(|url| url.parse())
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        self.connect_with(primary_url.parse()?, replicas).await
+    }
+
+    /// Create a `RoutedPool` from the given `ConnectOptions`.
+    ///
+    /// A connection to the primary is opened immediately to ensure the configuration is correct,
+    /// but the replica pools are created lazily so that a replica being down does not prevent
+    /// the application from starting.
+    pub async fn connect_with(
+        self,
+        primary: <DB::Connection as Connection>::Options,
+        replicas: impl IntoIterator<Item = <DB::Connection as Connection>::Options>,
+    ) -> Result<RoutedPool<DB>, Error> {
+        let primary = self.pool_options.clone().connect_with(primary).await?;
+
+        let replicas = replicas
+            .into_iter()
+            .map(|options| self.pool_options.clone().connect_lazy_with(options))
+            .collect();
+
+        Ok(self.build(primary, replicas))
+    }
+
+    /// Create a `RoutedPool` from the given `ConnectOptions` without opening any connections.
+    pub fn connect_lazy_with(
+        self,
+        primary: <DB::Connection as Connection>::Options,
+        replicas: impl IntoIterator<Item = <DB::Connection as Connection>::Options>,
+    ) -> RoutedPool<DB> {
+        let primary = self.pool_options.clone().connect_lazy_with(primary);
+
+        let replicas = replicas
+            .into_iter()
+            .map(|options| self.pool_options.clone().connect_lazy_with(options))
+            .collect();
+
+        self.build(primary, replicas)
+    }
+
+    /// Create a `RoutedPool` from existing pools.
+    ///
+    /// [`pool_options`][Self::pool_options] is ignored.
+    pub fn build(self, primary: Pool<DB>, replicas: Vec<Pool<DB>>) -> RoutedPool<DB> {
+        RoutedPool(Arc::new(RoutedPoolInner {
+            primary,
+            replicas: replicas
+                .into_iter()
+                .map(|pool| Replica {
+                    pool,
+                    down_until: Mutex::new(None),
+                })
+                .collect(),
+            selection: self.selection,
+            retry_after: self.retry_after,
+            next_replica: AtomicUsize::new(0),
+        }))
+    }
+}
+
+impl<DB: Database> RoutedPool<DB> {
+    /// Create a `RoutedPool` from existing pools with the default [`RoutedPoolOptions`].
+    pub fn new(primary: Pool<DB>, replicas: Vec<Pool<DB>>) -> Self {
+        RoutedPoolOptions::new().build(primary, replicas)
+    }
+
+    /// Get the primary pool, which should be used for all writes.
+    pub fn primary(&self) -> &Pool<DB> {
+        &self.0.primary
+    }
+
+    /// Get the next replica pool according to the configured [`ReplicaSelection`].
+    ///
+    /// Replicas that have been taken out of rotation are skipped. If there are no replicas,
+    /// or all of them are out of rotation, this returns the primary pool.
+    pub fn replica(&self) -> &Pool<DB> {
+        self.0
+            .replica_order()
+            .next()
+            .map_or(&self.0.primary, |replica| &replica.pool)
+    }
+
+    /// Get all replica pools, including any that are currently out of rotation.
+    pub fn replicas(&self) -> impl Iterator<Item = &Pool<DB>> + '_ {
+        self.0.replicas.iter().map(|replica| &replica.pool)
+    }
+
+    /// Retrieve a connection from the primary pool.
+    pub fn acquire(&self) -> impl Future<Output = Result<PoolConnection<DB>, Error>> + 'static {
+        self.0.primary.acquire()
+    }
+
+    /// Retrieve a connection from a replica pool, failing over to the next replica and finally
+    /// to the primary if a connection cannot be opened.
+    pub async fn acquire_replica(&self) -> Result<PoolConnection<DB>, Error> {
+        for replica in self.0.replica_order() {
+            match replica.pool.acquire().await {
+                Ok(conn) => {
+                    replica.mark_up();
+                    return Ok(conn);
+                }
+                Err(e) if replica.is_connect_failure(&e) => {
+                    log::warn!(
+                        "taking replica out of rotation for {:?} after error: {}",
+                        self.0.retry_after,
+                        e
+                    );
+
+                    replica.mark_down(self.0.retry_after);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        self.0.primary.acquire().await
+    }
+
+    /// Retrieve a connection from the primary pool and immediately begin a new transaction.
+    pub async fn begin(&self) -> Result<Transaction<'static, DB>, Error> {
+        self.0.primary.begin().await
+    }
+
+    /// Close the primary and all replica pools.
+    ///
+    /// See [`Pool::close()`] for details.
+    pub async fn close(&self) {
+        self.0.primary.close().await;
+
+        for replica in &self.0.replicas {
+            replica.pool.close().await;
+        }
+    }
+
+    /// Returns `true` if [`.close()`][Self::close] has been called, `false` otherwise.
+    pub fn is_closed(&self) -> bool {
+        self.0.primary.is_closed()
+    }
+
+    async fn acquire_for(&self, sql: &str) -> Result<PoolConnection<DB>, Error> {
+        if is_read_only(sql) {
+            self.acquire_replica().await
+        } else {
+            self.acquire().await
+        }
+    }
+}
+
+impl<DB: Database> RoutedPoolInner<DB> {
+    /// Iterate over replicas that are in rotation, starting with the preferred one.
+    fn replica_order(&self) -> impl Iterator<Item = &Replica<DB>> + '_ {
+        let len = self.replicas.len();
+
+        let start = match self.selection {
+            _ if len == 0 => 0,
+            ReplicaSelection::RoundRobin => self.next_replica.fetch_add(1, Ordering::Relaxed) % len,
+            ReplicaSelection::LeastBusy => self
+                .replicas
+                .iter()
+                .enumerate()
+                .filter(|(_, replica)| replica.is_up())
+                .min_by_key(|(_, replica)| replica.in_use())
+                .map_or(0, |(i, _)| i),
+        };
+
+        (0..len)
+            .map(move |i| &self.replicas[(start + i) % len])
+            .filter(|replica| replica.is_up())
+    }
+}
+
+impl<DB: Database> Replica<DB> {
+    fn is_up(&self) -> bool {
+        match *self.down_until.lock().unwrap() {
+            Some(until) => Instant::now() >= until,
+            None => true,
+        }
+    }
+
+    fn mark_up(&self) {
+        *self.down_until.lock().unwrap() = None;
+    }
+
+    fn mark_down(&self, retry_after: Duration) {
+        *self.down_until.lock().unwrap() = Some(Instant::now() + retry_after);
+    }
+
+    fn in_use(&self) -> usize {
+        (self.pool.size() as usize).saturating_sub(self.pool.num_idle())
+    }
+
+    /// Returns `true` if the error returned by `acquire()` means we couldn't connect.
+    fn is_connect_failure(&self, error: &Error) -> bool {
+        match error {
+            Error::PoolClosed => false,
+            // If the pool has no connections then it timed out trying to open one,
+            // otherwise the replica is just busy.
+            Error::PoolTimedOut => self.pool.size() == 0,
+            _ => true,
+        }
+    }
+}
+
+/// Returns `true` if `sql` is a single statement that is safe to run on a read replica.
+///
+/// This errs on the side of caution: anything it doesn't recognise is considered a write.
+fn is_read_only(sql: &str) -> bool {
+    let sql = skip_whitespace_and_comments(sql);
+
+    // Multiple statements may be sent at once through the simple query protocol;
+    // we don't try to classify each of them.
+    if let Some(i) = sql.find(';') {
+        if !skip_whitespace_and_comments(&sql[i + 1..]).is_empty() {
+            return false;
+        }
+    }
+
+    let upper = sql.to_ascii_uppercase();
+    let mut words = upper.split(|c: char| !c.is_ascii_alphanumeric() && c != '_');
+
+    let read_only = matches!(
+        words.next(),
+        Some("SELECT") | Some("VALUES") | Some("TABLE") | Some("SHOW")
+    );
+
+    // `SELECT ... FOR UPDATE` et al., `SELECT ... INTO new_table`, sequence manipulation
+    // and advisory locks need the primary.
+    read_only
+        && !words.any(|word| {
+            matches!(word, "FOR" | "INTO" | "NEXTVAL" | "SETVAL") || word.starts_with("PG_ADVISORY")
+        })
+}
+
+fn skip_whitespace_and_comments(mut sql: &str) -> &str {
+    loop {
+        sql = sql.trim_start();
+
+        if let Some(rest) = sql.strip_prefix("--") {
+            sql = rest.find('\n').map_or("", |i| &rest[i + 1..]);
+        } else if let Some(rest) = sql.strip_prefix("/*") {
+            sql = rest.find("*/").map_or("", |i| &rest[i + 2..]);
+        } else {
+            return sql;
+        }
+    }
+}
+
+impl<'p, DB: Database> Executor<'p> for &'_ RoutedPool<DB>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+    type Database = DB;
+
+    fn fetch_many<'e, 'q: 'e, E: 'q>(
+        self,
+        query: E,
+    ) -> BoxStream<'e, Result<Either<DB::QueryResult, DB::Row>, Error>>
+    where
+        E: Execute<'q, Self::Database>,
+    {
+        let pool = self.clone();
+
+        Box::pin(try_stream! {
+            let mut conn = pool.acquire_for(query.sql()).await?;
+            let mut s = conn.fetch_many(query);
+
+            while let Some(v) = s.try_next().await? {
+                r#yield!(v);
+            }
+
+            Ok(())
+        })
+    }
+
+    fn fetch_optional<'e, 'q: 'e, E: 'q>(
+        self,
+        query: E,
+    ) -> BoxFuture<'e, Result<Option<DB::Row>, Error>>
+    where
+        E: Execute<'q, Self::Database>,
+    {
+        let pool = self.clone();
+
+        Box::pin(async move {
+            pool.acquire_for(query.sql())
+                .await?
+                .fetch_optional(query)
+                .await
+        })
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [<Self::Database as Database>::TypeInfo],
+    ) -> BoxFuture<'e, Result<<Self::Database as HasStatement<'q>>::Statement, Error>> {
+        self.primary().prepare_with(sql, parameters)
+    }
+
+    #[doc(hidden)]
+    fn describe<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+    ) -> BoxFuture<'e, Result<Describe<Self::Database>, Error>> {
+        self.primary().describe(sql)
+    }
+}
+
+impl<'a, DB: Database> Acquire<'a> for &'_ RoutedPool<DB> {
+    type Database = DB;
+
+    type Connection = PoolConnection<DB>;
+
+    fn acquire(self) -> BoxFuture<'static, Result<Self::Connection, Error>> {
+        Box::pin(self.acquire())
+    }
+
+    fn begin(self) -> BoxFuture<'static, Result<Transaction<'a, DB>, Error>> {
+        let conn = self.acquire();
+
+        Box::pin(async move {
+            Transaction::begin(MaybePoolConnection::PoolConnection(conn.await?)).await
+        })
+    }
+}
+
+/// Returns a new [`RoutedPool`] tied to the same underlying pools.
+impl<DB: Database> Clone for RoutedPool<DB> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<DB: Database> Debug for RoutedPool<DB> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RoutedPool")
+            .field("primary", &self.0.primary)
+            .field(
+                "replicas",
+                &self.0.replicas.iter().map(|r| &r.pool).collect::<Vec<_>>(),
+            )
+            .field("selection", &self.0.selection)
+            .field("retry_after", &self.0.retry_after)
+            .finish()
+    }
+}
+
+impl<DB: Database> Debug for RoutedPoolOptions<DB> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RoutedPoolOptions")
+            .field("pool_options", &self.pool_options)
+            .field("selection", &self.selection)
+            .field("retry_after", &self.retry_after)
+            .finish()
+    }
+}
+
+#[test]
+fn test_is_read_only() {
+    assert!(is_read_only("SELECT 1"));
+    assert!(is_read_only("  select * from users where id = $1"));
+    assert!(is_read_only("-- get a user\nSELECT * FROM users;"));
+    assert!(is_read_only("/* comment */ VALUES (1), (2)"));
+    assert!(is_read_only("SHOW search_path"));
+    assert!(is_read_only("SELECT * FROM format"));
+
+    assert!(!is_read_only("INSERT INTO users (name) VALUES ('foo')"));
+    assert!(!is_read_only("UPDATE users SET name = 'foo'"));
+    assert!(!is_read_only("BEGIN"));
+    assert!(!is_read_only("SELECT * FROM users FOR UPDATE"));
+    assert!(!is_read_only("select * into new_users from users"));
+    assert!(!is_read_only("SELECT nextval('users_id_seq')"));
+    assert!(!is_read_only("SELECT pg_advisory_lock(1)"));
+    assert!(!is_read_only("SELECT 1; DELETE FROM users"));
+    assert!(!is_read_only(
+        "WITH d AS (DELETE FROM users RETURNING *) SELECT * FROM d"
+    ));
+    assert!(!is_read_only(""));
+}
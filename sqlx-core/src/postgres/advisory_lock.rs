@@ -1,10 +1,17 @@
-use crate::error::Result;
-use crate::postgres::PgConnection;
+use crate::connection::Connection;
+use crate::error::{Error, Result};
+use crate::pool::{Pool, PoolConnection};
+use crate::postgres::{PgConnection, Postgres};
+use crate::transaction::Transaction;
 use crate::Either;
+use futures_channel::oneshot;
+use futures_util::future::{self, FutureExt};
 use hkdf::Hkdf;
 use once_cell::sync::OnceCell;
 use sha2::Sha256;
+use std::io;
 use std::ops::{Deref, DerefMut};
+use std::time::Duration;
 
 /// A mutex-like type utilizing [Postgres advisory locks].
 ///
@@ -16,13 +23,13 @@ use std::ops::{Deref, DerefMut};
 /// advisory locks use, as well as RAII guards for releasing advisory locks when they fall out
 /// of scope.
 ///
-/// This API only handles session-scoped advisory locks (explicitly locked and unlocked, or
-/// automatically released when a connection is closed).
+/// This API handles session-scoped advisory locks (explicitly locked and unlocked, or
+/// automatically released when a connection is closed), either on a connection you provide or
+/// on one checked out of a [`PgPool`][crate::postgres::PgPool] for as long as the lock is held.
 ///
-/// It is also possible to use transaction-scoped locks but those can be used by beginning a
-/// transaction and calling the appropriate lock functions (e.g. `SELECT pg_advisory_xact_lock()`)
-/// manually, and cannot be explicitly released, but are automatically released when a transaction
-/// ends (is committed or rolled back).
+/// Transaction-scoped locks are also available through [`Self::acquire_xact()`] and
+/// [`Self::try_acquire_xact()`]. They cannot be explicitly released, but are automatically
+/// released when the transaction ends (is committed or rolled back).
 ///
 /// Session-level locks can be acquired either inside or outside a transaction and are not
 /// tied to transaction semantics; a lock acquired inside a transaction is still held when that
@@ -82,6 +89,32 @@ pub struct PgAdvisoryLockGuard<'lock, C: AsMut<PgConnection>> {
     conn: Option<C>,
 }
 
+/// A held Postgres advisory lock on a connection checked out of a [`Pool`] for that purpose.
+///
+/// Can be acquired by [`PgAdvisoryLock::acquire_pooled()`] or
+/// [`PgAdvisoryLock::try_acquire_pooled()`]. Released on-drop or via [`Self::release_now()`],
+/// after which the connection is returned to the pool.
+///
+/// A connection that sits idle while it holds the lock may be closed by the server
+/// (`idle_session_timeout`) or by anything in between, such as a proxy or a firewall, which
+/// releases the lock. Use [`Self::keep_alive()`] to prevent this.
+///
+/// ### Note: Release-on-drop is not immediate!
+/// Without a keepalive, dropping this guard queues a `pg_advisory_unlock()` call which is flushed
+/// to the server when the connection is returned to the pool; with one, the background task
+/// releases the lock as soon as it is notified. To ensure the lock is eagerly released, you can
+/// call [`.release_now().await`][Self::release_now()].
+pub struct PgAdvisoryLockPoolGuard<'lock> {
+    lock: &'lock PgAdvisoryLock,
+    conn: PooledLock<'lock>,
+}
+
+enum PooledLock<'lock> {
+    Held(Box<PgAdvisoryLockGuard<'lock, PoolConnection<Postgres>>>),
+    // the connection is owned by a task pinging it, which releases the lock when this is dropped
+    KeptAlive(oneshot::Sender<oneshot::Sender<Result<bool>>>),
+}
+
 impl PgAdvisoryLock {
     /// Construct a `PgAdvisoryLock` using the given string as a key.
     ///
@@ -269,6 +302,106 @@ impl PgAdvisoryLock {
         }
     }
 
+    /// Acquires an exclusive lock on a connection checked out of `pool` using
+    /// `pg_advisory_lock()`, waiting until the lock is acquired.
+    ///
+    /// The connection is held by the returned guard until the lock is released, so it is not
+    /// available to the rest of the pool in the meantime. This also waits for a connection
+    /// to become available.
+    ///
+    /// For a version that returns immediately if the lock is held elsewhere, see
+    /// [`Self::try_acquire_pooled()`].
+    pub async fn acquire_pooled(
+        &self,
+        pool: &Pool<Postgres>,
+    ) -> Result<PgAdvisoryLockPoolGuard<'_>> {
+        let guard = self.acquire(pool.acquire().await?).await?;
+
+        Ok(PgAdvisoryLockPoolGuard {
+            lock: self,
+            conn: PooledLock::Held(Box::new(guard)),
+        })
+    }
+
+    /// Acquires an exclusive lock on a connection checked out of `pool` using
+    /// `pg_try_advisory_lock()`, returning `None` immediately if the lock could not be acquired.
+    ///
+    /// In that case, the connection is returned to the pool. This still waits for a connection
+    /// to become available.
+    ///
+    /// For a version that waits until the lock is acquired, see [`Self::acquire_pooled()`].
+    pub async fn try_acquire_pooled(
+        &self,
+        pool: &Pool<Postgres>,
+    ) -> Result<Option<PgAdvisoryLockPoolGuard<'_>>> {
+        match self.try_acquire(pool.acquire().await?).await? {
+            Either::Left(guard) => Ok(Some(PgAdvisoryLockPoolGuard {
+                lock: self,
+                conn: PooledLock::Held(Box::new(guard)),
+            })),
+            Either::Right(_) => Ok(None),
+        }
+    }
+
+    /// Acquires an exclusive transaction-level lock using `pg_advisory_xact_lock()`, waiting
+    /// until the lock is acquired.
+    ///
+    /// The lock is held until the transaction is committed or rolled back, and cannot be
+    /// released before that. If the lock is acquired inside a savepoint (a nested transaction),
+    /// it is still held until the outermost transaction ends, unless that savepoint is rolled
+    /// back.
+    ///
+    /// Transaction-level and session-level locks share the same keys, so this waits for a
+    /// session-level lock of the same key held by another connection, and vice versa.
+    ///
+    /// For a version that returns immediately instead of waiting,
+    /// see [`Self::try_acquire_xact()`].
+    pub async fn acquire_xact(&self, tx: &mut Transaction<'_, Postgres>) -> Result<()> {
+        match &self.key {
+            PgAdvisoryLockKey::BigInt(key) => {
+                crate::query::query("SELECT pg_advisory_xact_lock($1)")
+                    .bind(key)
+                    .execute(&mut **tx)
+                    .await?;
+            }
+            PgAdvisoryLockKey::IntPair(key1, key2) => {
+                crate::query::query("SELECT pg_advisory_xact_lock($1, $2)")
+                    .bind(key1)
+                    .bind(key2)
+                    .execute(&mut **tx)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Acquires an exclusive transaction-level lock using `pg_try_advisory_xact_lock()`,
+    /// returning `false` immediately if the lock could not be acquired.
+    ///
+    /// If `true` is returned, the lock is held until the transaction is committed or rolled back.
+    ///
+    /// For a version that waits until the lock is acquired, see [`Self::acquire_xact()`].
+    pub async fn try_acquire_xact(&self, tx: &mut Transaction<'_, Postgres>) -> Result<bool> {
+        let locked: bool = match &self.key {
+            PgAdvisoryLockKey::BigInt(key) => {
+                crate::query_scalar::query_scalar("SELECT pg_try_advisory_xact_lock($1)")
+                    .bind(key)
+                    .fetch_one(&mut **tx)
+                    .await?
+            }
+            PgAdvisoryLockKey::IntPair(key1, key2) => {
+                crate::query_scalar::query_scalar("SELECT pg_try_advisory_xact_lock($1, $2)")
+                    .bind(key1)
+                    .bind(key2)
+                    .fetch_one(&mut **tx)
+                    .await?
+            }
+        };
+
+        Ok(locked)
+    }
+
     /// Execute `pg_advisory_unlock()` for this lock's key on the given connection.
     ///
     /// This is used by [`PgAdvisoryLockGuard::release_now()`] and is also provided for manually
@@ -363,6 +496,109 @@ impl<'lock, C: AsMut<PgConnection>> PgAdvisoryLockGuard<'lock, C> {
     }
 }
 
+impl<'lock> PgAdvisoryLockPoolGuard<'lock> {
+    /// Keep the connection holding the lock from sitting idle by pinging it every `interval`,
+    /// from a background task.
+    ///
+    /// If a ping fails, the lock is most likely lost with the connection, which is logged as
+    /// a warning.
+    ///
+    /// Calling this again has no effect.
+    pub fn keep_alive(mut self, interval: Duration) -> Self {
+        if let PooledLock::Held(guard) = self.conn {
+            let (release_tx, release_rx) = oneshot::channel();
+
+            sqlx_rt::spawn(keep_alive(
+                self.lock.clone(),
+                guard.leak(),
+                interval,
+                release_rx,
+            ));
+
+            self.conn = PooledLock::KeptAlive(release_tx);
+        }
+
+        self
+    }
+
+    /// Immediately release the held advisory lock and return the connection to the pool.
+    ///
+    /// An error should only be returned if there is something wrong with the connection,
+    /// in which case the lock will be automatically released by the connection closing anyway.
+    ///
+    /// If `pg_advisory_unlock()` returns `false`, a warning will be logged, both by SQLx as
+    /// well as the Postgres server. This would only happen if the lock was released without
+    /// using this guard.
+    pub async fn release_now(self) -> Result<()> {
+        let released = match self.conn {
+            PooledLock::Held(guard) => {
+                let conn = guard.leak();
+                self.lock.force_release(conn).await?.1
+            }
+
+            PooledLock::KeptAlive(release_tx) => {
+                let (reply_tx, reply_rx) = oneshot::channel();
+
+                // the task is only gone if pinging the connection failed, losing the lock with it
+                let lost = || Error::Io(io::ErrorKind::ConnectionAborted.into());
+
+                release_tx.send(reply_tx).map_err(|_| lost())?;
+                reply_rx.await.map_err(|_| lost())??
+            }
+        };
+
+        if !released {
+            log::warn!(
+                "PgAdvisoryLockPoolGuard: advisory lock {:?} was not held by the contained connection",
+                self.lock.key
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Ping the connection holding the lock every `interval` until told to release the lock, or the
+/// guard is dropped.
+async fn keep_alive(
+    lock: PgAdvisoryLock,
+    mut conn: PoolConnection<Postgres>,
+    interval: Duration,
+    mut release_rx: oneshot::Receiver<oneshot::Sender<Result<bool>>>,
+) {
+    loop {
+        let sleep = sqlx_rt::sleep(interval).boxed();
+
+        match future::select(sleep, &mut release_rx).await {
+            future::Either::Left(_) => {
+                if let Err(error) = conn.ping().await {
+                    log::warn!(
+                        "PgAdvisoryLockPoolGuard: failed to ping the connection holding advisory lock {:?}: {}",
+                        lock.key,
+                        error
+                    );
+
+                    return;
+                }
+            }
+
+            future::Either::Right((reply_tx, _)) => {
+                let released = lock
+                    .force_release(&mut conn)
+                    .await
+                    .map(|(_, released)| released);
+
+                // `Err` if the guard was dropped instead of released with `release_now()`
+                if let Ok(reply_tx) = reply_tx {
+                    let _ = reply_tx.send(released);
+                }
+
+                return;
+            }
+        }
+    }
+}
+
 impl<'lock, C: AsMut<PgConnection> + AsRef<PgConnection>> Deref for PgAdvisoryLockGuard<'lock, C> {
     type Target = PgConnection;
 
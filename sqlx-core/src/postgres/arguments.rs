@@ -168,6 +168,32 @@ impl PgArgumentBuffer {
     }
 }
 
+impl PgArgumentBuffer {
+    // Fill in the type holes using only the types this connection has already resolved, for when
+    // we can't stop to ask postgres (e.g. in the middle of a `COPY`)
+    pub(crate) fn apply_cached_type_holes(&mut self, conn: &PgConnection) -> Result<(), Error> {
+        for (offset, name) in &self.type_holes {
+            let oid = conn
+                .cached_type_id_by_name(name)
+                .ok_or_else(|| Error::TypeNotFound {
+                    type_name: name.to_string(),
+                })?;
+
+            self.buffer[*offset..(*offset + 4)].copy_from_slice(&oid.0.to_be_bytes());
+        }
+
+        self.type_holes.clear();
+
+        Ok(())
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.buffer.truncate(len);
+        self.patches.retain(|(offset, ..)| *offset < len);
+        self.type_holes.retain(|(offset, _)| *offset < len);
+    }
+}
+
 impl Deref for PgArgumentBuffer {
     type Target = Vec<u8>;
 
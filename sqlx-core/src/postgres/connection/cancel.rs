@@ -0,0 +1,176 @@
+use std::fmt::{self, Debug, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use bytes::Bytes;
+use futures_channel::oneshot;
+
+use crate::error::Error;
+use crate::postgres::connection::{stream::PgStream, tls};
+use crate::postgres::message::CancelRequest;
+use crate::postgres::{PgConnectOptions, PgConnection};
+
+/// A handle used to cancel the query currently running on a [`PgConnection`].
+///
+/// Created with [`PgConnection::cancel_token()`]. The token is cheap to clone and may be sent to
+/// another task; cancelling opens a new connection to the same server, as described in
+/// [the PostgreSQL documentation](https://www.postgresql.org/docs/current/protocol-flow.html#id-1.10.6.9).
+#[derive(Clone)]
+pub struct PgCancelToken {
+    // options for the host the connection was actually established with
+    options: Arc<PgConnectOptions>,
+    process_id: u32,
+    secret_key: u32,
+}
+
+impl PgCancelToken {
+    /// Ask the server to cancel the query currently running on the connection, if any.
+    ///
+    /// Cancellation is best-effort: the server may have already finished the query by the time
+    /// the request is processed, in which case the request has no effect. If the query is
+    /// cancelled, it will fail with SQLSTATE `57014` (`query_canceled`).
+    pub async fn cancel(&self) -> Result<(), Error> {
+        let mut stream = PgStream::connect(&self.options).await?;
+
+        tls::maybe_upgrade(&mut stream, &self.options).await?;
+
+        stream
+            .send(CancelRequest {
+                process_id: self.process_id,
+                secret_key: self.secret_key,
+            })
+            .await?;
+
+        // The server closes the connection without replying once it has processed the request;
+        // waiting for that means the cancel can't race with whatever we send next.
+        let _ = stream.read::<Bytes>(1).await;
+
+        Ok(())
+    }
+}
+
+impl Debug for PgCancelToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PgCancelToken")
+            .field("host", &self.options.host)
+            .field("port", &self.options.port)
+            .field("process_id", &self.process_id)
+            .finish()
+    }
+}
+
+/// A cancel request sent by a [`CancelOnDrop`] that has not finished yet.
+#[derive(Default)]
+pub(crate) struct PendingCancel(Arc<Mutex<Option<oneshot::Receiver<()>>>>);
+
+impl PendingCancel {
+    pub(crate) fn take(&self) -> Option<oneshot::Receiver<()>> {
+        self.lock().take()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<oneshot::Receiver<()>>> {
+        // The slot is always consistent so it's fine to ignore poisoning.
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Cancels the running query if dropped before [`disarm()`][Self::disarm] is called.
+///
+/// Held by the futures and streams returned by the [`Executor`][crate::executor::Executor] impl,
+/// so that dropping one of them mid-query (e.g. on a timeout) doesn't leave the server working
+/// on a query nobody is waiting for.
+///
+/// Nothing is sent if the server had already finished the query (see [`finished()`][Self::finished])
+/// or if dropped outside of a runtime.
+pub(crate) struct CancelOnDrop {
+    token: Option<PgCancelToken>,
+    pending: PendingCancel,
+    finished: Arc<AtomicBool>,
+}
+
+impl CancelOnDrop {
+    pub(crate) fn new(conn: &PgConnection) -> Self {
+        // some proxies don't send `BackendKeyData`, in which case there's nothing to cancel with
+        let token = (conn.process_id != 0 || conn.secret_key != 0).then(|| conn.cancel_token());
+
+        Self {
+            token,
+            pending: PendingCancel(Arc::clone(&conn.pending_cancel.0)),
+            finished: Arc::default(),
+        }
+    }
+
+    /// A flag to set once the server is known to have finished the query, i.e. its results
+    /// only have to be received, so it isn't worth opening a connection to cancel it.
+    pub(crate) fn finished(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.finished)
+    }
+
+    /// The query finished (successfully or not); there is nothing to cancel.
+    pub(crate) fn disarm(&mut self) {
+        self.token = None;
+    }
+
+    /// Disarm if `result` is an error, as the query is no longer running.
+    pub(crate) fn check<T>(&mut self, result: Result<T, Error>) -> Result<T, Error> {
+        if result.is_err() {
+            self.disarm();
+        }
+
+        result
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        let token = match self.token.take() {
+            Some(token) if !self.finished.load(Ordering::Acquire) => token,
+            _ => return,
+        };
+
+        // the rest of the query's results are skipped by the next query either way
+        #[cfg(not(feature = "_rt-async-std"))]
+        let handle = match sqlx_rt::Handle::try_current() {
+            Ok(handle) => handle,
+            Err(_) => return,
+        };
+
+        let (tx, rx) = oneshot::channel();
+
+        // the connection waits for this before sending anything else,
+        // so the cancel can't hit a later query
+        *self.pending.lock() = Some(rx);
+
+        let cancel = async move {
+            if let Err(error) = token.cancel().await {
+                log::warn!("error cancelling dropped query: {}", error);
+            }
+
+            let _ = tx.send(());
+        };
+
+        #[cfg(not(feature = "_rt-async-std"))]
+        handle.spawn(cancel);
+
+        #[cfg(feature = "_rt-async-std")]
+        sqlx_rt::spawn(cancel);
+    }
+}
+
+impl PgConnection {
+    /// Returns a handle that can be used to cancel the query running on this connection
+    /// from another task.
+    ///
+    /// Note that futures and streams returned by this connection's
+    /// [`Executor`][crate::executor::Executor] methods already cancel their query if they are
+    /// dropped before it completes.
+    pub fn cancel_token(&self) -> PgCancelToken {
+        PgCancelToken {
+            options: Arc::clone(&self.cancel_options),
+            process_id: self.process_id,
+            secret_key: self.secret_key,
+        }
+    }
+}
@@ -10,6 +10,7 @@ use crate::query_scalar::{query_scalar, query_scalar_with};
 use crate::types::Json;
 use crate::HashMap;
 use futures_core::future::BoxFuture;
+use std::collections::HashSet;
 use std::fmt::Write;
 use std::sync::Arc;
 
@@ -24,6 +25,7 @@ enum TypType {
     Enum,
     Pseudo,
     Range,
+    MultiRange,
 }
 
 impl TryFrom<u8> for TypType {
@@ -37,6 +39,7 @@ impl TryFrom<u8> for TypType {
             b'e' => Self::Enum,
             b'p' => Self::Pseudo,
             b'r' => Self::Range,
+            b'm' => Self::MultiRange,
             _ => return Err(()),
         };
         Ok(t)
@@ -220,6 +223,10 @@ impl PgConnection {
                     self.fetch_range_by_oid(oid, name).await
                 }
 
+                (Ok(TypType::MultiRange), Ok(TypCategory::Range)) => {
+                    self.fetch_multirange_by_oid(oid, name).await
+                }
+
                 (Ok(TypType::Enum), Ok(TypCategory::Enum)) => {
                     self.fetch_enum_by_oid(oid, name).await
                 }
@@ -338,18 +345,53 @@ WHERE rngtypid = $1
         })
     }
 
+    fn fetch_multirange_by_oid(
+        &mut self,
+        oid: Oid,
+        name: String,
+    ) -> BoxFuture<'_, Result<PgTypeInfo, Error>> {
+        Box::pin(async move {
+            let range_oid: Oid = query_scalar(
+                r#"
+SELECT rngtypid
+FROM pg_catalog.pg_range
+WHERE rngmultitypid = $1
+                "#,
+            )
+            .bind(oid)
+            .fetch_one(&mut *self)
+            .await?;
+
+            let range = self.maybe_fetch_type_info_by_oid(range_oid, true).await?;
+
+            Ok(PgTypeInfo(PgType::Custom(Arc::new(PgCustomType {
+                kind: PgTypeKind::MultiRange(range),
+                name: name.into(),
+                oid,
+            }))))
+        })
+    }
+
+    pub(crate) fn cached_type_id_by_name(&self, name: &str) -> Option<Oid> {
+        self.cache_type_oid.get(name).copied()
+    }
+
     pub(crate) async fn fetch_type_id_by_name(&mut self, name: &str) -> Result<Oid, Error> {
-        if let Some(oid) = self.cache_type_oid.get(name) {
-            return Ok(*oid);
+        if let Some(oid) = self.cached_type_id_by_name(name) {
+            return Ok(oid);
         }
 
-        // language=SQL
-        let (oid,): (Oid,) = query_as(
-            "
-SELECT oid FROM pg_catalog.pg_type WHERE typname ILIKE $1
-                ",
-        )
-        .bind(name)
+        // `element[]` is the array of `element`, whatever Postgres has named it
+        let (column, type_name) = match name.strip_suffix("[]") {
+            Some(element) => ("typarray", element),
+            None => ("oid", name),
+        };
+
+        let (oid,): (Oid,) = query_as(&format!(
+            "SELECT {} FROM pg_catalog.pg_type WHERE typname ILIKE $1",
+            column
+        ))
+        .bind(type_name)
         .fetch_optional(&mut *self)
         .await?
         .ok_or_else(|| Error::TypeNotFound {
@@ -420,8 +462,9 @@ SELECT oid FROM pg_catalog.pg_type WHERE typname ILIKE $1
 
     /// Infer nullability for columns of this statement using EXPLAIN VERBOSE.
     ///
-    /// This currently only marks columns that are on the inner half of an outer join
-    /// and returns `None` for all others.
+    /// This marks columns that come from the nullable side of an outer join, and columns
+    /// computed by expressions that can never be NULL, such as `count(*)` or a `COALESCE` with
+    /// a non-null argument. It returns `None` for all others.
     async fn nullables_from_explain(
         &mut self,
         stmt_id: Oid,
@@ -449,42 +492,483 @@ SELECT oid FROM pg_catalog.pg_type WHERE typname ILIKE $1
             explain += ")";
         }
 
-        let (Json([explain]),): (Json<[Explain; 1]>,) = query_as(&explain).fetch_one(self).await?;
+        // a custom plan would fold the NULL arguments into constants, so ask for a generic plan
+        // where we can; without one, only outer joins are trusted for statements with parameters
+        let generic_plan = params_len == 0 || self.server_version_num().unwrap_or(0) >= 120000;
+        let mut plan_cache_mode = None;
+
+        // inside a transaction, only change the transaction-local value so that a `SET LOCAL`
+        // of the caller's isn't turned into a session-level setting
+        let is_local = self.transaction_depth > 0;
 
-        let mut nullables = Vec::new();
+        if params_len > 0 && generic_plan {
+            let (previous, _): (String, String) = query_as(
+                "SELECT current_setting('plan_cache_mode'), \
+                 set_config('plan_cache_mode', 'force_generic_plan', $1)",
+            )
+            .bind(is_local)
+            .fetch_one(&mut *self)
+            .await?;
 
-        if let Some(outputs) = &explain.plan.output {
-            nullables.resize(outputs.len(), None);
-            visit_plan(&explain.plan, outputs, &mut nullables);
+            plan_cache_mode = Some(previous);
         }
 
+        let explained = query_as(&explain).fetch_one(&mut *self).await;
+
+        let restored = match plan_cache_mode {
+            Some(previous) => query_as("SELECT set_config('plan_cache_mode', $1, $2)")
+                .bind(previous)
+                .bind(is_local)
+                .fetch_one(&mut *self)
+                .await
+                .map(|_: (String,)| ()),
+
+            None => Ok(()),
+        };
+
+        // if the `EXPLAIN` failed inside a transaction, the restore fails too as the transaction
+        // is aborted; rolling it back undoes the `set_config` anyway, so report the first error
+        let (Json([explain]),): (Json<[Explain; 1]>,) = explained?;
+        restored?;
+
+        let outputs = match &explain.plan.output {
+            Some(outputs) => outputs,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut nullable = NullableSide::default();
+        visit_plan(&explain.plan, false, &mut nullable);
+
+        // the tables with columns that `COALESCE` needs to know about
+        let mut relations = HashSet::new();
+
+        if generic_plan {
+            for output in outputs {
+                is_non_null_output(output, &mut |alias, _| {
+                    relations.extend(nullable.relation(alias).cloned());
+                    false
+                });
+            }
+        }
+
+        let not_null_columns: HashSet<(String, String, String)> = if relations.is_empty() {
+            HashSet::new()
+        } else {
+            let (schemas, relations): (Vec<String>, Vec<String>) = relations.into_iter().unzip();
+
+            query_as(
+                "SELECT n.nspname::text, c.relname::text, a.attname::text \
+                 FROM pg_catalog.pg_attribute a \
+                 JOIN pg_catalog.pg_class c ON c.oid = a.attrelid \
+                 JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+                 WHERE a.attnotnull AND a.attnum > 0 \
+                   AND (n.nspname, c.relname) IN (SELECT * FROM UNNEST($1::text[], $2::text[]))",
+            )
+            .bind(schemas)
+            .bind(relations)
+            .fetch_all(&mut *self)
+            .await?
+            .into_iter()
+            .collect()
+        };
+
+        let mut is_not_null_column = |alias: &str, column: &str| match nullable.relation(alias) {
+            Some((schema, relation)) => {
+                not_null_columns.contains(&(schema.clone(), relation.clone(), column.to_owned()))
+            }
+            None => false,
+        };
+
+        let nullables = outputs
+            .iter()
+            .map(|output| {
+                let expr = strip_parens(output);
+
+                if nullable.outputs.contains(expr) {
+                    // computed on the nullable side of an outer join
+                    // N.B. this may produce false positives but those don't cause runtime errors
+                    Some(true)
+                } else if generic_plan && is_non_null_output(expr, &mut is_not_null_column) {
+                    Some(false)
+                } else if column_refs(expr)
+                    .iter()
+                    .any(|(alias, _)| nullable.aliases.contains(alias))
+                {
+                    // uses a column from the nullable side of an outer join
+                    Some(true)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
         Ok(nullables)
     }
 }
 
-fn visit_plan(plan: &Plan, outputs: &[String], nullables: &mut Vec<Option<bool>>) {
-    if let Some(plan_outputs) = &plan.output {
-        // all outputs of a Full Join must be marked nullable
-        // otherwise, all outputs of the inner half of an outer join must be marked nullable
-        if plan.join_type.as_deref() == Some("Full")
-            || plan.parent_relation.as_deref() == Some("Inner")
-        {
-            for output in plan_outputs {
-                if let Some(i) = outputs.iter().position(|o| o == output) {
-                    // N.B. this may produce false positives but those don't cause runtime errors
-                    nullables[i] = Some(true);
-                }
+/// What is on the nullable side of the outer joins of a plan.
+#[derive(Default)]
+struct NullableSide {
+    // aliases of the relations scanned on the nullable side
+    aliases: HashSet<String>,
+    // outputs of the plan nodes on the nullable side
+    outputs: HashSet<String>,
+    // every relation scanned by the plan, by alias, as `(schema, relation name)`
+    relations: HashMap<String, (String, String)>,
+}
+
+impl NullableSide {
+    /// Returns the table scanned with this alias, if it is not on the nullable side.
+    ///
+    /// Columns are only printed without an alias when there is a single relation.
+    fn relation(&self, alias: &str) -> Option<&(String, String)> {
+        if alias.is_empty() && self.relations.len() == 1 {
+            return self.relations.values().next();
+        }
+
+        if self.aliases.contains(alias) {
+            return None;
+        }
+
+        self.relations.get(alias)
+    }
+}
+
+fn visit_plan(plan: &Plan, nullable_side: bool, nullable: &mut NullableSide) {
+    if let (Some(alias), Some(schema), Some(relation)) =
+        (&plan.alias, &plan.schema, &plan.relation_name)
+    {
+        nullable
+            .relations
+            .insert(alias.clone(), (schema.clone(), relation.clone()));
+    }
+
+    if nullable_side {
+        if let Some(alias) = &plan.alias {
+            nullable.aliases.insert(alias.clone());
+        }
+
+        if let Some(outputs) = &plan.output {
+            for output in outputs {
+                nullable.outputs.insert(strip_parens(output).to_owned());
             }
         }
     }
 
     if let Some(plans) = &plan.plans {
-        if let Some("Left") | Some("Right") = plan.join_type.as_deref() {
-            for plan in plans {
-                visit_plan(plan, outputs, nullables);
+        for child in plans {
+            // both halves of a full join are nullable, otherwise it is the inner half of a left
+            // join, or the outer half of a right join
+            let child_nullable = nullable_side
+                || matches!(
+                    (plan.join_type.as_deref(), child.parent_relation.as_deref()),
+                    (Some("Full"), _)
+                        | (Some("Left"), Some("Inner"))
+                        | (Some("Right"), Some("Outer"))
+                );
+
+            visit_plan(child, child_nullable, nullable);
+        }
+    }
+}
+
+/// Returns `true` if an output expression, as printed by `EXPLAIN VERBOSE`, can never be NULL.
+///
+/// Constants are ignored here, as they may be a folded expression of the arguments, and columns
+/// are left to the catalog lookup.
+fn is_non_null_output(expr: &str, column: &mut dyn FnMut(&str, &str) -> bool) -> bool {
+    let expr = strip_casts(expr);
+
+    match split_call(expr) {
+        Some((name, args, rest)) => is_non_null_call(name, args, rest, column),
+        None => false,
+    }
+}
+
+/// Returns `true` if an expression, as printed by `EXPLAIN VERBOSE`, can never be NULL.
+///
+/// `column` is called with the alias and name of each column to check.
+fn is_non_null(expr: &str, column: &mut dyn FnMut(&str, &str) -> bool) -> bool {
+    let expr = strip_casts(expr);
+
+    if let Some((name, args, rest)) = split_call(expr) {
+        return is_non_null_call(name, args, rest, column);
+    }
+
+    if is_literal(expr) {
+        return true;
+    }
+
+    match column_ref(expr) {
+        Some((alias, name)) => column(&alias, &name),
+        None => false,
+    }
+}
+
+fn is_non_null_call(
+    name: &str,
+    args: &str,
+    rest: &str,
+    column: &mut dyn FnMut(&str, &str) -> bool,
+) -> bool {
+    let name = name.trim_start_matches("pg_catalog.").to_ascii_lowercase();
+
+    // aggregates and window functions may be followed by `FILTER (...)` or `OVER (...)`
+    let aggregate = rest.is_empty() || rest.starts_with("FILTER ") || rest.starts_with("OVER ");
+
+    match &*name {
+        "coalesce" if rest.is_empty() => split_top_level(args).any(|arg| is_non_null(arg, column)),
+
+        "count" | "row_number" | "rank" | "dense_rank" | "percent_rank" | "cume_dist" => aggregate,
+
+        _ => false,
+    }
+}
+
+/// Remove any casts and parentheses around an expression.
+fn strip_casts(mut expr: &str) -> &str {
+    loop {
+        expr = strip_parens(expr);
+
+        match find_top_level(expr, "::") {
+            Some(i) => expr = &expr[..i],
+            None => return expr,
+        }
+    }
+}
+
+/// Remove the parentheses around an expression, if they enclose all of it.
+fn strip_parens(mut expr: &str) -> &str {
+    loop {
+        expr = expr.trim();
+
+        match closing_paren(expr) {
+            Some(close) if close == expr.len() - 1 => expr = &expr[1..close],
+            _ => return expr,
+        }
+    }
+}
+
+/// Split a function call into its name, arguments and whatever follows the arguments.
+fn split_call(expr: &str) -> Option<(&str, &str, &str)> {
+    let open = expr.find('(')?;
+    let name = &expr[..open];
+
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '"')
+    {
+        return None;
+    }
+
+    let close = open + closing_paren(&expr[open..])?;
+
+    Some((name, &expr[open + 1..close], expr[close + 1..].trim()))
+}
+
+/// Split comma-separated arguments.
+fn split_top_level(args: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(args);
+
+    std::iter::from_fn(move || {
+        let args = rest?;
+
+        match find_top_level(args, ",") {
+            Some(i) => {
+                rest = Some(&args[i + 1..]);
+                Some(&args[..i])
+            }
+
+            None => {
+                rest = None;
+                Some(args)
+            }
+        }
+    })
+}
+
+/// Find `pattern` outside of any parentheses, brackets and quotes.
+fn find_top_level(expr: &str, pattern: &str) -> Option<usize> {
+    scan(expr, |i, depth| {
+        depth == 0 && expr[i..].starts_with(pattern)
+    })
+}
+
+/// If the expression starts with a parenthesis, find the one that closes it.
+fn closing_paren(expr: &str) -> Option<usize> {
+    if !expr.starts_with('(') {
+        return None;
+    }
+
+    scan(expr, |i, depth| depth == 0 && expr[i..].starts_with(')'))
+}
+
+/// Find the first character outside of quotes where `f(index, depth)` returns `true`, where the
+/// depth of a closing parenthesis or bracket is outside of it.
+fn scan(expr: &str, mut f: impl FnMut(usize, usize) -> bool) -> Option<usize> {
+    let mut depth = 0_usize;
+    let mut quote = None;
+
+    for (i, c) in expr.char_indices() {
+        match quote {
+            // doubled quotes are escapes, which is the same as closing and opening again
+            Some(q) if c == q => quote = None,
+            Some(_) => continue,
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None => {}
+        }
+
+        if c == ')' || c == ']' {
+            depth = depth.saturating_sub(1);
+        }
+
+        if quote.is_none() && c != '\'' && c != '"' && f(i, depth) {
+            return Some(i);
+        }
+
+        if c == '(' || c == '[' {
+            depth += 1;
+        }
+    }
+
+    None
+}
+
+fn is_literal(expr: &str) -> bool {
+    match expr {
+        "true" | "false" => true,
+
+        _ if expr.len() >= 2 && expr.starts_with('\'') && expr.ends_with('\'') => {
+            !expr[1..expr.len() - 1].replace("''", "").contains('\'')
+        }
+
+        // `f64` also parses `nan` and `inf`, which are column names here, not literals
+        _ => {
+            expr.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.')
+                && expr.parse::<f64>().is_ok()
+        }
+    }
+}
+
+/// Parse an expression which is only a column reference, `alias.column` or `column`, in which
+/// case the alias is empty.
+fn column_ref(expr: &str) -> Option<(String, String)> {
+    match tokenize(expr).as_slice() {
+        [Token::Ident(alias), Token::Dot, Token::Ident(column)] => {
+            Some((alias.clone(), column.clone()))
+        }
+
+        [Token::Ident(column)] => Some((String::new(), column.clone())),
+
+        _ => None,
+    }
+}
+
+/// Every column referenced by an expression, as `(alias, column)`.
+fn column_refs(expr: &str) -> Vec<(String, String)> {
+    let tokens = tokenize(expr);
+    let mut refs = Vec::new();
+
+    for (i, window) in tokens.windows(3).enumerate() {
+        if let [Token::Ident(alias), Token::Dot, Token::Ident(column)] = window {
+            // skip type names and function names, which may be qualified with their schema
+            let cast = i > 0 && tokens[i - 1] == Token::Cast;
+            let call = tokens.get(i + 3) == Some(&Token::Open);
+            // and the middle of `a.b.c`
+            let nested = i > 0 && tokens[i - 1] == Token::Dot;
+
+            if !cast && !call && !nested {
+                refs.push((alias.clone(), column.clone()));
             }
         }
     }
+
+    refs
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Dot,
+    Cast,
+    Open,
+    Other,
+}
+
+/// Split an expression into tokens, enough to find the column references.
+fn tokenize(expr: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+
+            '.' => Token::Dot,
+            '(' => Token::Open,
+
+            ':' if chars.peek() == Some(&':') => {
+                chars.next();
+                Token::Cast
+            }
+
+            '\'' => {
+                // a doubled quote is an escape, which is the same as two adjacent literals here
+                for c in chars.by_ref() {
+                    if c == '\'' {
+                        break;
+                    }
+                }
+
+                Token::Other
+            }
+
+            '"' => {
+                let mut ident = String::new();
+
+                while let Some(c) = chars.next() {
+                    if c == '"' {
+                        if chars.peek() != Some(&'"') {
+                            break;
+                        }
+
+                        chars.next();
+                    }
+
+                    ident.push(c);
+                }
+
+                Token::Ident(ident)
+            }
+
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut ident = String::from(c);
+
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_' || c == '$') {
+                        break;
+                    }
+
+                    ident.push(c);
+                    chars.next();
+                }
+
+                // numbers aren't identifiers
+                if c.is_ascii_digit() {
+                    Token::Other
+                } else {
+                    Token::Ident(ident)
+                }
+            }
+
+            _ => Token::Other,
+        };
+
+        tokens.push(token);
+    }
+
+    tokens
 }
 
 #[derive(serde::Deserialize)]
@@ -503,4 +987,73 @@ struct Plan {
     output: Option<Vec<String>>,
     #[serde(rename = "Plans")]
     plans: Option<Vec<Plan>>,
+    #[serde(rename = "Alias")]
+    alias: Option<String>,
+    #[serde(rename = "Schema")]
+    schema: Option<String>,
+    #[serde(rename = "Relation Name")]
+    relation_name: Option<String>,
+}
+
+#[test]
+fn test_is_non_null_output() {
+    let mut not_null = |alias: &str, column: &str| alias == "a" && column == "id";
+
+    assert!(is_non_null_output("count(*)", &mut not_null));
+    assert!(is_non_null_output("count(b.id)", &mut not_null));
+    assert!(is_non_null_output("(count(*))::integer", &mut not_null));
+    assert!(is_non_null_output(
+        "count(*) FILTER (WHERE (b.id > 1))",
+        &mut not_null
+    ));
+    assert!(is_non_null_output("row_number() OVER (?)", &mut not_null));
+    assert!(is_non_null_output(
+        "COALESCE(b.name, 'none'::text)",
+        &mut not_null
+    ));
+    assert!(is_non_null_output("COALESCE(b.id, a.id)", &mut not_null));
+    assert!(is_non_null_output(
+        "COALESCE(b.id, COALESCE(b.id, 0))",
+        &mut not_null
+    ));
+
+    assert!(!is_non_null_output("sum(b.id)", &mut not_null));
+    assert!(!is_non_null_output(
+        "COALESCE(b.id, a.owner_id)",
+        &mut not_null
+    ));
+    assert!(!is_non_null_output(
+        "COALESCE(b.id, NULL::bigint)",
+        &mut not_null
+    ));
+    assert!(!is_non_null_output(
+        "(COALESCE(b.id, 0) + b.id)",
+        &mut not_null
+    ));
+    // columns named like special float values
+    assert!(!is_non_null_output("COALESCE(nan, inf)", &mut not_null));
+    assert!(is_non_null_output("COALESCE(nan, -1.5)", &mut not_null));
+    // columns and constants are left to the caller
+    assert!(!is_non_null_output("a.id", &mut not_null));
+    assert!(!is_non_null_output("'11'::bigint", &mut not_null));
+}
+
+#[test]
+fn test_column_refs() {
+    assert_eq!(
+        column_refs(r#"(("Tweet"."Text")::character varying || 'a.b'::text)"#),
+        vec![("Tweet".to_owned(), "Text".to_owned())]
+    );
+
+    assert_eq!(
+        column_refs("COALESCE(a.id, pg_catalog.abs(b.id), '1.5'::public.my_type)"),
+        vec![
+            ("a".to_owned(), "id".to_owned()),
+            ("b".to_owned(), "id".to_owned())
+        ]
+    );
+
+    assert_eq!(column_ref("a.id"), Some(("a".to_owned(), "id".to_owned())));
+    assert_eq!(column_ref("id"), Some((String::new(), "id".to_owned())));
+    assert_eq!(column_ref("(a.id + 1)"), None);
 }
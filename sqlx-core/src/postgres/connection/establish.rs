@@ -1,20 +1,142 @@
+use std::io;
+use std::sync::Arc;
+
 use crate::HashMap;
 
+use rand::seq::SliceRandom;
+
 use crate::common::StatementCache;
+use crate::connection::Connection;
 use crate::error::Error;
+use crate::executor::Executor;
 use crate::io::Decode;
-use crate::postgres::connection::{sasl, stream::PgStream, tls};
+use crate::postgres::connection::{cancel::PendingCancel, sasl, stream::PgStream, tls};
 use crate::postgres::message::{
     Authentication, BackendKeyData, MessageFormat, Password, ReadyForQuery, Startup,
 };
 use crate::postgres::types::Oid;
-use crate::postgres::{PgConnectOptions, PgConnection};
+use crate::postgres::{
+    PgChannelBinding, PgConnectOptions, PgConnection, PgLoadBalanceHosts, PgTargetSessionAttrs,
+};
+use crate::row::Row;
 
 // https://www.postgresql.org/docs/current/protocol-flow.html#id-1.10.5.7.3
 // https://www.postgresql.org/docs/current/protocol-flow.html#id-1.10.5.7.11
 
 impl PgConnection {
     pub(crate) async fn establish(options: &PgConnectOptions) -> Result<Self, Error> {
+        if options.additional_hosts.is_empty()
+            && options.target_session_attrs == PgTargetSessionAttrs::Any
+        {
+            return Self::establish_with_host(options).await;
+        }
+
+        Self::establish_with_any_host(options).await
+    }
+
+    /// Try each of the hosts in turn, following libpq's handling of `target_session_attrs`.
+    async fn establish_with_any_host(options: &PgConnectOptions) -> Result<Self, Error> {
+        let target = options.target_session_attrs;
+        let mut hosts = options.host_list();
+
+        if options.load_balance_hosts == PgLoadBalanceHosts::Random {
+            hosts.shuffle(&mut rand::thread_rng());
+        }
+
+        let mut last_error = None;
+        let mut any_connected = false;
+
+        // with `prefer-standby`, the first server we find is kept in case there's no standby
+        let mut fallback: Option<PgConnection> = None;
+
+        for (host, port) in hosts {
+            let host_options = options.clone().host(&host).port(port);
+
+            let mut conn = match Self::establish_with_host(&host_options).await {
+                Ok(conn) => conn,
+                Err(error) => {
+                    log::debug!("failed to connect to {}:{}: {}", host, port, error);
+                    last_error = Some(error);
+                    continue;
+                }
+            };
+
+            any_connected = true;
+
+            if target == PgTargetSessionAttrs::Any {
+                return Ok(conn);
+            }
+
+            let (in_recovery, read_only) = match conn.session_state().await {
+                Ok(state) => state,
+                Err(error) => {
+                    last_error = Some(error);
+                    continue;
+                }
+            };
+
+            if target.matches(in_recovery, read_only) {
+                if let Some(fallback) = fallback {
+                    let _ = fallback.close().await;
+                }
+
+                return Ok(conn);
+            }
+
+            log::debug!(
+                "server at {}:{} does not match target_session_attrs={}",
+                host,
+                port,
+                target
+            );
+
+            if target == PgTargetSessionAttrs::PreferStandby && fallback.is_none() {
+                fallback = Some(conn);
+            } else {
+                let _ = conn.close().await;
+            }
+        }
+
+        if let Some(conn) = fallback {
+            return Ok(conn);
+        }
+
+        match last_error {
+            Some(error) if !any_connected => Err(error),
+            _ => Err(Error::Configuration(
+                format!(
+                    "could not find a server matching target_session_attrs={}",
+                    target
+                )
+                .into(),
+            )),
+        }
+    }
+
+    /// Returns whether the server is a hot standby, and whether the session is read-only.
+    async fn session_state(&mut self) -> Result<(bool, bool), Error> {
+        // a simple query avoids polluting the statement cache
+        let row = self
+            .fetch_one("SELECT pg_is_in_recovery(), current_setting('transaction_read_only')")
+            .await?;
+
+        let in_recovery: bool = row.try_get(0)?;
+        let read_only: String = row.try_get(1)?;
+
+        Ok((in_recovery, read_only == "on"))
+    }
+
+    async fn establish_with_host(options: &PgConnectOptions) -> Result<Self, Error> {
+        match options.connect_timeout {
+            Some(timeout) => sqlx_rt::timeout(timeout, Self::connect_to_host(options))
+                .await
+                .map_err(|_| Error::Io(io::ErrorKind::TimedOut.into()))?,
+
+            None => Self::connect_to_host(options).await,
+        }
+    }
+
+    async fn connect_to_host(options: &PgConnectOptions) -> Result<Self, Error> {
         let mut stream = PgStream::connect(options).await?;
 
         // Upgrade to TLS if we were asked to and the server supports it
@@ -46,6 +168,11 @@ impl PgConnection {
             params.push(("options", options));
         }
 
+        if options.replication {
+            // logical replication, connected to the database
+            params.push(("replication", "database"));
+        }
+
         stream
             .send(Startup {
                 username: Some(&options.username),
@@ -61,6 +188,7 @@ impl PgConnection {
 
         let mut process_id = 0;
         let mut secret_key = 0;
+        let mut channel_bound = false;
         let transaction_status;
 
         loop {
@@ -70,6 +198,20 @@ impl PgConnection {
                     Authentication::Ok => {
                         // the authentication exchange is successfully completed
                         // do nothing; no more information is required to continue
+
+                        if options.channel_binding == PgChannelBinding::Require && !channel_bound {
+                            return Err(err_protocol!(
+                                "channel binding required, but the server authenticated the connection without it"
+                            ));
+                        }
+                    }
+
+                    Authentication::CleartextPassword | Authentication::Md5Password(_)
+                        if options.channel_binding == PgChannelBinding::Require =>
+                    {
+                        return Err(err_protocol!(
+                            "channel binding required, but the server asked for a password without it"
+                        ));
                     }
 
                     Authentication::CleartextPassword => {
@@ -99,7 +241,7 @@ impl PgConnection {
                     }
 
                     Authentication::Sasl(body) => {
-                        sasl::authenticate(&mut stream, options, body).await?;
+                        channel_bound = sasl::authenticate(&mut stream, options, body).await?;
                     }
 
                     method => {
@@ -141,6 +283,8 @@ impl PgConnection {
             stream,
             process_id,
             secret_key,
+            cancel_options: Arc::new(options.clone()),
+            pending_cancel: PendingCancel::default(),
             transaction_status,
             transaction_depth: 0,
             pending_ready_for_query_count: 0,
@@ -2,6 +2,7 @@ use crate::describe::Describe;
 use crate::error::Error;
 use crate::executor::{Execute, Executor};
 use crate::logger::QueryLogger;
+use crate::postgres::connection::CancelOnDrop;
 use crate::postgres::message::{
     self, Bind, Close, CommandComplete, DataRow, MessageFormat, ParameterDescription, Parse, Query,
     RowDescription,
@@ -18,6 +19,7 @@ use futures_core::future::BoxFuture;
 use futures_core::stream::BoxStream;
 use futures_core::Stream;
 use futures_util::{pin_mut, TryStreamExt};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::{borrow::Cow, sync::Arc};
 
 async fn prepare(
@@ -161,7 +163,7 @@ impl PgConnection {
         self.pending_ready_for_query_count += 1;
     }
 
-    async fn get_or_prepare<'a>(
+    pub(in crate::postgres) async fn get_or_prepare<'a>(
         &mut self,
         sql: &str,
         parameters: &[PgTypeInfo],
@@ -199,6 +201,8 @@ impl PgConnection {
         limit: u8,
         persistent: bool,
         metadata_opt: Option<Arc<PgStatementMetadata>>,
+        // set once the server has finished the query
+        finished: Arc<AtomicBool>,
     ) -> Result<impl Stream<Item = Result<Either<PgQueryResult, PgRow>, Error>> + 'e, Error> {
         let mut logger = QueryLogger::new(query, self.log_settings.clone());
 
@@ -264,6 +268,8 @@ impl PgConnection {
         self.stream.flush().await?;
 
         Ok(try_stream! {
+            let mut rows_returned = 0_u64;
+
             loop {
                 let message = self.stream.recv().await?;
 
@@ -306,6 +312,16 @@ impl PgConnection {
                     MessageFormat::DataRow => {
                         logger.increment_rows_returned();
 
+                        rows_returned += 1;
+
+                        // the server stops after `limit` rows; otherwise, the end of the result
+                        // may have been received already
+                        if (limit > 0 && rows_returned >= u64::from(limit))
+                            || self.stream.is_query_finished()
+                        {
+                            finished.store(true, Ordering::Release);
+                        }
+
                         // one of the set of rows returned by a SELECT, FETCH, etc query
                         let data: DataRow = message.decode()?;
                         let row = PgRow {
@@ -354,13 +370,22 @@ impl<'c> Executor<'c> for &'c mut PgConnection {
         let persistent = query.persistent();
 
         Box::pin(try_stream! {
-            let s = self.run(sql, arguments, 0, persistent, metadata).await?;
+            // if this stream is dropped before the query completes, ask the server to cancel it
+            let mut cancel = CancelOnDrop::new(self);
+            let finished = cancel.finished();
+
+            let s = cancel.check(
+                self.run(sql, arguments, 0, persistent, metadata, finished)
+                    .await,
+            )?;
             pin_mut!(s);
 
-            while let Some(v) = s.try_next().await? {
+            while let Some(v) = cancel.check(s.try_next().await)? {
                 r#yield!(v);
             }
 
+            cancel.disarm();
+
             Ok(())
         })
     }
@@ -379,15 +404,25 @@ impl<'c> Executor<'c> for &'c mut PgConnection {
         let persistent = query.persistent();
 
         Box::pin(async move {
-            let s = self.run(sql, arguments, 1, persistent, metadata).await?;
+            let mut cancel = CancelOnDrop::new(self);
+            let finished = cancel.finished();
+
+            let s = cancel.check(
+                self.run(sql, arguments, 1, persistent, metadata, finished)
+                    .await,
+            )?;
             pin_mut!(s);
 
-            while let Some(s) = s.try_next().await? {
+            while let Some(s) = cancel.check(s.try_next().await)? {
                 if let Either::Right(r) = s {
+                    // any remaining rows are skipped by the next query
+                    cancel.disarm();
                     return Ok(Some(r));
                 }
             }
 
+            cancel.disarm();
+
             Ok(None)
         })
     }
@@ -1,5 +1,6 @@
 use std::fmt::{self, Debug, Formatter};
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::HashMap;
 use futures_core::future::BoxFuture;
@@ -8,8 +9,10 @@ use futures_util::FutureExt;
 use crate::common::StatementCache;
 use crate::connection::{Connection, LogSettings};
 use crate::error::Error;
+use crate::executor::Executor;
 use crate::ext::ustr::UStr;
 use crate::io::Decode;
+use crate::postgres::connection::cancel::PendingCancel;
 use crate::postgres::message::{
     Close, Message, MessageFormat, Query, ReadyForQuery, Terminate, TransactionStatus,
 };
@@ -18,8 +21,11 @@ use crate::postgres::types::Oid;
 use crate::postgres::{PgConnectOptions, PgTypeInfo, Postgres};
 use crate::transaction::Transaction;
 
+pub(crate) use self::cancel::CancelOnDrop;
+pub use self::cancel::PgCancelToken;
 pub use self::stream::PgStream;
 
+mod cancel;
 pub(crate) mod describe;
 mod establish;
 mod executor;
@@ -27,6 +33,9 @@ mod sasl;
 mod stream;
 mod tls;
 
+// how long to wait for the cancel request of a dropped query if `connect_timeout` isn't set
+const DEFAULT_CANCEL_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// A connection to a PostgreSQL database.
 pub struct PgConnection {
     // underlying TCP or UDS stream,
@@ -36,20 +45,25 @@ pub struct PgConnection {
 
     // process id of this backend
     // used to send cancel requests
-    #[allow(dead_code)]
     process_id: u32,
 
     // secret key of this backend
     // used to send cancel requests
-    #[allow(dead_code)]
     secret_key: u32,
 
+    // options for the host we are connected to
+    // used to open the side connection for cancel requests
+    cancel_options: Arc<PgConnectOptions>,
+
+    // cancel request sent when a query was dropped before it completed
+    pending_cancel: PendingCancel,
+
     // sequence of statement IDs for use in preparing statements
     // in PostgreSQL, the statement is prepared to a user-supplied identifier
     next_statement_id: Oid,
 
     // cache statement by query string to the id and columns
-    cache_statement: StatementCache<(Oid, Arc<PgStatementMetadata>)>,
+    pub(in crate::postgres) cache_statement: StatementCache<(Oid, Arc<PgStatementMetadata>)>,
 
     // cache user-defined types by id <-> info
     cache_type_info: HashMap<Oid, PgTypeInfo>,
@@ -62,7 +76,7 @@ pub struct PgConnection {
     transaction_status: TransactionStatus,
     pub(crate) transaction_depth: usize,
 
-    log_settings: LogSettings,
+    pub(in crate::postgres) log_settings: LogSettings,
 }
 
 impl PgConnection {
@@ -73,12 +87,43 @@ impl PgConnection {
 
     // will return when the connection is ready for another query
     pub(in crate::postgres) async fn wait_until_ready(&mut self) -> Result<(), Error> {
+        // if a query was dropped mid-flight, wait for its cancel request to be processed
+        // so that it can't hit the next query instead
+        let cancelled = match self.pending_cancel.take() {
+            Some(pending) => {
+                // don't let a cancel request that never completes hold up the connection
+                let timeout = self
+                    .cancel_options
+                    .connect_timeout
+                    .unwrap_or(DEFAULT_CANCEL_TIMEOUT);
+
+                if sqlx_rt::timeout(timeout, pending).await.is_err() {
+                    log::warn!("timed out waiting for the cancel request of a dropped query");
+                }
+
+                true
+            }
+
+            None => false,
+        };
+
         if !self.stream.wbuf.is_empty() {
             self.stream.flush().await?;
         }
 
         while self.pending_ready_for_query_count > 0 {
-            let message = self.stream.recv().await?;
+            let message = match self.stream.recv().await {
+                Ok(message) => message,
+
+                // nobody is waiting on the result of a cancelled query
+                Err(Error::Database(error))
+                    if cancelled && error.code().as_deref() == Some("57014") =>
+                {
+                    continue
+                }
+
+                Err(error) => return Err(error),
+            };
 
             if let MessageFormat::ReadyForQuery = message.format {
                 self.handle_ready_for_query(message)?;
@@ -88,7 +133,7 @@ impl PgConnection {
         Ok(())
     }
 
-    async fn recv_ready_for_query(&mut self) -> Result<(), Error> {
+    pub(in crate::postgres) async fn recv_ready_for_query(&mut self) -> Result<(), Error> {
         let r: ReadyForQuery = self
             .stream
             .recv_expect(MessageFormat::ReadyForQuery)
@@ -162,6 +207,17 @@ impl Connection for PgConnection {
         })
     }
 
+    fn reset(&mut self) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            self.execute("DISCARD ALL").await?;
+
+            // `DISCARD ALL` deallocates every prepared statement on the server
+            self.cache_statement.clear();
+
+            Ok(())
+        })
+    }
+
     fn begin(&mut self) -> BoxFuture<'_, Result<Transaction<'_, Self::Database>, Error>>
     where
         Self: Sized,
@@ -3,13 +3,16 @@ use crate::postgres::connection::stream::PgStream;
 use crate::postgres::message::{
     Authentication, AuthenticationSasl, MessageFormat, SaslInitialResponse, SaslResponse,
 };
-use crate::postgres::PgConnectOptions;
+use crate::postgres::{PgChannelBinding, PgConnectOptions};
 use hmac::{Hmac, Mac};
 use rand::Rng;
-use sha2::{Digest, Sha256};
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
 use stringprep::saslprep;
 
 const GS2_HEADER: &str = "n,,";
+// the client supports channel binding, but the server didn't offer it
+const GS2_HEADER_UNSUPPORTED_BY_SERVER: &str = "y,,";
+const GS2_HEADER_TLS_SERVER_END_POINT: &str = "p=tls-server-end-point,,";
 const CHANNEL_ATTR: &str = "c";
 const USERNAME_ATTR: &str = "n";
 const CLIENT_PROOF_ATTR: &str = "p";
@@ -19,7 +22,7 @@ pub(crate) async fn authenticate(
     stream: &mut PgStream,
     options: &PgConnectOptions,
     data: AuthenticationSasl,
-) -> Result<(), Error> {
+) -> Result<bool, Error> {
     let mut has_sasl = false;
     let mut has_sasl_plus = false;
     let mut unknown = Vec::new();
@@ -47,8 +50,48 @@ pub(crate) async fn authenticate(
         ));
     }
 
-    // channel-binding = "c=" base64
-    let channel_binding = format!("{}={}", CHANNEL_ATTR, base64::encode(GS2_HEADER));
+    let binding = options.channel_binding;
+
+    // https://datatracker.ietf.org/doc/html/rfc5929#section-4
+    let server_end_point = match stream.server_certificate()? {
+        Some(cert) if has_sasl_plus && binding != PgChannelBinding::Disable => {
+            tls_server_end_point(&cert)
+        }
+
+        _ => None,
+    };
+
+    let (gs2_header, cbind_data) = match server_end_point {
+        Some(hash) => (GS2_HEADER_TLS_SERVER_END_POINT, hash),
+
+        None if binding == PgChannelBinding::Require => {
+            return Err(err_protocol!(
+                "channel binding required, but {}",
+                if !stream.is_tls() {
+                    "the connection is not encrypted"
+                } else if !has_sasl_plus {
+                    "the server does not support it"
+                } else {
+                    "the signature algorithm of the server certificate is not supported"
+                }
+            ));
+        }
+
+        None if stream.is_tls() && !has_sasl_plus && binding == PgChannelBinding::Prefer => {
+            (GS2_HEADER_UNSUPPORTED_BY_SERVER, Vec::new())
+        }
+
+        None => (GS2_HEADER, Vec::new()),
+    };
+
+    let plus = gs2_header == GS2_HEADER_TLS_SERVER_END_POINT;
+
+    // channel-binding = "c=" base64(gs2-header [cbind-data])
+    let channel_binding = format!(
+        "{}={}",
+        CHANNEL_ATTR,
+        base64::encode([gs2_header.as_bytes(), &cbind_data].concat())
+    );
 
     // "n=" saslname ;; Usernames are prepared using SASLprep.
     let username = format!("{}={}", USERNAME_ATTR, options.username);
@@ -67,14 +110,14 @@ pub(crate) async fn authenticate(
 
     let client_first_message = format!(
         "{gs2_header}{client_first_message_bare}",
-        gs2_header = GS2_HEADER,
+        gs2_header = gs2_header,
         client_first_message_bare = client_first_message_bare
     );
 
     stream
         .send(SaslInitialResponse {
             response: &client_first_message,
-            plus: false,
+            plus,
         })
         .await?;
 
@@ -164,7 +207,92 @@ pub(crate) async fn authenticate(
     // authentication is only considered valid if this verification passes
     mac.verify_slice(&data.verifier).map_err(Error::protocol)?;
 
-    Ok(())
+    Ok(plus)
+}
+
+// the `tls-server-end-point` channel binding data: the certificate hashed with the hash function
+// of its signature algorithm, or SHA-256 if that is MD5 or SHA-1
+fn tls_server_end_point(cert: &[u8]) -> Option<Vec<u8>> {
+    // 1.2.840.113549.1.1.*
+    const RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01];
+    // 1.2.840.10045.4.*
+    const ECDSA: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04];
+
+    let hash = match signature_algorithm(cert)? {
+        // md5WithRSAEncryption, sha1WithRSAEncryption, sha256WithRSAEncryption
+        [rsa @ .., 0x04 | 0x05 | 0x0b] if rsa == RSA => Sha256::digest(cert).to_vec(),
+        // sha384WithRSAEncryption
+        [rsa @ .., 0x0c] if rsa == RSA => Sha384::digest(cert).to_vec(),
+        // sha512WithRSAEncryption
+        [rsa @ .., 0x0d] if rsa == RSA => Sha512::digest(cert).to_vec(),
+        // sha224WithRSAEncryption
+        [rsa @ .., 0x0e] if rsa == RSA => Sha224::digest(cert).to_vec(),
+
+        // ecdsa-with-SHA1
+        [ecdsa @ .., 0x01] if ecdsa == ECDSA => Sha256::digest(cert).to_vec(),
+        // ecdsa-with-SHA224, ecdsa-with-SHA256, ecdsa-with-SHA384, ecdsa-with-SHA512
+        [ecdsa @ .., 0x03, 0x01] if ecdsa == ECDSA => Sha224::digest(cert).to_vec(),
+        [ecdsa @ .., 0x03, 0x02] if ecdsa == ECDSA => Sha256::digest(cert).to_vec(),
+        [ecdsa @ .., 0x03, 0x03] if ecdsa == ECDSA => Sha384::digest(cert).to_vec(),
+        [ecdsa @ .., 0x03, 0x04] if ecdsa == ECDSA => Sha512::digest(cert).to_vec(),
+
+        // e.g. RSASSA-PSS or Ed25519, which Postgres can't bind to either
+        _ => return None,
+    };
+
+    Some(hash)
+}
+
+// Certificate ::= SEQUENCE {
+//     tbsCertificate       TBSCertificate,
+//     signatureAlgorithm   AlgorithmIdentifier,
+//     signatureValue       BIT STRING }
+//
+// AlgorithmIdentifier ::= SEQUENCE {
+//     algorithm            OBJECT IDENTIFIER,
+//     parameters           ANY DEFINED BY algorithm OPTIONAL }
+fn signature_algorithm(cert: &[u8]) -> Option<&[u8]> {
+    const SEQUENCE: u8 = 0x30;
+    const OBJECT_IDENTIFIER: u8 = 0x06;
+
+    let (certificate, _) = der_read(cert, SEQUENCE)?;
+    let (_, rest) = der_read(certificate, SEQUENCE)?;
+    let (algorithm_identifier, _) = der_read(rest, SEQUENCE)?;
+    let (algorithm, _) = der_read(algorithm_identifier, OBJECT_IDENTIFIER)?;
+
+    Some(algorithm)
+}
+
+// reads a DER value with the given tag, returning its contents and whatever follows it
+fn der_read(buf: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (&actual, buf) = buf.split_first()?;
+    let (&len, mut buf) = buf.split_first()?;
+
+    if actual != tag {
+        return None;
+    }
+
+    let len = if len & 0x80 == 0 {
+        usize::from(len)
+    } else {
+        // the long form gives the number of bytes in the length
+        let n = usize::from(len & 0x7f);
+
+        if n > std::mem::size_of::<usize>() || buf.len() < n {
+            return None;
+        }
+
+        let (len, rest) = buf.split_at(n);
+        buf = rest;
+
+        len.iter().fold(0, |len, &b| (len << 8) | usize::from(b))
+    };
+
+    if buf.len() < len {
+        return None;
+    }
+
+    Some(buf.split_at(len))
 }
 
 // nonce is a sequence of random printable bytes
@@ -213,3 +341,41 @@ fn hi<'a>(s: &'a str, salt: &'a [u8], iter_count: u32) -> Result<[u8; 32], Error
 
     Ok(hi.into())
 }
+
+#[test]
+fn test_tls_server_end_point() {
+    fn cert(algorithm: &[u8]) -> Vec<u8> {
+        let mut algorithm_identifier = vec![0x06, algorithm.len() as u8];
+        algorithm_identifier.extend_from_slice(algorithm);
+        // NULL parameters
+        algorithm_identifier.extend_from_slice(&[0x05, 0x00]);
+
+        // an empty TBSCertificate, the algorithm and an empty signature
+        let mut certificate = vec![0x30, 0x00, 0x30, algorithm_identifier.len() as u8];
+        certificate.extend(algorithm_identifier);
+        certificate.extend_from_slice(&[0x03, 0x01, 0x00]);
+
+        let mut cert = vec![0x30, certificate.len() as u8];
+        cert.extend(certificate);
+        cert
+    }
+
+    const SHA256_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+    const SHA1_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x05];
+    const ECDSA_WITH_SHA384: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x03];
+    const ED25519: &[u8] = &[0x2b, 0x65, 0x70];
+
+    let c = cert(SHA256_WITH_RSA);
+    assert_eq!(signature_algorithm(&c), Some(SHA256_WITH_RSA));
+    assert_eq!(tls_server_end_point(&c), Some(Sha256::digest(&c).to_vec()));
+
+    let c = cert(SHA1_WITH_RSA);
+    assert_eq!(tls_server_end_point(&c), Some(Sha256::digest(&c).to_vec()));
+
+    let c = cert(ECDSA_WITH_SHA384);
+    assert_eq!(tls_server_end_point(&c), Some(Sha384::digest(&c).to_vec()));
+
+    assert_eq!(tls_server_end_point(&cert(ED25519)), None);
+    assert_eq!(tls_server_end_point(&[0x30, 0x81]), None);
+    assert_eq!(tls_server_end_point(b""), None);
+}
@@ -4,7 +4,7 @@ use std::str::FromStr;
 
 use bytes::{Buf, Bytes};
 use futures_channel::mpsc::UnboundedSender;
-use futures_util::SinkExt;
+use futures_util::{FutureExt, SinkExt};
 use log::Level;
 
 use crate::error::Error;
@@ -81,16 +81,36 @@ impl PgStream {
     pub(crate) async fn recv_unchecked(&mut self) -> Result<Message, Error> {
         // all packets in postgres start with a 5-byte header
         // this header contains the message type and the total length of the message
-        let mut header: Bytes = self.inner.read(5).await?;
+        let mut header = self.inner.peek(5).await?;
 
         let format = MessageFormat::try_from_u8(header.get_u8())?;
         let size = (header.get_u32() - 4) as usize;
 
-        let contents = self.inner.read(size).await?;
+        // the header is only consumed along with the rest of the message, so if this future
+        // is dropped part-way through nothing is lost and the next call picks up where it left off
+        let mut contents: Bytes = self.inner.read(5 + size).await?;
+        contents.advance(5);
 
         Ok(Message { format, contents })
     }
 
+    // Whether the server is known to have finished the current query, i.e. the next message is
+    // [ReadyForQuery], or [CommandComplete] followed by it. This never waits for the server;
+    // messages that have already arrived are peeked at, which leaves them to be received as usual.
+    pub(crate) fn is_query_finished(&mut self) -> bool {
+        let len = match self.inner.peek(5).now_or_never() {
+            Some(Ok(header)) if header[0] == b'Z' => return true,
+            Some(Ok(header)) if header[0] == b'C' => {
+                // the length doesn't include the message type
+                1 + u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize
+            }
+
+            _ => return false,
+        };
+
+        matches!(self.inner.peek(len + 5).now_or_never(), Some(Ok(buf)) if buf[len] == b'Z')
+    }
+
     // Get the next message from the server
     // May wait for more data from the server
     pub(crate) async fn recv(&mut self) -> Result<Message, Error> {
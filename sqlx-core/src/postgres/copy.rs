@@ -1,16 +1,30 @@
+use crate::encode::Encode;
 use crate::error::{Error, Result};
 use crate::ext::async_stream::TryAsyncStream;
+use crate::from_row::FromRow;
+use crate::io::Decode;
 use crate::pool::{Pool, PoolConnection};
 use crate::postgres::connection::PgConnection;
 use crate::postgres::message::{
-    CommandComplete, CopyData, CopyDone, CopyFail, CopyResponse, MessageFormat, Query,
+    Close, CommandComplete, CopyData, CopyDone, CopyFail, CopyResponse, DataRow, MessageFormat,
+    Query,
 };
-use crate::postgres::Postgres;
-use bytes::{BufMut, Bytes};
+use crate::postgres::{PgArgumentBuffer, PgRow, PgValueFormat, Postgres};
+use byteorder::{BigEndian, ByteOrder};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use futures_core::stream::BoxStream;
+use futures_util::TryStreamExt;
 use smallvec::alloc::borrow::Cow;
 use sqlx_rt::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use std::cmp;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+// https://www.postgresql.org/docs/current/sql-copy.html#id-1.9.3.55.9.4
+const BINARY_SIGNATURE: &[u8] = b"PGCOPY\n\xff\r\n\0";
+
+// rows written with `PgCopyIn::write_row` are sent once this much data is buffered
+const ROW_BUFFER_SIZE: usize = 64 * 1024;
 
 impl PgConnection {
     /// Issue a `COPY FROM STDIN` statement and transition the connection to streaming data
@@ -56,6 +70,23 @@ impl PgConnection {
     ) -> Result<BoxStream<'c, Result<Bytes>>> {
         pg_begin_copy_out(self, statement).await
     }
+
+    /// Copy the results of `query` out of Postgres using binary `COPY`, decoding each row as `T`.
+    ///
+    /// `query` is anything that `COPY (...) TO STDOUT` accepts, such as a `SELECT`, `VALUES` or
+    /// `TABLE` command; it may not have bind parameters. It is prepared first to find the names
+    /// and types of its columns, so `T` can be any [`FromRow`] type, just as with
+    /// [`query_as`][crate::query_as::query_as].
+    ///
+    /// As with [`copy_out_raw`][Self::copy_out_raw], if you don't read the stream to completion,
+    /// the next time the connection is used it will need to read and discard the remaining data.
+    #[allow(clippy::needless_lifetimes)]
+    pub async fn copy_out<'c, T>(&'c mut self, query: &str) -> Result<BoxStream<'c, Result<T>>>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin + 'c,
+    {
+        pg_begin_copy_out_rows(self, query).await
+    }
 }
 
 impl Pool<Postgres> {
@@ -100,6 +131,18 @@ impl Pool<Postgres> {
     pub async fn copy_out_raw(&self, statement: &str) -> Result<BoxStream<'static, Result<Bytes>>> {
         pg_begin_copy_out(self.acquire().await?, statement).await
     }
+
+    /// Copy the results of `query` out of Postgres using binary `COPY`, decoding each row as `T`.
+    ///
+    /// A single connection will be checked out for the duration.
+    ///
+    /// See [`PgConnection::copy_out`] for details.
+    pub async fn copy_out<T>(&self, query: &str) -> Result<BoxStream<'static, Result<T>>>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin + 'static,
+    {
+        pg_begin_copy_out_rows(self.acquire().await?, query).await
+    }
 }
 
 /// A connection in streaming `COPY FROM STDIN` mode.
@@ -113,6 +156,9 @@ impl Pool<Postgres> {
 pub struct PgCopyIn<C: DerefMut<Target = PgConnection>> {
     conn: Option<C>,
     response: CopyResponse,
+    // rows from `write_row` that haven't been sent yet
+    row_buf: PgArgumentBuffer,
+    wrote_header: bool,
 }
 
 impl<C: DerefMut<Target = PgConnection>> PgCopyIn<C> {
@@ -128,6 +174,8 @@ impl<C: DerefMut<Target = PgConnection>> PgCopyIn<C> {
         Ok(PgCopyIn {
             conn: Some(conn),
             response,
+            row_buf: PgArgumentBuffer::default(),
+            wrote_header: false,
         })
     }
 
@@ -154,10 +202,81 @@ impl<C: DerefMut<Target = PgConnection>> PgCopyIn<C> {
         self.response.format_codes[column] == 0
     }
 
+    /// Encode a row in the binary `COPY` format and queue it to be sent.
+    ///
+    /// `row` may be a tuple or a struct that derives `Encode`, whose fields each implement
+    /// [`Encode<Postgres>`][Encode]; see [`PgCopyRow`]. The statement must have been issued with
+    /// `(FORMAT binary)` and the row must have a field for every column being copied.
+    ///
+    /// Rows are buffered and sent in batches; the last batch is sent by [Self::finish].
+    ///
+    /// ### Note
+    /// The server does not tell us the types of the columns, so `Json<T>` values are always
+    /// encoded as `JSONB`. Values of custom types (or arrays of them) can only be encoded once
+    /// this connection has looked up the type, e.g. by having already used it in a query.
+    pub async fn write_row<R: PgCopyRow>(&mut self, row: R) -> Result<&mut Self> {
+        if self.is_textual() {
+            return Err(err_protocol!(
+                "`write_row` requires a `COPY ... FROM STDIN` statement with `(FORMAT binary)`"
+            ));
+        }
+
+        if !self.wrote_header {
+            self.row_buf.extend_from_slice(BINARY_SIGNATURE);
+            // flags field
+            self.row_buf.extend(&0_i32.to_be_bytes());
+            // header extension area length
+            self.row_buf.extend(&0_i32.to_be_bytes());
+
+            self.wrote_header = true;
+        }
+
+        let offset = self.row_buf.len();
+        let mut encoder = PgCopyRowEncoder::new(&mut self.row_buf);
+
+        row.encode_row(&mut encoder);
+
+        let num_fields = encoder.finish();
+
+        if num_fields != self.response.num_columns as usize {
+            self.row_buf.truncate(offset);
+
+            return Err(err_protocol!(
+                "row has {} fields but COPY expects {} columns",
+                num_fields,
+                self.response.num_columns
+            ));
+        }
+
+        if self.row_buf.len() >= ROW_BUFFER_SIZE {
+            self.send_rows().await?;
+        }
+
+        Ok(self)
+    }
+
+    // send any rows buffered by `write_row`
+    async fn send_rows(&mut self) -> Result<()> {
+        if self.row_buf.is_empty() {
+            return Ok(());
+        }
+
+        let conn: &mut PgConnection = self.conn.as_deref_mut().expect("send_rows: conn taken");
+
+        self.row_buf.apply_cached_type_holes(conn)?;
+        conn.stream.send(CopyData(&**self.row_buf)).await?;
+
+        self.row_buf.truncate(0);
+
+        Ok(())
+    }
+
     /// Send a chunk of `COPY` data.
     ///
     /// If you're copying data from an `AsyncRead`, maybe consider [Self::read_from] instead.
     pub async fn send(&mut self, data: impl Deref<Target = [u8]>) -> Result<&mut Self> {
+        self.send_rows().await?;
+
         self.conn
             .as_deref_mut()
             .expect("send_data: conn taken")
@@ -184,6 +303,8 @@ impl<C: DerefMut<Target = PgConnection>> PgCopyIn<C> {
             }
         }
 
+        self.send_rows().await?;
+
         let conn: &mut PgConnection = self.conn.as_deref_mut().expect("copy_from: conn taken");
 
         // flush any existing messages in the buffer and clear it
@@ -272,6 +393,13 @@ impl<C: DerefMut<Target = PgConnection>> PgCopyIn<C> {
     ///
     /// The number of rows affected is returned.
     pub async fn finish(mut self) -> Result<u64> {
+        if self.wrote_header {
+            // file trailer
+            self.row_buf.extend(&(-1_i16).to_be_bytes());
+        }
+
+        self.send_rows().await?;
+
         let mut conn = self
             .conn
             .take()
@@ -306,7 +434,11 @@ async fn pg_begin_copy_out<'c, C: DerefMut<Target = PgConnection> + Send + 'c>(
     statement: &str,
 ) -> Result<BoxStream<'c, Result<Bytes>>> {
     conn.wait_until_ready().await?;
-    conn.stream.send(Query(statement)).await?;
+
+    // if the stream is dropped early, the rest of the data is discarded
+    // the next time the connection is used
+    conn.queue_simple_query(statement);
+    conn.stream.flush().await?;
 
     let _: CopyResponse = conn
         .stream
@@ -321,7 +453,7 @@ async fn pg_begin_copy_out<'c, C: DerefMut<Target = PgConnection> + Send + 'c>(
                 MessageFormat::CopyDone => {
                     let _ = msg.decode::<CopyDone>()?;
                     conn.stream.recv_expect(MessageFormat::CommandComplete).await?;
-                    conn.stream.recv_expect(MessageFormat::ReadyForQuery).await?;
+                    conn.recv_ready_for_query().await?;
                     return Ok(())
                 },
                 _ => return Err(err_protocol!("unexpected message format during copy out: {:?}", msg.format))
@@ -331,3 +463,263 @@ async fn pg_begin_copy_out<'c, C: DerefMut<Target = PgConnection> + Send + 'c>(
 
     Ok(Box::pin(stream))
 }
+
+async fn pg_begin_copy_out_rows<'c, C, T>(
+    mut conn: C,
+    query: &str,
+) -> Result<BoxStream<'c, Result<T>>>
+where
+    C: DerefMut<Target = PgConnection> + Send + 'c,
+    T: for<'r> FromRow<'r, PgRow> + Send + Unpin + 'c,
+{
+    conn.wait_until_ready().await?;
+
+    // `COPY` doesn't describe its output, so prepare the query to learn the column types
+    let cached = conn.cache_statement.contains_key(query);
+    let (statement, metadata) = conn.get_or_prepare(query, &[], false, None).await?;
+
+    if !cached {
+        // the statement is only needed for its description; it's closed before the `COPY` runs
+        conn.stream.write(Close::Statement(statement));
+        conn.write_sync();
+    }
+
+    let statement = format!("COPY ({}) TO STDOUT (FORMAT binary)", query);
+    let mut data = pg_begin_copy_out(conn, &statement).await?;
+
+    let stream: TryAsyncStream<'c, T> = try_stream! {
+        let mut reader = BinaryCopyReader::default();
+
+        while let Some(chunk) = data.try_next().await? {
+            reader.buf.extend_from_slice(&chunk);
+
+            while let Some(tuple) = reader.next_tuple()? {
+                let row = PgRow {
+                    data: DataRow::decode(tuple)?,
+                    format: PgValueFormat::Binary,
+                    metadata: Arc::clone(&metadata),
+                };
+
+                r#yield!(T::from_row(&row)?);
+            }
+        }
+
+        if !reader.finished {
+            return Err(err_protocol!("binary COPY data ended without a trailer"));
+        }
+
+        Ok(())
+    };
+
+    Ok(Box::pin(stream))
+}
+
+/// A row that can be written with [`PgCopyIn::write_row`].
+///
+/// This is implemented for tuples of up to 16 elements and by `#[derive(Encode)]` on structs
+/// with named fields, writing the fields in the order they are declared.
+pub trait PgCopyRow {
+    fn encode_row(&self, encoder: &mut PgCopyRowEncoder<'_>);
+}
+
+impl<R: PgCopyRow + ?Sized> PgCopyRow for &'_ R {
+    fn encode_row(&self, encoder: &mut PgCopyRowEncoder<'_>) {
+        (**self).encode_row(encoder)
+    }
+}
+
+/// Encodes the fields of a [`PgCopyRow`] as a tuple in the binary `COPY` format.
+pub struct PgCopyRowEncoder<'a> {
+    buf: &'a mut PgArgumentBuffer,
+    off: usize,
+    num: u16,
+}
+
+impl<'a> PgCopyRowEncoder<'a> {
+    fn new(buf: &'a mut PgArgumentBuffer) -> Self {
+        let off = buf.len();
+
+        // reserve space for a field count
+        buf.extend(&0_u16.to_be_bytes());
+
+        Self { buf, off, num: 0 }
+    }
+
+    /// Encode the next field of the row.
+    pub fn encode<'q, T>(&mut self, value: T) -> &mut Self
+    where
+        T: Encode<'q, Postgres>,
+    {
+        self.buf.encode(value);
+        self.num += 1;
+
+        self
+    }
+
+    fn finish(self) -> usize {
+        // fill in the field count
+        self.buf[self.off..(self.off + 2)].copy_from_slice(&self.num.to_be_bytes());
+        self.num as usize
+    }
+}
+
+macro_rules! impl_copy_row_for_tuple {
+    ($( $idx:tt : $T:ident ),+) => {
+        impl<$($T,)+> PgCopyRow for ($($T,)+)
+        where
+            $($T: for<'q> Encode<'q, Postgres>,)+
+        {
+            fn encode_row(&self, encoder: &mut PgCopyRowEncoder<'_>) {
+                $(encoder.encode(&self.$idx);)+
+            }
+        }
+    };
+}
+
+impl_copy_row_for_tuple!(0: T1);
+impl_copy_row_for_tuple!(0: T1, 1: T2);
+impl_copy_row_for_tuple!(0: T1, 1: T2, 2: T3);
+impl_copy_row_for_tuple!(0: T1, 1: T2, 2: T3, 3: T4);
+impl_copy_row_for_tuple!(0: T1, 1: T2, 2: T3, 3: T4, 4: T5);
+impl_copy_row_for_tuple!(0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6);
+impl_copy_row_for_tuple!(0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7);
+impl_copy_row_for_tuple!(0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8);
+impl_copy_row_for_tuple!(0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8, 8: T9);
+impl_copy_row_for_tuple!(0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8, 8: T9, 9: T10);
+impl_copy_row_for_tuple!(
+    0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8, 8: T9, 9: T10, 10: T11
+);
+impl_copy_row_for_tuple!(
+    0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8, 8: T9, 9: T10, 10: T11, 11: T12
+);
+impl_copy_row_for_tuple!(
+    0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8, 8: T9, 9: T10, 10: T11, 11: T12,
+    12: T13
+);
+impl_copy_row_for_tuple!(
+    0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8, 8: T9, 9: T10, 10: T11, 11: T12,
+    12: T13, 13: T14
+);
+impl_copy_row_for_tuple!(
+    0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8, 8: T9, 9: T10, 10: T11, 11: T12,
+    12: T13, 13: T14, 14: T15
+);
+impl_copy_row_for_tuple!(
+    0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8, 8: T9, 9: T10, 10: T11, 11: T12,
+    12: T13, 13: T14, 14: T15, 15: T16
+);
+
+// Splits binary `COPY` data into tuples. Each tuple has the same layout as a `DataRow` message.
+#[derive(Default)]
+struct BinaryCopyReader {
+    buf: BytesMut,
+    read_header: bool,
+    finished: bool,
+}
+
+impl BinaryCopyReader {
+    // returns the next complete tuple, or `None` if more data is needed
+    fn next_tuple(&mut self) -> Result<Option<Bytes>> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        if !self.read_header {
+            // signature, flags field and header extension area length
+            let header_len = BINARY_SIGNATURE.len() + 8;
+
+            if self.buf.len() < header_len {
+                return Ok(None);
+            }
+
+            if !self.buf.starts_with(BINARY_SIGNATURE) {
+                return Err(err_protocol!("invalid binary COPY signature"));
+            }
+
+            let extension_len = BigEndian::read_u32(&self.buf[(header_len - 4)..]) as usize;
+
+            if self.buf.len() < header_len + extension_len {
+                return Ok(None);
+            }
+
+            self.buf.advance(header_len + extension_len);
+            self.read_header = true;
+        }
+
+        if self.buf.len() < 2 {
+            return Ok(None);
+        }
+
+        let num_fields = match BigEndian::read_i16(&self.buf) {
+            -1 => {
+                // file trailer
+                self.buf.advance(2);
+                self.finished = true;
+
+                return Ok(None);
+            }
+
+            num if num < 0 => {
+                return Err(err_protocol!("invalid binary COPY field count: {}", num));
+            }
+
+            num => num as usize,
+        };
+
+        let mut len = 2;
+
+        for _ in 0..num_fields {
+            if self.buf.len() < len + 4 {
+                return Ok(None);
+            }
+
+            // -1 indicates NULL, with no value bytes following
+            let field_len = BigEndian::read_i32(&self.buf[len..]);
+            len += 4 + cmp::max(field_len, 0) as usize;
+        }
+
+        if self.buf.len() < len {
+            return Ok(None);
+        }
+
+        Ok(Some(self.buf.split_to(len).freeze()))
+    }
+}
+
+#[test]
+fn test_encode_copy_row() {
+    let mut buf = PgArgumentBuffer::default();
+    let mut encoder = PgCopyRowEncoder::new(&mut buf);
+
+    (1_i32, None::<i32>, "a").encode_row(&mut encoder);
+    assert_eq!(encoder.finish(), 3);
+
+    assert_eq!(
+        &**buf,
+        b"\x00\x03\x00\x00\x00\x04\x00\x00\x00\x01\xff\xff\xff\xff\x00\x00\x00\x01a"
+    );
+}
+
+#[test]
+fn test_read_binary_copy() {
+    let mut reader = BinaryCopyReader::default();
+
+    reader.buf.extend_from_slice(BINARY_SIGNATURE);
+    reader
+        .buf
+        .extend_from_slice(b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x02");
+
+    // incomplete tuple
+    assert!(reader.next_tuple().unwrap().is_none());
+
+    reader
+        .buf
+        .extend_from_slice(b"\x00\x00\x00\x01a\xff\xff\xff\xff\xff\xff");
+
+    let tuple = DataRow::decode(reader.next_tuple().unwrap().unwrap()).unwrap();
+    assert_eq!(tuple.get(0), Some(&b"a"[..]));
+    assert_eq!(tuple.get(1), None);
+
+    assert!(reader.next_tuple().unwrap().is_none());
+    assert!(reader.finished);
+}
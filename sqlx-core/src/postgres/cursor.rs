@@ -0,0 +1,343 @@
+use std::fmt::{self, Display, Formatter};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use futures_core::stream::BoxStream;
+
+use crate::error::Result;
+use crate::executor::{Execute, Executor};
+use crate::logger::QueryLogger;
+use crate::postgres::message::{self, Bind, CommandComplete, DataRow, MessageFormat};
+use crate::postgres::{PgArguments, PgConnection, PgRow, PgValueFormat, Postgres};
+use crate::query::Query;
+use crate::transaction::Transaction;
+
+// used to give every cursor declared by this process a unique name
+static NEXT_CURSOR_ID: AtomicU32 = AtomicU32::new(1);
+
+/// A server-side cursor, declared with [`DECLARE`] inside a transaction.
+///
+/// Instead of sending the whole result of the query at once, the server keeps the cursor open
+/// and returns rows as they are fetched, so very large results can be processed in chunks
+/// without holding them all in memory.
+///
+/// ```rust,no_run
+/// # use sqlx_core::error::Error;
+/// # use sqlx_core::postgres::PgConnection;
+/// # async fn example(conn: &mut PgConnection) -> Result<(), Error> {
+/// use sqlx_core::connection::Connection;
+/// use sqlx_core::postgres::PgCursor;
+/// use sqlx_core::query::query;
+///
+/// let mut tx = conn.begin().await?;
+/// let mut cursor = PgCursor::declare(&mut tx, query("SELECT * FROM huge_table")).await?;
+///
+/// loop {
+///     let rows = cursor.fetch_next(1000).await?;
+///
+///     if rows.is_empty() {
+///         break;
+///     }
+///
+///     // process the rows
+/// }
+///
+/// cursor.close().await?;
+/// tx.commit().await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The cursor is closed automatically when the transaction ends; [`close`](Self::close)
+/// closes it early.
+///
+/// [`DECLARE`]: https://www.postgresql.org/docs/current/sql-declare.html
+pub struct PgCursor<'c> {
+    conn: &'c mut PgConnection,
+    name: String,
+}
+
+/// The rows a [`PgCursor`] fetches or moves over, relative to its current position.
+///
+/// See [`FETCH`] for the exact semantics of each direction.
+///
+/// [`FETCH`]: https://www.postgresql.org/docs/current/sql-fetch.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PgCursorDirection {
+    /// The next row.
+    Next,
+
+    /// The previous row.
+    Prior,
+
+    /// The first row.
+    First,
+
+    /// The last row.
+    Last,
+
+    /// The row at the given position. Negative positions count from the end.
+    Absolute(i64),
+
+    /// The row the given number of rows after the current one, or before it if negative.
+    Relative(i64),
+
+    /// The next `n` rows.
+    Forward(u64),
+
+    /// All remaining rows.
+    ForwardAll,
+
+    /// The previous `n` rows, in reverse order.
+    Backward(u64),
+
+    /// All previous rows, in reverse order.
+    BackwardAll,
+}
+
+impl Display for PgCursorDirection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PgCursorDirection::Next => f.write_str("NEXT"),
+            PgCursorDirection::Prior => f.write_str("PRIOR"),
+            PgCursorDirection::First => f.write_str("FIRST"),
+            PgCursorDirection::Last => f.write_str("LAST"),
+            PgCursorDirection::Absolute(n) => write!(f, "ABSOLUTE {}", n),
+            PgCursorDirection::Relative(n) => write!(f, "RELATIVE {}", n),
+            PgCursorDirection::Forward(n) => write!(f, "FORWARD {}", n),
+            PgCursorDirection::ForwardAll => f.write_str("FORWARD ALL"),
+            PgCursorDirection::Backward(n) => write!(f, "BACKWARD {}", n),
+            PgCursorDirection::BackwardAll => f.write_str("BACKWARD ALL"),
+        }
+    }
+}
+
+impl<'c> PgCursor<'c> {
+    /// Declare a cursor over the results of `query`.
+    ///
+    /// The cursor can only move forward; use [`declare_scroll`](Self::declare_scroll) to also
+    /// move backward.
+    pub async fn declare<'q>(
+        tx: &'c mut Transaction<'_, Postgres>,
+        query: Query<'q, Postgres, PgArguments>,
+    ) -> Result<PgCursor<'c>> {
+        Self::declare_with(tx, query, "NO SCROLL").await
+    }
+
+    /// Declare a cursor over the results of `query` that can move in any direction.
+    ///
+    /// Depending on the query plan, this may require the server to keep a copy of the rows
+    /// already fetched.
+    pub async fn declare_scroll<'q>(
+        tx: &'c mut Transaction<'_, Postgres>,
+        query: Query<'q, Postgres, PgArguments>,
+    ) -> Result<PgCursor<'c>> {
+        Self::declare_with(tx, query, "SCROLL").await
+    }
+
+    async fn declare_with<'q>(
+        conn: &'c mut PgConnection,
+        mut query: Query<'q, Postgres, PgArguments>,
+        scroll: &str,
+    ) -> Result<PgCursor<'c>> {
+        let name = format!(
+            "sqlx_cursor_{}",
+            NEXT_CURSOR_ID.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let sql = format!("DECLARE {} {} CURSOR FOR {}", name, scroll, query.sql());
+        let arguments = query.take_arguments().unwrap_or_default();
+
+        crate::query::query_with(&sql, arguments)
+            .persistent(false)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(PgCursor { conn, name })
+    }
+
+    /// The name of the cursor, e.g. for use in `WHERE CURRENT OF`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Fetch up to the next `n` rows.
+    ///
+    /// Returns an empty `Vec` once the cursor is exhausted.
+    pub async fn fetch_next(&mut self, n: u64) -> Result<Vec<PgRow>> {
+        self.fetch(PgCursorDirection::Forward(n)).await
+    }
+
+    /// Fetch the rows in the given direction and leave the cursor on the last one.
+    pub async fn fetch(&mut self, direction: PgCursorDirection) -> Result<Vec<PgRow>> {
+        let sql = format!("FETCH {} FROM {}", direction, self.name);
+
+        crate::query::query(&sql)
+            .persistent(false)
+            .fetch_all(&mut *self.conn)
+            .await
+    }
+
+    /// Move the cursor as [`fetch`](Self::fetch) would, without returning any rows.
+    ///
+    /// Returns the number of rows that would have been fetched.
+    pub async fn seek(&mut self, direction: PgCursorDirection) -> Result<u64> {
+        let sql = format!("MOVE {} FROM {}", direction, self.name);
+
+        let done = crate::query::query(&sql)
+            .persistent(false)
+            .execute(&mut *self.conn)
+            .await?;
+
+        Ok(done.rows_affected())
+    }
+
+    /// Close the cursor, freeing its resources on the server.
+    ///
+    /// This is done automatically when the transaction ends.
+    pub async fn close(self) -> Result<()> {
+        let sql = format!("CLOSE {}", self.name);
+
+        self.conn.execute(&*sql).await?;
+
+        Ok(())
+    }
+}
+
+impl<'q> Query<'q, Postgres, PgArguments> {
+    /// Execute the query and return the rows as a stream, fetching them from the server
+    /// `batch_size` rows at a time.
+    ///
+    /// Normally the server sends every row of the result as fast as it can, whether or not the
+    /// stream is being read. Here the rows are read through a portal with a row limit: the
+    /// server stops after each batch and waits to be asked for the next one, so memory use
+    /// stays bounded however large the result is. A `batch_size` of `0` fetches all rows at
+    /// once.
+    ///
+    /// Unlike a [`PgCursor`], this doesn't need an explicit transaction. The query runs in an
+    /// implicit transaction until the stream ends, so avoid keeping it open for long.
+    pub fn fetch_in_batches<'e, 'c: 'e>(
+        mut self,
+        conn: &'c mut PgConnection,
+        batch_size: u32,
+    ) -> BoxStream<'e, Result<PgRow>>
+    where
+        'q: 'e,
+    {
+        let sql = self.sql();
+        let metadata = self.statement().map(|s| Arc::clone(&s.metadata));
+        let mut arguments = self.take_arguments().unwrap_or_default();
+        let persistent = Execute::persistent(&self);
+
+        Box::pin(try_stream! {
+            let mut logger = QueryLogger::new(sql, conn.log_settings.clone());
+
+            conn.wait_until_ready().await?;
+
+            let (statement, metadata) = conn
+                .get_or_prepare(sql, &arguments.types, persistent, metadata)
+                .await?;
+
+            arguments.apply_patches(conn, &metadata.parameters).await?;
+            conn.wait_until_ready().await?;
+
+            // if this stream ends before the portal does, the [Sync] ends its implicit
+            // transaction and the next query skips any rows still on their way
+            let mut portal = SyncOnDrop { conn, armed: true };
+
+            portal.conn.stream.write(Bind {
+                portal: None,
+                statement,
+                formats: &[PgValueFormat::Binary],
+                num_params: arguments.types.len() as i16,
+                params: &arguments.buffer,
+                result_formats: &[PgValueFormat::Binary],
+            });
+
+            portal.execute(batch_size).await?;
+
+            loop {
+                let message = portal.conn.stream.recv().await?;
+
+                match message.format {
+                    MessageFormat::BindComplete => {}
+
+                    MessageFormat::DataRow => {
+                        logger.increment_rows_returned();
+
+                        let data: DataRow = message.decode()?;
+                        let row = PgRow {
+                            data,
+                            format: PgValueFormat::Binary,
+                            metadata: Arc::clone(&metadata),
+                        };
+
+                        r#yield!(row);
+                    }
+
+                    MessageFormat::PortalSuspended => {
+                        // the batch is done; ask for the next one
+                        portal.execute(batch_size).await?;
+                    }
+
+                    MessageFormat::CommandComplete => {
+                        let cc: CommandComplete = message.decode()?;
+                        logger.increase_rows_affected(cc.rows_affected());
+
+                        break;
+                    }
+
+                    MessageFormat::EmptyQueryResponse => break,
+
+                    _ => {
+                        return Err(err_protocol!(
+                            "fetch_in_batches: unexpected message: {:?}",
+                            message.format
+                        ));
+                    }
+                }
+            }
+
+            portal.finish().await?;
+
+            Ok(())
+        })
+    }
+}
+
+/// Holds the connection while a portal is being read, ending the portal if dropped.
+struct SyncOnDrop<'c> {
+    conn: &'c mut PgConnection,
+    armed: bool,
+}
+
+impl<'c> SyncOnDrop<'c> {
+    async fn execute(&mut self, limit: u32) -> Result<()> {
+        self.conn.stream.write(message::Execute {
+            portal: None,
+            limit,
+        });
+
+        // without a [Sync], a [Flush] is needed to get the results of the [Execute]
+        self.conn.stream.write(message::Flush);
+        self.conn.stream.flush().await?;
+
+        Ok(())
+    }
+
+    async fn finish(&mut self) -> Result<()> {
+        self.armed = false;
+        self.conn.write_sync();
+
+        self.conn.wait_until_ready().await
+    }
+}
+
+impl Drop for SyncOnDrop<'_> {
+    fn drop(&mut self) {
+        if self.armed {
+            // picked up by the next `wait_until_ready()`
+            self.conn.write_sync();
+        }
+    }
+}
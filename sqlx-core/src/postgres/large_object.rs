@@ -0,0 +1,487 @@
+use crate::error::{Error, Result};
+use crate::net::{PollReadBuf, PollReadOut};
+use crate::postgres::types::Oid;
+use crate::postgres::PgConnection;
+use futures_core::future::BoxFuture;
+use futures_core::ready;
+use futures_util::future::poll_fn;
+use sqlx_rt::{AsyncRead, AsyncSeek, AsyncWrite};
+use std::cmp;
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, SeekFrom};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+// https://github.com/postgres/postgres/blob/REL_14_0/src/include/libpq/libpq-fs.h
+const INV_WRITE: i32 = 0x0002_0000;
+const INV_READ: i32 = 0x0004_0000;
+
+// a single `loread` or `lowrite` is kept well below the 1 GB limit of a `bytea`
+const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+
+impl PgConnection {
+    /// Create a new, empty [large object] and return its OID.
+    ///
+    /// [large object]: https://www.postgresql.org/docs/current/largeobjects.html
+    pub async fn create_large_object(&mut self) -> Result<Oid> {
+        // passing 0 lets the server assign an unused OID
+        crate::query_scalar::query_scalar("SELECT lo_create(0)")
+            .fetch_one(self)
+            .await
+    }
+
+    /// Open the [large object] with the given OID for reading and/or writing.
+    ///
+    /// The returned [`PgLargeObject`] implements `AsyncRead`, `AsyncWrite` and `AsyncSeek`,
+    /// so the contents of the object can be streamed without loading them into memory.
+    ///
+    /// ### Note: Must be Used in a Transaction
+    /// Postgres closes all large object descriptors at the end of the transaction they
+    /// were opened in, so this has to be called on a [`Transaction`][crate::transaction::Transaction]
+    /// (or after an explicit `BEGIN`); otherwise the object is closed again right away and any
+    /// use of it will fail.
+    ///
+    /// [large object]: https://www.postgresql.org/docs/current/largeobjects.html
+    pub async fn open_large_object(
+        &mut self,
+        oid: Oid,
+        mode: PgLargeObjectMode,
+    ) -> Result<PgLargeObject<'_>> {
+        let fd: i32 = crate::query_scalar::query_scalar("SELECT lo_open($1, $2)")
+            .bind(oid)
+            .bind(mode.flags())
+            .fetch_one(&mut *self)
+            .await?;
+
+        Ok(PgLargeObject {
+            oid,
+            fd,
+            position: 0,
+            read_buf: Vec::new(),
+            write_buf: Vec::new(),
+            conn: Some(self),
+            pending: None,
+            pending_operation: None,
+        })
+    }
+
+    /// Delete the [large object] with the given OID.
+    ///
+    /// [large object]: https://www.postgresql.org/docs/current/largeobjects.html
+    pub async fn unlink_large_object(&mut self, oid: Oid) -> Result<()> {
+        crate::query::query("SELECT lo_unlink($1)")
+            .bind(oid)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Whether a [`PgLargeObject`] is opened for reading, writing or both.
+///
+/// An object opened with [`Read`](Self::Read) sees its contents as of the start of the
+/// transaction (or the snapshot in use), even if it is written to afterwards; the other modes
+/// see the latest committed contents and any changes made in this transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PgLargeObjectMode {
+    /// Open the object for reading only.
+    Read,
+
+    /// Open the object for writing only.
+    Write,
+
+    /// Open the object for both reading and writing.
+    ReadWrite,
+}
+
+impl PgLargeObjectMode {
+    fn flags(self) -> i32 {
+        match self {
+            PgLargeObjectMode::Read => INV_READ,
+            PgLargeObjectMode::Write => INV_WRITE,
+            PgLargeObjectMode::ReadWrite => INV_READ | INV_WRITE,
+        }
+    }
+}
+
+/// An open [large object], created with [`PgConnection::open_large_object`].
+///
+/// Reads, writes and seeks are implemented through the server-side `lo_*` functions, one
+/// query per call (each transferring at most 1 MiB), so it is usually worth wrapping the object
+/// in a buffered reader or writer.
+///
+/// A write only returns once the server has written the data. If a write that returned
+/// `Pending` is abandoned, the data may still be written: it is sent to the server right away.
+///
+/// The object is closed when the transaction ends; [`close`](Self::close) closes it early.
+///
+/// [large object]: https://www.postgresql.org/docs/current/largeobjects.html
+pub struct PgLargeObject<'c> {
+    oid: Oid,
+    fd: i32,
+    // current position, as far as the server knows
+    position: u64,
+    // data read from the server but not returned yet, because the read that asked for it was
+    // abandoned and the next one had a smaller buffer; the server is this far ahead of us
+    read_buf: Vec<u8>,
+    // the data of the pending write, to recognize the caller polling for it again
+    write_buf: Vec<u8>,
+    // taken by the pending operation while it runs
+    conn: Option<&'c mut PgConnection>,
+    pending: Option<BoxFuture<'c, (&'c mut PgConnection, Result<Outcome>)>>,
+    pending_operation: Option<Operation>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operation {
+    Read,
+    Write,
+    // the position as requested by the caller
+    Seek(SeekFrom),
+}
+
+enum Request {
+    Read(usize),
+    // the data to write, after moving back over the given number of bytes
+    Write(Vec<u8>, usize),
+    Seek(SeekFrom),
+}
+
+enum Outcome {
+    Read(Vec<u8>),
+    Write(usize),
+    Seek(u64),
+}
+
+impl<'c> PgLargeObject<'c> {
+    /// The OID of this large object.
+    pub fn oid(&self) -> Oid {
+        self.oid
+    }
+
+    /// Truncate (or extend, with zeroes) this large object to `len` bytes.
+    ///
+    /// The current position is unchanged.
+    pub async fn truncate(&mut self, len: u64) -> Result<()> {
+        let len = i64::try_from(len).unwrap_or(i64::MAX);
+        let fd = self.fd;
+
+        crate::query::query("SELECT lo_truncate64($1, $2)")
+            .bind(fd)
+            .bind(len)
+            .execute(self.connection().await?)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Close this large object, waiting for any write in progress to finish.
+    ///
+    /// This is done automatically when the transaction ends.
+    pub async fn close(mut self) -> Result<()> {
+        let fd = self.fd;
+
+        crate::query::query("SELECT lo_close($1)")
+            .bind(fd)
+            .execute(self.connection().await?)
+            .await?;
+
+        Ok(())
+    }
+
+    // wait for the pending operation, if any, and return the connection
+    async fn connection(&mut self) -> Result<&mut PgConnection> {
+        poll_fn(|cx| self.poll_pending(cx)).await?;
+
+        Ok(self.conn.as_deref_mut().expect("BUG: connection taken"))
+    }
+
+    fn finish(&mut self, res: Result<Outcome>) -> Result<()> {
+        match res? {
+            Outcome::Read(data) => {
+                self.position += data.len() as u64;
+                self.read_buf = data;
+            }
+
+            Outcome::Write(written) => self.position += written as u64,
+            Outcome::Seek(position) => self.position = position,
+        }
+
+        Ok(())
+    }
+
+    fn start(&mut self, operation: Operation, request: Request) {
+        let conn = self.conn.take().expect("BUG: connection taken");
+
+        self.pending = Some(Box::pin(run(conn, self.fd, request)));
+        self.pending_operation = Some(operation);
+    }
+
+    fn start_seek(&mut self, position: SeekFrom) {
+        let request = match position {
+            // the server is ahead of us by any data we haven't returned yet
+            SeekFrom::Current(offset) => SeekFrom::Current(offset - self.read_buf.len() as i64),
+
+            position => position,
+        };
+
+        self.read_buf.clear();
+        self.start(Operation::Seek(position), Request::Seek(request));
+    }
+
+    // wait for the pending operation, if any, to complete
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let pending = match &mut self.pending {
+            Some(pending) => pending,
+            None => return Poll::Ready(Ok(())),
+        };
+
+        let (conn, res) = ready!(pending.as_mut().poll(cx));
+
+        self.pending = None;
+        self.pending_operation = None;
+        self.conn = Some(conn);
+
+        Poll::Ready(self.finish(res).map_err(into_io_error))
+    }
+
+    // the current position, as far as the caller knows
+    fn logical_position(&self) -> u64 {
+        self.position - self.read_buf.len() as u64
+    }
+}
+
+async fn run(
+    conn: &mut PgConnection,
+    fd: i32,
+    request: Request,
+) -> (&mut PgConnection, Result<Outcome>) {
+    let res = match request {
+        Request::Read(len) => crate::query_scalar::query_scalar("SELECT loread($1, $2)")
+            .bind(fd)
+            .bind(len as i32)
+            .fetch_one(&mut *conn)
+            .await
+            .map(Outcome::Read),
+
+        Request::Write(data, rewind) => write(conn, fd, data, rewind).await,
+
+        Request::Seek(pos) => {
+            // SEEK_SET, SEEK_CUR and SEEK_END
+            let (offset, whence) = match pos {
+                SeekFrom::Start(offset) => (i64::try_from(offset).unwrap_or(i64::MAX), 0),
+                SeekFrom::Current(offset) => (offset, 1),
+                SeekFrom::End(offset) => (offset, 2),
+            };
+
+            crate::query_scalar::query_scalar("SELECT lo_lseek64($1, $2, $3)")
+                .bind(fd)
+                .bind(offset)
+                .bind(whence)
+                .fetch_one(&mut *conn)
+                .await
+                .map(|position: i64| Outcome::Seek(position as u64))
+        }
+    };
+
+    (conn, res)
+}
+
+async fn write(conn: &mut PgConnection, fd: i32, data: Vec<u8>, rewind: usize) -> Result<Outcome> {
+    if rewind > 0 {
+        crate::query::query("SELECT lo_lseek64($1, $2, 1)")
+            .bind(fd)
+            .bind(-(rewind as i64))
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    let written: i32 = crate::query_scalar::query_scalar("SELECT lowrite($1, $2)")
+        .bind(fd)
+        .bind(data)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    Ok(Outcome::Write(written as usize))
+}
+
+fn into_io_error(error: Error) -> io::Error {
+    match error {
+        Error::Io(error) => error,
+        error => io::Error::new(io::ErrorKind::Other, error),
+    }
+}
+
+impl Debug for PgLargeObject<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PgLargeObject")
+            .field("oid", &self.oid)
+            .field("fd", &self.fd)
+            .field("position", &self.logical_position())
+            .finish()
+    }
+}
+
+impl AsyncRead for PgLargeObject<'_> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut PollReadBuf<'_>,
+    ) -> Poll<io::Result<PollReadOut>> {
+        let this = self.get_mut();
+
+        #[cfg(any(feature = "_rt-actix", feature = "_rt-tokio"))]
+        let len = buf.remaining();
+
+        #[cfg(feature = "_rt-async-std")]
+        let len = buf.len();
+
+        let len = cmp::min(len, MAX_CHUNK_SIZE);
+
+        if len == 0 {
+            #[cfg(any(feature = "_rt-actix", feature = "_rt-tokio"))]
+            return Poll::Ready(Ok(()));
+
+            #[cfg(feature = "_rt-async-std")]
+            return Poll::Ready(Ok(0));
+        }
+
+        if this.read_buf.is_empty() {
+            // unless we're being polled again after returning `Pending`, finish whatever else
+            // is in progress and start the read
+            if this.pending_operation != Some(Operation::Read) {
+                ready!(this.poll_pending(cx))?;
+            }
+
+            if this.read_buf.is_empty() {
+                if this.pending.is_none() {
+                    this.start(Operation::Read, Request::Read(len));
+                }
+
+                ready!(this.poll_pending(cx))?;
+            }
+        }
+
+        // the read may have been started with a larger buffer than this one
+        let n = cmp::min(len, this.read_buf.len());
+
+        #[cfg(any(feature = "_rt-actix", feature = "_rt-tokio"))]
+        buf.put_slice(&this.read_buf[..n]);
+
+        #[cfg(feature = "_rt-async-std")]
+        buf[..n].copy_from_slice(&this.read_buf[..n]);
+
+        this.read_buf.drain(..n);
+
+        #[cfg(any(feature = "_rt-actix", feature = "_rt-tokio"))]
+        return Poll::Ready(Ok(()));
+
+        #[cfg(feature = "_rt-async-std")]
+        return Poll::Ready(Ok(n));
+    }
+}
+
+impl AsyncWrite for PgLargeObject<'_> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        // being polled again after returning `Pending`
+        if this.pending_operation == Some(Operation::Write) && buf.starts_with(&this.write_buf) {
+            ready!(this.poll_pending(cx))?;
+
+            return Poll::Ready(Ok(this.write_buf.len()));
+        }
+
+        // anything else in progress, including an abandoned write which can't be taken back,
+        // has to complete (successfully) first
+        ready!(this.poll_pending(cx))?;
+
+        let data = &buf[..cmp::min(buf.len(), MAX_CHUNK_SIZE)];
+
+        // the server first has to move back over any data read but not returned
+        let rewind = this.read_buf.len();
+        this.position -= rewind as u64;
+        this.read_buf.clear();
+
+        this.write_buf.clear();
+        this.write_buf.extend_from_slice(data);
+
+        this.start(Operation::Write, Request::Write(data.to_vec(), rewind));
+
+        ready!(this.poll_pending(cx))?;
+
+        Poll::Ready(Ok(data.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // every write completes before returning, except one that was abandoned
+        self.get_mut().poll_pending(cx)
+    }
+
+    #[cfg(any(feature = "_rt-actix", feature = "_rt-tokio"))]
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+
+    #[cfg(feature = "_rt-async-std")]
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+#[cfg(any(feature = "_rt-actix", feature = "_rt-tokio"))]
+impl AsyncSeek for PgLargeObject<'_> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+
+        if this.pending.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "other operation on the large object is in progress; call `poll_complete` first",
+            ));
+        }
+
+        this.start_seek(position);
+
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+
+        ready!(this.poll_pending(cx))?;
+
+        Poll::Ready(Ok(this.logical_position()))
+    }
+}
+
+#[cfg(feature = "_rt-async-std")]
+impl AsyncSeek for PgLargeObject<'_> {
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        position: SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+
+        // unless we're being polled again after returning `Pending`, finish whatever else
+        // is in progress and start the seek
+        if this.pending_operation != Some(Operation::Seek(position)) {
+            ready!(this.poll_pending(cx))?;
+            this.start_seek(position);
+        }
+
+        ready!(this.poll_pending(cx))?;
+
+        Poll::Ready(Ok(this.logical_position()))
+    }
+}
@@ -31,6 +31,7 @@ pub struct PgListener {
 }
 
 /// An asynchronous notification from Postgres.
+#[derive(Clone)]
 pub struct PgNotification(Notification);
 
 impl PgListener {
@@ -145,14 +146,20 @@ impl PgListener {
     }
 
     #[inline]
-    async fn connect_if_needed(&mut self) -> Result<(), Error> {
+    pub(crate) async fn connect_if_needed(&mut self) -> Result<(), Error> {
         if self.connection.is_none() {
             let mut connection = self.pool.acquire().await?;
             connection.stream.notifications = self.buffer_tx.take();
 
-            connection
+            let res = connection
                 .execute(&*build_listen_all_query(&self.channels))
-                .await?;
+                .await;
+
+            if let Err(error) = res {
+                // keep the buffer for the next attempt
+                self.buffer_tx = connection.stream.notifications.take();
+                return Err(error);
+            }
 
             self.connection = Some(connection);
         }
@@ -165,6 +172,13 @@ impl PgListener {
         self.connection.as_mut().unwrap()
     }
 
+    /// Drop the current connection; a new one is opened by the next call to `try_recv()`.
+    pub(crate) fn disconnect(&mut self) {
+        if let Some(mut connection) = self.connection.take() {
+            self.buffer_tx = connection.stream.notifications.take();
+        }
+    }
+
     /// Receives the next notification available from any of the subscribed channels.
     ///
     /// If the connection to PostgreSQL is lost, it is automatically reconnected on the next
@@ -256,8 +270,7 @@ impl PgListener {
                 // The connection is dead, ensure that it is dropped,
                 // update self state, and loop to try again.
                 Err(Error::Io(err)) if err.kind() == io::ErrorKind::ConnectionAborted => {
-                    self.buffer_tx = self.connection().stream.notifications.take();
-                    self.connection = None;
+                    self.disconnect();
 
                     // lost connection
                     return Ok(None);
@@ -411,7 +424,7 @@ impl Debug for PgNotification {
     }
 }
 
-fn ident(mut name: &str) -> String {
+pub(crate) fn ident(mut name: &str) -> String {
     // If the input string contains a NUL byte, we should truncate the
     // identifier.
     if let Some(index) = name.find('\0') {
@@ -7,13 +7,18 @@ mod arguments;
 mod column;
 mod connection;
 mod copy;
+mod cursor;
 mod database;
 mod error;
 mod io;
+mod large_object;
 mod listener;
 mod message;
+mod notification_hub;
 mod options;
+mod pipeline;
 mod query_result;
+pub mod replication;
 mod row;
 mod statement;
 mod transaction;
@@ -24,16 +29,24 @@ mod value;
 #[cfg(feature = "migrate")]
 mod migrate;
 
-pub use advisory_lock::{PgAdvisoryLock, PgAdvisoryLockGuard, PgAdvisoryLockKey};
+pub use advisory_lock::{
+    PgAdvisoryLock, PgAdvisoryLockGuard, PgAdvisoryLockKey, PgAdvisoryLockPoolGuard,
+};
 pub use arguments::{PgArgumentBuffer, PgArguments};
 pub use column::PgColumn;
-pub use connection::PgConnection;
-pub use copy::PgCopyIn;
+pub use connection::{PgCancelToken, PgConnection};
+pub use copy::{PgCopyIn, PgCopyRow, PgCopyRowEncoder};
+pub use cursor::{PgCursor, PgCursorDirection};
 pub use database::Postgres;
 pub use error::{PgDatabaseError, PgErrorPosition};
+pub use large_object::{PgLargeObject, PgLargeObjectMode};
 pub use listener::{PgListener, PgNotification};
 pub use message::PgSeverity;
-pub use options::{PgConnectOptions, PgSslMode};
+pub use notification_hub::{PgNotificationEvent, PgNotificationHub, PgSubscription};
+pub use options::{
+    PgChannelBinding, PgConnectOptions, PgLoadBalanceHosts, PgSslMode, PgTargetSessionAttrs,
+};
+pub use pipeline::{PgPipeline, PgPipelineError};
 pub use query_result::PgQueryResult;
 pub use row::PgRow;
 pub use statement::PgStatement;
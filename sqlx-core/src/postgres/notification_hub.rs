@@ -0,0 +1,302 @@
+use std::cmp;
+use std::fmt::{self, Debug};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_channel::{mpsc, oneshot};
+use futures_core::stream::Stream;
+use futures_util::future::{self, Either};
+use futures_util::StreamExt;
+
+use crate::error::Error;
+use crate::pool::Pool;
+use crate::postgres::{PgListener, PgNotification, Postgres};
+use crate::HashMap;
+
+/// Shares a single listening connection between any number of subscribers.
+///
+/// Where a [`PgListener`] has one consumer for all of its channels, a hub hands out a separate
+/// [`PgSubscription`] stream for each call to [`subscribe()`][Self::subscribe] and delivers
+/// every notification to all of the subscriptions for its channel. `LISTEN` is issued when the
+/// first subscription to a channel is created and `UNLISTEN` when the last one is dropped.
+///
+/// The connection is owned by a background task, which reconnects (and re-subscribes) if it is
+/// lost. As notifications sent while disconnected are never delivered, every subscription then
+/// receives [`PgNotificationEvent::Reconnected`] so it can catch up on whatever it might have
+/// missed.
+///
+/// The hub is cheap to clone; the connection is released once the hub and all of its
+/// subscriptions have been dropped.
+///
+/// ```rust,no_run
+/// # use sqlx_core::error::Error;
+/// # async fn example() -> Result<(), Error> {
+/// use futures_util::StreamExt;
+/// use sqlx_core::postgres::{PgNotificationEvent, PgNotificationHub};
+///
+/// let hub = PgNotificationHub::connect("postgres:// ...").await?;
+/// let mut orders = hub.subscribe("orders").await?;
+///
+/// while let Some(event) = orders.next().await {
+///     match event {
+///         PgNotificationEvent::Notification(notification) => {
+///             println!("order updated: {}", notification.payload());
+///         }
+///
+///         PgNotificationEvent::Reconnected => {
+///             // notifications may have been missed; reload anything we care about
+///         }
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct PgNotificationHub {
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+/// A stream of the events for one channel of a [`PgNotificationHub`].
+///
+/// Events are buffered without limit until they are received, so a subscription should be
+/// polled regularly or dropped. The stream ends if the hub's pool is closed.
+pub struct PgSubscription {
+    id: u64,
+    channel: String,
+    events: mpsc::UnboundedReceiver<PgNotificationEvent>,
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+/// An event received by a [`PgSubscription`].
+#[derive(Debug, Clone)]
+pub enum PgNotificationEvent {
+    /// A notification was sent on the subscribed channel.
+    Notification(PgNotification),
+
+    /// The connection was lost and has been re-established; any notifications sent in the
+    /// meantime were missed.
+    Reconnected,
+}
+
+enum Command {
+    Subscribe {
+        channel: String,
+        events: mpsc::UnboundedSender<PgNotificationEvent>,
+        reply: oneshot::Sender<Result<u64, Error>>,
+    },
+
+    Unsubscribe {
+        channel: String,
+        id: u64,
+    },
+}
+
+impl PgNotificationHub {
+    pub async fn connect(url: &str) -> Result<Self, Error> {
+        Ok(Self::new(PgListener::connect(url).await?))
+    }
+
+    pub async fn connect_with(pool: &Pool<Postgres>) -> Result<Self, Error> {
+        Ok(Self::new(PgListener::connect_with(pool).await?))
+    }
+
+    fn new(listener: PgListener) -> Self {
+        let (commands, receiver) = mpsc::unbounded();
+
+        sqlx_rt::spawn(run(listener, receiver));
+
+        Self { commands }
+    }
+
+    /// Subscribe to notifications on a channel.
+    ///
+    /// Once this returns, the channel is being listened to and every notification sent on it
+    /// will be delivered to the subscription.
+    pub async fn subscribe(&self, channel: &str) -> Result<PgSubscription, Error> {
+        let (events, receiver) = mpsc::unbounded();
+        let (reply, reply_rx) = oneshot::channel();
+
+        self.commands
+            .unbounded_send(Command::Subscribe {
+                channel: channel.to_owned(),
+                events,
+                reply,
+            })
+            .map_err(|_| Error::PoolClosed)?;
+
+        let id = reply_rx.await.map_err(|_| Error::PoolClosed)??;
+
+        Ok(PgSubscription {
+            id,
+            channel: channel.to_owned(),
+            events: receiver,
+            commands: self.commands.clone(),
+        })
+    }
+}
+
+impl PgSubscription {
+    /// The channel this subscription receives notifications from.
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+}
+
+impl Stream for PgSubscription {
+    type Item = PgNotificationEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_next_unpin(cx)
+    }
+}
+
+impl Drop for PgSubscription {
+    fn drop(&mut self) {
+        // if the hub has already stopped there is nothing to clean up
+        let _ = self.commands.unbounded_send(Command::Unsubscribe {
+            channel: std::mem::take(&mut self.channel),
+            id: self.id,
+        });
+    }
+}
+
+impl Debug for PgNotificationHub {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PgNotificationHub").finish()
+    }
+}
+
+impl Debug for PgSubscription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PgSubscription")
+            .field("channel", &self.channel)
+            .finish()
+    }
+}
+
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+// the background task that owns the listening connection
+async fn run(mut listener: PgListener, mut commands: mpsc::UnboundedReceiver<Command>) {
+    let mut subscribers: HashMap<String, Vec<(u64, mpsc::UnboundedSender<PgNotificationEvent>)>> =
+        HashMap::new();
+    let mut next_id = 0_u64;
+
+    loop {
+        // `try_recv()` is cancel-safe while connected, so it's fine to drop it
+        // whenever a command arrives first
+        let next = {
+            let recv = listener.try_recv();
+            futures_util::pin_mut!(recv);
+
+            match future::select(recv, commands.next()).await {
+                Either::Left((res, _)) => Either::Left(res),
+                Either::Right((command, _)) => Either::Right(command),
+            }
+        };
+
+        match next {
+            Either::Left(res) => {
+                match res {
+                    Ok(Some(notification)) => {
+                        if let Some(senders) = subscribers.get(notification.channel()) {
+                            for (_, sender) in senders {
+                                let _ = sender.unbounded_send(PgNotificationEvent::Notification(
+                                    notification.clone(),
+                                ));
+                            }
+                        }
+
+                        continue;
+                    }
+
+                    // the connection was closed
+                    Ok(None) => {}
+
+                    Err(Error::PoolClosed) => break,
+
+                    Err(error) => {
+                        log::warn!("PgNotificationHub: lost connection: {}", error);
+                        listener.disconnect();
+                    }
+                }
+
+                if reconnect(&mut listener).await.is_err() {
+                    break;
+                }
+
+                for (_, sender) in subscribers.values().flatten() {
+                    let _ = sender.unbounded_send(PgNotificationEvent::Reconnected);
+                }
+            }
+
+            Either::Right(Some(Command::Subscribe {
+                channel,
+                events,
+                reply,
+            })) => {
+                let res = if subscribers.contains_key(&channel) {
+                    Ok(())
+                } else {
+                    listener.listen(&channel).await
+                };
+
+                let res = res.map(|_| {
+                    next_id += 1;
+
+                    subscribers
+                        .entry(channel)
+                        .or_default()
+                        .push((next_id, events));
+
+                    next_id
+                });
+
+                let _ = reply.send(res);
+            }
+
+            Either::Right(Some(Command::Unsubscribe { channel, id })) => {
+                if let Some(senders) = subscribers.get_mut(&channel) {
+                    senders.retain(|(other, _)| *other != id);
+
+                    if senders.is_empty() {
+                        subscribers.remove(&channel);
+
+                        if let Err(error) = listener.unlisten(&channel).await {
+                            log::warn!("PgNotificationHub: failed to UNLISTEN: {}", error);
+                        }
+                    }
+                }
+            }
+
+            // the hub and all of its subscriptions have been dropped
+            Either::Right(None) => break,
+        }
+    }
+}
+
+/// Keep trying to reconnect until we succeed or the pool is closed.
+async fn reconnect(listener: &mut PgListener) -> Result<(), Error> {
+    let mut delay = Duration::from_millis(100);
+
+    loop {
+        match listener.connect_if_needed().await {
+            Ok(()) => return Ok(()),
+
+            Err(Error::PoolClosed) => return Err(Error::PoolClosed),
+
+            Err(error) => {
+                log::warn!(
+                    "PgNotificationHub: failed to reconnect, retrying in {:?}: {}",
+                    delay,
+                    error
+                );
+
+                sqlx_rt::sleep(delay).await;
+
+                delay = cmp::min(delay * 2, MAX_RECONNECT_DELAY);
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use crate::error::Error;
+use std::str::FromStr;
+
+/// Options for controlling the use of SCRAM channel binding, which ties authentication to the
+/// TLS connection it happens over.
+///
+/// It is used by the [`channel_binding`](super::PgConnectOptions::channel_binding) method and
+/// mirrors the libpq parameter of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PgChannelBinding {
+    /// Never use channel binding.
+    Disable,
+
+    /// Use channel binding if the connection is encrypted and the server supports it.
+    #[default]
+    Prefer,
+
+    /// Only accept a connection if the server authenticated it with `SCRAM-SHA-256-PLUS`
+    /// bound to the TLS connection. This requires an SSL connection.
+    Require,
+}
+
+impl FromStr for PgChannelBinding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(match &*s.to_ascii_lowercase() {
+            "disable" => PgChannelBinding::Disable,
+            "prefer" => PgChannelBinding::Prefer,
+            "require" => PgChannelBinding::Require,
+
+            _ => {
+                return Err(Error::Configuration(
+                    format!("unknown value {:?} for `channel_binding`", s).into(),
+                ));
+            }
+        })
+    }
+}
@@ -0,0 +1,153 @@
+use crate::error::Error;
+use std::env::var_os;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Load the connection parameters of the named service from the connection service files,
+/// looking in the same places as libpq.
+///
+/// https://www.postgresql.org/docs/current/libpq-pgservice.html
+pub fn load_service(name: &str) -> Result<Vec<(String, String)>, Error> {
+    // the per-user file is `PGSERVICEFILE`, or `~/.pg_service.conf`
+    let user_file = match var_os("PGSERVICEFILE") {
+        Some(file) => Some(PathBuf::from(file)),
+
+        #[cfg(not(target_os = "windows"))]
+        None => dirs::home_dir().map(|path| path.join(".pg_service.conf")),
+
+        #[cfg(target_os = "windows")]
+        None => dirs::data_dir().map(|path| path.join("postgresql").join(".pg_service.conf")),
+    };
+
+    // the system-wide file is `pg_service.conf` in `PGSYSCONFDIR`
+    let system_file = var_os("PGSYSCONFDIR").map(|dir| PathBuf::from(dir).join("pg_service.conf"));
+
+    for file in user_file.iter().chain(system_file.iter()) {
+        if let Some(params) = load_service_from_file(file, name)? {
+            return Ok(params);
+        }
+    }
+
+    Err(Error::Configuration(
+        format!("definition of service {:?} not found", name).into(),
+    ))
+}
+
+/// try to load a service from a service file, which is fine to be missing
+fn load_service_from_file(path: &Path, name: &str) -> Result<Option<Vec<(String, String)>>, Error> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return Ok(None),
+    };
+
+    load_service_from_reader(BufReader::new(file), name)
+        .map_err(|error| Error::Configuration(format!("{}: {}", path.display(), error).into()))
+}
+
+fn load_service_from_reader(
+    reader: impl BufRead,
+    name: &str,
+) -> Result<Option<Vec<(String, String)>>, String> {
+    let mut params: Option<Vec<(String, String)>> = None;
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|error| error.to_string())?;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // [service]
+        if let Some(section) = line.strip_prefix('[') {
+            if params.is_some() {
+                // the service we're looking for has ended
+                break;
+            }
+
+            if section.strip_suffix(']') == Some(name) {
+                params = Some(Vec::new());
+            }
+
+            continue;
+        }
+
+        // keyword=value
+        if let Some(params) = &mut params {
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("syntax error on line {}", i + 1))?;
+
+            let key = key.trim();
+
+            if key == "service" {
+                return Err(format!(
+                    "nested service specifications not supported on line {}",
+                    i + 1
+                ));
+            }
+
+            params.push((key.to_owned(), value.trim().to_owned()));
+        }
+    }
+
+    Ok(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::load_service_from_reader;
+
+    #[test]
+    fn test_load_service_from_reader() {
+        let file = b"
+# a comment
+[other]
+host=other.example.com
+
+[mydb]
+host = db.example.com
+port=5433
+  dbname=mydb
+options=-c geqo=off
+
+[last]
+host=last.example.com
+";
+
+        assert_eq!(
+            load_service_from_reader(&file[..], "mydb").unwrap(),
+            Some(vec![
+                ("host".to_owned(), "db.example.com".to_owned()),
+                ("port".to_owned(), "5433".to_owned()),
+                ("dbname".to_owned(), "mydb".to_owned()),
+                ("options".to_owned(), "-c geqo=off".to_owned()),
+            ])
+        );
+
+        assert_eq!(
+            load_service_from_reader(&file[..], "last").unwrap(),
+            Some(vec![("host".to_owned(), "last.example.com".to_owned())])
+        );
+
+        assert_eq!(
+            load_service_from_reader(&file[..], "missing").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_load_service_from_reader_errors() {
+        // a line without `=`
+        assert!(load_service_from_reader(&b"[mydb]\nhost"[..], "mydb").is_err());
+        // services cannot refer to other services
+        assert!(load_service_from_reader(&b"[mydb]\nservice=other"[..], "mydb").is_err());
+
+        // only the service we're looking for has to be valid
+        assert_eq!(
+            load_service_from_reader(&b"[other]\nhost\n[mydb]\nport=5432"[..], "mydb").unwrap(),
+            Some(vec![("port".to_owned(), "5432".to_owned())])
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use crate::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// The kind of server to look for when connecting to one of several hosts.
+///
+/// It is used by the [`target_session_attrs`](super::PgConnectOptions::target_session_attrs)
+/// method and mirrors the libpq parameter of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PgTargetSessionAttrs {
+    /// Any successful connection is acceptable.
+    #[default]
+    Any,
+
+    /// The session must accept read-write transactions by default.
+    ReadWrite,
+
+    /// The session must not accept read-write transactions by default.
+    ReadOnly,
+
+    /// The server must not be in hot standby mode.
+    Primary,
+
+    /// The server must be in hot standby mode.
+    Standby,
+
+    /// First try to find a standby server, but if none of the hosts is a standby,
+    /// try again in [`Any`](Self::Any) mode.
+    PreferStandby,
+}
+
+impl PgTargetSessionAttrs {
+    /// Returns `true` if a server in this state is acceptable.
+    ///
+    /// [`PreferStandby`](Self::PreferStandby) only accepts standbys here; falling back to any
+    /// server is up to the caller.
+    pub(crate) fn matches(self, in_recovery: bool, read_only: bool) -> bool {
+        match self {
+            PgTargetSessionAttrs::Any => true,
+            PgTargetSessionAttrs::ReadWrite => !read_only,
+            PgTargetSessionAttrs::ReadOnly => read_only,
+            PgTargetSessionAttrs::Primary => !in_recovery,
+            PgTargetSessionAttrs::Standby | PgTargetSessionAttrs::PreferStandby => in_recovery,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            PgTargetSessionAttrs::Any => "any",
+            PgTargetSessionAttrs::ReadWrite => "read-write",
+            PgTargetSessionAttrs::ReadOnly => "read-only",
+            PgTargetSessionAttrs::Primary => "primary",
+            PgTargetSessionAttrs::Standby => "standby",
+            PgTargetSessionAttrs::PreferStandby => "prefer-standby",
+        }
+    }
+}
+
+impl Display for PgTargetSessionAttrs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PgTargetSessionAttrs {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(match &*s.to_ascii_lowercase() {
+            "any" => PgTargetSessionAttrs::Any,
+            "read-write" => PgTargetSessionAttrs::ReadWrite,
+            "read-only" => PgTargetSessionAttrs::ReadOnly,
+            "primary" => PgTargetSessionAttrs::Primary,
+            "standby" => PgTargetSessionAttrs::Standby,
+            "prefer-standby" => PgTargetSessionAttrs::PreferStandby,
+
+            _ => {
+                return Err(Error::Configuration(
+                    format!("unknown value {:?} for `target_session_attrs`", s).into(),
+                ));
+            }
+        })
+    }
+}
+
+/// Controls the order in which multiple hosts are tried.
+///
+/// It is used by the [`load_balance_hosts`](super::PgConnectOptions::load_balance_hosts)
+/// method and mirrors the libpq parameter of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PgLoadBalanceHosts {
+    /// Try hosts in the order they were given.
+    #[default]
+    Disable,
+
+    /// Try hosts in a random order, spreading connections across them.
+    Random,
+}
+
+impl FromStr for PgLoadBalanceHosts {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(match &*s.to_ascii_lowercase() {
+            "disable" => PgLoadBalanceHosts::Disable,
+            "random" => PgLoadBalanceHosts::Random,
+
+            _ => {
+                return Err(Error::Configuration(
+                    format!("unknown value {:?} for `load_balance_hosts`", s).into(),
+                ));
+            }
+        })
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::HashSet;
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use either::Either;
+use futures_core::stream::BoxStream;
+use futures_util::{StreamExt, TryStreamExt};
+
+use crate::error::Error;
+use crate::executor::Execute;
+use crate::logger::QueryLogger;
+use crate::postgres::connection::CancelOnDrop;
+use crate::postgres::message::{self, Bind, CommandComplete, DataRow, MessageFormat};
+use crate::postgres::statement::PgStatementMetadata;
+use crate::postgres::types::Oid;
+use crate::postgres::{PgArguments, PgConnection, PgQueryResult, PgRow, PgValueFormat, Postgres};
+
+/// A batch of queries to be sent to the server in a single round trip.
+///
+/// Normally each query waits for the server to finish the previous one before it is sent.
+/// A pipeline instead writes the `Bind` and `Execute` messages for every query followed by a
+/// single `Sync`, so a loop of 1,000 inserts costs one round trip instead of 1,000.
+///
+/// As with any group of statements sent before a `Sync`, the queries run in an implicit
+/// transaction (unless one is already open): if one fails, the server skips the rest and
+/// the earlier ones are rolled back.
+///
+/// ```rust,no_run
+/// # use sqlx_core::error::Error;
+/// # use sqlx_core::postgres::PgConnection;
+/// # async fn example(conn: &mut PgConnection) -> Result<(), Error> {
+/// use futures_util::TryStreamExt;
+/// use sqlx_core::postgres::PgPipeline;
+/// use sqlx_core::query::query;
+///
+/// let mut pipeline = PgPipeline::new();
+///
+/// for i in 0..1000 {
+///     pipeline.push(query("INSERT INTO foo (bar) VALUES ($1)").bind(i));
+/// }
+///
+/// let results: Vec<_> = pipeline.execute(conn).try_collect().await?;
+/// assert_eq!(results.len(), 1000);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ### Note
+/// Each query is prepared (and cached, if persistent) before the pipeline is sent, which costs
+/// a round trip for every statement not already in the cache. Once the pipeline's statements
+/// fill the statement cache, the rest are prepared without being cached. Queries are always
+/// sent using the extended protocol, so each one may contain only a single statement.
+#[derive(Default)]
+pub struct PgPipeline<'q> {
+    queries: Vec<PipelinedQuery<'q>>,
+}
+
+struct PipelinedQuery<'q> {
+    sql: &'q str,
+    arguments: PgArguments,
+    persistent: bool,
+    metadata: Option<Arc<PgStatementMetadata>>,
+}
+
+/// An error returned by a [`PgPipeline`], along with the position of the query that caused it.
+#[derive(Debug)]
+pub struct PgPipelineError {
+    index: usize,
+    error: Error,
+}
+
+impl<'q> PgPipeline<'q> {
+    /// Create an empty pipeline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a query to the end of the pipeline.
+    pub fn push<E>(&mut self, mut query: E) -> &mut Self
+    where
+        E: Execute<'q, Postgres>,
+    {
+        self.queries.push(PipelinedQuery {
+            sql: query.sql(),
+            metadata: query.statement().map(|s| Arc::clone(&s.metadata)),
+            arguments: query.take_arguments().unwrap_or_default(),
+            persistent: query.persistent(),
+        });
+
+        self
+    }
+
+    /// The number of queries in the pipeline.
+    pub fn len(&self) -> usize {
+        self.queries.len()
+    }
+
+    /// Returns `true` if no queries have been added to the pipeline.
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+
+    /// Send the pipeline and return a stream of the results of each query, in order.
+    ///
+    /// The stream yields exactly one [`PgQueryResult`] per query unless one of them fails,
+    /// in which case the stream ends with the error. Any rows returned are discarded.
+    pub fn execute<'e, 'c: 'e>(
+        self,
+        conn: &'c mut PgConnection,
+    ) -> BoxStream<'e, Result<PgQueryResult, PgPipelineError>>
+    where
+        'q: 'e,
+    {
+        self.fetch_many(conn)
+            .try_filter_map(|(_, step)| async move {
+                Ok(match step {
+                    Either::Left(result) => Some(result),
+                    Either::Right(_) => None,
+                })
+            })
+            .boxed()
+    }
+
+    /// Send the pipeline and return a stream of the rows and results of every query, along with
+    /// the index of the query that produced them.
+    pub fn fetch_many<'e, 'c: 'e>(
+        self,
+        conn: &'c mut PgConnection,
+    ) -> BoxStream<'e, Result<(usize, Either<PgQueryResult, PgRow>), PgPipelineError>>
+    where
+        'q: 'e,
+    {
+        // the index of the query we are waiting on, used to attribute errors
+        let current = Arc::new(AtomicUsize::new(0));
+        let index = Arc::clone(&current);
+
+        let stream: BoxStream<'e, Result<(usize, Either<PgQueryResult, PgRow>), Error>> = Box::pin(
+            try_stream! {
+                // if this stream is dropped before the pipeline completes,
+                // ask the server to cancel it
+                let mut cancel = CancelOnDrop::new(conn);
+
+                // each query is logged once its results have been received
+                let loggers: Vec<_> = self
+                    .queries
+                    .iter()
+                    .map(|query| QueryLogger::new(query.sql, conn.log_settings.clone()))
+                    .collect();
+
+                let statements = cancel.check(prepare(conn, self.queries, &index).await)?;
+
+                for (statement, arguments, _) in &statements {
+                    conn.stream.write(Bind {
+                        portal: None,
+                        statement: *statement,
+                        formats: &[PgValueFormat::Binary],
+                        num_params: arguments.types.len() as i16,
+                        params: &arguments.buffer,
+                        result_formats: &[PgValueFormat::Binary],
+                    });
+
+                    conn.stream.write(message::Execute {
+                        portal: None,
+                        limit: 0,
+                    });
+                }
+
+                // a single [Sync] for the whole pipeline
+                conn.write_sync();
+                cancel.check(conn.stream.flush().await)?;
+
+                for (i, ((_, _, metadata), mut logger)) in statements.iter().zip(loggers).enumerate() {
+                    index.store(i, Ordering::Relaxed);
+
+                    loop {
+                        let message = cancel.check(conn.stream.recv().await)?;
+
+                        match message.format {
+                            MessageFormat::BindComplete => {}
+
+                            MessageFormat::DataRow => {
+                                logger.increment_rows_returned();
+
+                                let data: DataRow = message.decode()?;
+                                let row = PgRow {
+                                    data,
+                                    format: PgValueFormat::Binary,
+                                    metadata: Arc::clone(metadata),
+                                };
+
+                                r#yield!((i, Either::Right(row)));
+                            }
+
+                            MessageFormat::CommandComplete => {
+                                let cc: CommandComplete = message.decode()?;
+
+                                let rows_affected = cc.rows_affected();
+                                logger.increase_rows_affected(rows_affected);
+
+                                r#yield!((i, Either::Left(PgQueryResult { rows_affected })));
+
+                                break;
+                            }
+
+                            MessageFormat::EmptyQueryResponse => {
+                                r#yield!((i, Either::Left(PgQueryResult::default())));
+
+                                break;
+                            }
+
+                            _ => {
+                                cancel.disarm();
+
+                                return Err(err_protocol!(
+                                    "pipeline: unexpected message: {:?}",
+                                    message.format
+                                ));
+                            }
+                        }
+                    }
+                }
+
+                cancel.disarm();
+                conn.recv_ready_for_query().await?;
+
+                Ok(())
+            },
+        );
+
+        stream
+            .map_err(move |error| PgPipelineError {
+                index: current.load(Ordering::Relaxed),
+                error,
+            })
+            .boxed()
+    }
+}
+
+async fn prepare(
+    conn: &mut PgConnection,
+    queries: Vec<PipelinedQuery<'_>>,
+    index: &AtomicUsize,
+) -> Result<Vec<(Oid, PgArguments, Arc<PgStatementMetadata>)>, Error> {
+    conn.wait_until_ready().await?;
+
+    let mut statements = Vec::with_capacity(queries.len());
+
+    // the cached statements used by the pipeline; once they fill the cache, caching another one
+    // would evict (and close) a statement that still has to be bound, so the rest are prepared
+    // without caching them
+    let mut cached = HashSet::new();
+
+    for (i, mut query) in queries.into_iter().enumerate() {
+        index.store(i, Ordering::Relaxed);
+
+        let persistent = query.persistent
+            && (cached.contains(query.sql)
+                || conn.cache_statement.contains_key(query.sql)
+                || cached.len() < conn.cache_statement.capacity());
+
+        let (statement, metadata) = conn
+            .get_or_prepare(
+                query.sql,
+                &query.arguments.types,
+                persistent,
+                query.metadata,
+            )
+            .await?;
+
+        if persistent && conn.cache_statement.is_enabled() {
+            cached.insert(query.sql);
+        }
+
+        query
+            .arguments
+            .apply_patches(conn, &metadata.parameters)
+            .await?;
+
+        statements.push((statement, query.arguments, metadata));
+    }
+
+    conn.wait_until_ready().await?;
+
+    Ok(statements)
+}
+
+impl PgPipelineError {
+    /// The position in the pipeline of the query that caused the error.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The error returned by the query.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    /// Take the error returned by the query, discarding its position.
+    pub fn into_error(self) -> Error {
+        self.error
+    }
+}
+
+impl Display for PgPipelineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "query {} in pipeline failed: {}", self.index, self.error)
+    }
+}
+
+impl StdError for PgPipelineError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<PgPipelineError> for Error {
+    #[inline]
+    fn from(error: PgPipelineError) -> Self {
+        error.error
+    }
+}
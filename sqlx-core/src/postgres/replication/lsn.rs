@@ -0,0 +1,62 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use crate::error::Error;
+
+/// A position in the write-ahead log, equivalent to the `pg_lsn` type.
+///
+/// Formatted as two hexadecimal numbers separated by a slash, e.g. `16/B374D848`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PgLsn(u64);
+
+impl PgLsn {
+    pub const fn from_u64(value: u64) -> Self {
+        Self(value)
+    }
+
+    pub const fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for PgLsn {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<PgLsn> for u64 {
+    fn from(lsn: PgLsn) -> Self {
+        lsn.0
+    }
+}
+
+impl Display for PgLsn {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:X}/{:X}", self.0 >> 32, self.0 as u32)
+    }
+}
+
+impl FromStr for PgLsn {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let invalid = || Error::Decode(format!("invalid LSN: {:?}", s).into());
+
+        let (high, low) = s.split_once('/').ok_or_else(invalid)?;
+        let high = u32::from_str_radix(high, 16).map_err(|_| invalid())?;
+        let low = u32::from_str_radix(low, 16).map_err(|_| invalid())?;
+
+        Ok(Self((u64::from(high) << 32) | u64::from(low)))
+    }
+}
+
+#[test]
+fn test_lsn_round_trip() {
+    let lsn: PgLsn = "16/B374D848".parse().unwrap();
+
+    assert_eq!(lsn.as_u64(), 0x16_B374_D848);
+    assert_eq!(lsn.to_string(), "16/B374D848");
+    assert_eq!("0/0".parse::<PgLsn>().unwrap(), PgLsn::default());
+    assert!("16B374D848".parse::<PgLsn>().is_err());
+}
@@ -0,0 +1,351 @@
+//! Logical replication, for streaming changes out of Postgres.
+//!
+//! ```rust,no_run
+//! # use sqlx_core::error::Error;
+//! # async fn example() -> Result<(), Error> {
+//! use sqlx_core::postgres::replication::{PgReplicationConnection, PgReplicationEvent};
+//!
+//! // a publication must have been created with `CREATE PUBLICATION`
+//! let mut conn = PgReplicationConnection::connect("postgres://localhost/mydb").await?;
+//! let slot = conn.create_replication_slot("my_slot", true).await?;
+//!
+//! let mut stream = conn
+//!     .start_replication("my_slot", &["my_publication"], slot.consistent_point())
+//!     .await?;
+//!
+//! while let Some(event) = stream.recv().await? {
+//!     if let PgReplicationEvent::Insert { relation, .. } = event {
+//!         println!("new row in {}", relation.name());
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::cmp;
+use std::time::{Duration, Instant};
+
+use bytes::{Buf, BufMut, Bytes};
+
+use crate::connection::{ConnectOptions, Connection};
+use crate::error::Error;
+use crate::executor::Executor;
+use crate::postgres::listener::ident;
+use crate::postgres::message::{CopyData, CopyDone, MessageFormat};
+use crate::postgres::{PgConnectOptions, PgConnection};
+use crate::row::Row;
+
+mod lsn;
+mod pgoutput;
+
+pub use lsn::PgLsn;
+pub use pgoutput::{PgRelation, PgReplicationEvent};
+
+use pgoutput::PgOutputDecoder;
+
+/// A connection in logical replication mode (`replication=database`).
+///
+/// Only replication commands and simple SQL queries can be run on such a connection, so it is
+/// not an [`Executor`].
+pub struct PgReplicationConnection {
+    conn: PgConnection,
+}
+
+/// A replication slot created by [`PgReplicationConnection::create_replication_slot`].
+#[derive(Debug, Clone)]
+pub struct PgReplicationSlot {
+    name: String,
+    consistent_point: PgLsn,
+    snapshot_name: Option<String>,
+}
+
+impl PgReplicationSlot {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The LSN at which the slot became consistent; streaming from here will see every
+    /// transaction that commits after the slot was created.
+    pub fn consistent_point(&self) -> PgLsn {
+        self.consistent_point
+    }
+
+    /// The snapshot exported by the slot, which can be used with `SET TRANSACTION SNAPSHOT` on
+    /// another connection to read the data as of the consistent point. It remains valid until
+    /// the next command on the replication connection.
+    pub fn snapshot_name(&self) -> Option<&str> {
+        self.snapshot_name.as_deref()
+    }
+}
+
+impl PgReplicationConnection {
+    pub async fn connect(url: &str) -> Result<Self, Error> {
+        Self::connect_with(&url.parse()?).await
+    }
+
+    pub async fn connect_with(options: &PgConnectOptions) -> Result<Self, Error> {
+        let mut options = options.clone();
+        options.replication = true;
+
+        Ok(Self {
+            conn: options.connect().await?,
+        })
+    }
+
+    /// Create a logical replication slot using the `pgoutput` plugin.
+    ///
+    /// A temporary slot is dropped when this connection is closed.
+    pub async fn create_replication_slot(
+        &mut self,
+        name: &str,
+        temporary: bool,
+    ) -> Result<PgReplicationSlot, Error> {
+        let row = self
+            .conn
+            .fetch_one(&*format!(
+                r#"CREATE_REPLICATION_SLOT "{}" {}LOGICAL pgoutput"#,
+                ident(name),
+                if temporary { "TEMPORARY " } else { "" }
+            ))
+            .await?;
+
+        Ok(PgReplicationSlot {
+            name: row.try_get("slot_name")?,
+            consistent_point: row.try_get::<String, _>("consistent_point")?.parse()?,
+            snapshot_name: row.try_get("snapshot_name")?,
+        })
+    }
+
+    /// Drop a replication slot, optionally waiting for it to stop being used.
+    pub async fn drop_replication_slot(&mut self, name: &str, wait: bool) -> Result<(), Error> {
+        self.conn
+            .execute(&*format!(
+                r#"DROP_REPLICATION_SLOT "{}"{}"#,
+                ident(name),
+                if wait { " WAIT" } else { "" }
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Start streaming changes for `publications` from a slot, beginning at `start_lsn`.
+    ///
+    /// The server resumes from the slot's confirmed position if that is later than `start_lsn`,
+    /// so `PgLsn::default()` can be used to continue where the last stream left off.
+    pub async fn start_replication(
+        mut self,
+        slot: &str,
+        publications: &[&str],
+        start_lsn: PgLsn,
+    ) -> Result<PgReplicationStream, Error> {
+        let publications = publications
+            .iter()
+            .map(|name| format!(r#""{}""#, ident(name)))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let query = format!(
+            r#"START_REPLICATION SLOT "{}" LOGICAL {} (proto_version '1', publication_names '{}')"#,
+            ident(slot),
+            start_lsn,
+            publications.replace('\'', "''")
+        );
+
+        self.conn.wait_until_ready().await?;
+
+        // the `ReadyForQuery` only arrives once streaming has stopped
+        self.conn.queue_simple_query(&query);
+        self.conn.stream.flush().await?;
+
+        self.conn
+            .stream
+            .recv_expect::<Bytes>(MessageFormat::CopyBothResponse)
+            .await?;
+
+        Ok(PgReplicationStream {
+            conn: self.conn,
+            decoder: PgOutputDecoder::default(),
+            received_lsn: start_lsn,
+            flushed_lsn: start_lsn,
+            pending_flush: None,
+            status_interval: Duration::from_secs(10),
+            last_status: Instant::now(),
+            done: false,
+        })
+    }
+
+    /// Explicitly close this connection.
+    pub async fn close(self) -> Result<(), Error> {
+        self.conn.close().await
+    }
+}
+
+/// A stream of changes from a logical replication slot.
+///
+/// Standby status updates are sent to the server automatically: when it asks for one, and
+/// otherwise every [`status_interval`][Self::set_status_interval] while [`recv()`][Self::recv]
+/// is being called. A transaction is acknowledged as flushed (allowing the server to discard
+/// its WAL) once its [`Commit`][PgReplicationEvent::Commit] has been returned and `recv()` is
+/// called again, so an event should be fully processed before asking for the next one.
+pub struct PgReplicationStream {
+    conn: PgConnection,
+    decoder: PgOutputDecoder,
+    received_lsn: PgLsn,
+    flushed_lsn: PgLsn,
+    // the end of the last commit returned from `recv()`
+    pending_flush: Option<PgLsn>,
+    status_interval: Duration,
+    last_status: Instant,
+    // the server has ended the stream
+    done: bool,
+}
+
+impl PgReplicationStream {
+    /// Receive the next change, or `None` if the server has ended the stream.
+    pub async fn recv(&mut self) -> Result<Option<PgReplicationEvent>, Error> {
+        if let Some(lsn) = self.pending_flush.take() {
+            self.flushed_lsn = lsn;
+        }
+
+        loop {
+            if self.done {
+                return Ok(None);
+            }
+
+            if self.last_status.elapsed() >= self.status_interval {
+                self.send_status().await?;
+            }
+
+            let message = self.conn.stream.recv().await?;
+
+            match message.format {
+                MessageFormat::CopyData => {
+                    let mut buf = message.contents;
+
+                    match buf.get_u8() {
+                        // XLogData
+                        b'w' => {
+                            let _start = buf.get_u64();
+                            self.advance_received(buf.get_u64());
+                            let _server_time = buf.get_i64();
+
+                            let event = self.decoder.decode(buf)?;
+
+                            if let Some(PgReplicationEvent::Commit { end_lsn, .. }) = &event {
+                                self.pending_flush = Some(*end_lsn);
+                            }
+
+                            if event.is_some() {
+                                return Ok(event);
+                            }
+                        }
+
+                        // Primary keepalive message
+                        b'k' => {
+                            self.advance_received(buf.get_u64());
+                            let _server_time = buf.get_i64();
+
+                            if buf.get_u8() == 1 {
+                                self.send_status().await?;
+                            }
+                        }
+
+                        tag => {
+                            return Err(err_protocol!(
+                                "replication: unexpected message: {:?}",
+                                tag as char
+                            ));
+                        }
+                    }
+                }
+
+                MessageFormat::CopyDone => {
+                    // the server has finished streaming; finish our side too
+                    self.conn.stream.send(CopyDone).await?;
+                    self.done = true;
+                }
+
+                format => {
+                    return Err(err_protocol!(
+                        "replication: unexpected message: {:?}",
+                        format
+                    ));
+                }
+            }
+        }
+    }
+
+    /// The last WAL position received from the server.
+    pub fn received_lsn(&self) -> PgLsn {
+        self.received_lsn
+    }
+
+    /// The WAL position acknowledged to the server as processed.
+    pub fn flushed_lsn(&self) -> PgLsn {
+        self.flushed_lsn
+    }
+
+    /// Set how often a status update is sent to the server. Defaults to 10 seconds.
+    ///
+    /// This must be less than the server's `wal_sender_timeout`.
+    pub fn set_status_interval(&mut self, interval: Duration) {
+        self.status_interval = interval;
+    }
+
+    /// Stop streaming and return to a connection that can run replication commands.
+    pub async fn stop(mut self) -> Result<PgReplicationConnection, Error> {
+        if !self.done {
+            if let Some(lsn) = self.pending_flush.take() {
+                self.flushed_lsn = lsn;
+            }
+
+            self.send_status().await?;
+            self.conn.stream.send(CopyDone).await?;
+
+            // discard anything sent before the server saw our `CopyDone`
+            loop {
+                let message = self.conn.stream.recv().await?;
+
+                match message.format {
+                    MessageFormat::CopyData => {}
+                    MessageFormat::CopyDone => break,
+                    format => {
+                        return Err(err_protocol!(
+                            "replication: unexpected message: {:?}",
+                            format
+                        ));
+                    }
+                }
+            }
+        }
+
+        self.conn.wait_until_ready().await?;
+
+        Ok(PgReplicationConnection { conn: self.conn })
+    }
+
+    fn advance_received(&mut self, wal_end: u64) {
+        self.received_lsn = cmp::max(self.received_lsn, PgLsn::from(wal_end));
+    }
+
+    // https://www.postgresql.org/docs/current/protocol-replication.html#PROTOCOL-REPLICATION-STANDBY-STATUS-UPDATE
+    async fn send_status(&mut self) -> Result<(), Error> {
+        let mut status = Vec::with_capacity(34);
+
+        status.put_u8(b'r');
+        // written
+        status.put_u64(self.received_lsn.as_u64());
+        // flushed
+        status.put_u64(self.flushed_lsn.as_u64());
+        // applied
+        status.put_u64(self.flushed_lsn.as_u64());
+        status.put_i64(pgoutput::now_micros());
+        // don't ask the server to reply
+        status.put_u8(0);
+
+        self.conn.stream.send(CopyData(status)).await?;
+        self.last_status = Instant::now();
+
+        Ok(())
+    }
+}
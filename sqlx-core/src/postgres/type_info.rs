@@ -99,6 +99,10 @@ pub enum PgType {
     RecordArray,
     Uuid,
     UuidArray,
+    Tsvector,
+    TsvectorArray,
+    Tsquery,
+    TsqueryArray,
     Jsonb,
     JsonbArray,
     Int4Range,
@@ -113,6 +117,18 @@ pub enum PgType {
     DateRangeArray,
     Int8Range,
     Int8RangeArray,
+    Int4MultiRange,
+    Int4MultiRangeArray,
+    NumMultiRange,
+    NumMultiRangeArray,
+    TsMultiRange,
+    TsMultiRangeArray,
+    TstzMultiRange,
+    TstzMultiRangeArray,
+    DateMultiRange,
+    DateMultiRangeArray,
+    Int8MultiRange,
+    Int8MultiRangeArray,
     Jsonpath,
     JsonpathArray,
     Money,
@@ -152,6 +168,8 @@ pub enum PgTypeKind {
     Array(PgTypeInfo),
     Enum(Arc<[String]>),
     Range(PgTypeInfo),
+    /// A multirange, holding its range type.
+    MultiRange(PgTypeInfo),
 }
 
 impl PgTypeInfo {
@@ -213,6 +231,9 @@ impl PgTypeInfo {
     ///
     /// The OID for the type will be fetched from Postgres on use of
     /// a value of this type. The fetched OID will be cached per-connection.
+    ///
+    /// An array of a type can be named with a trailing `[]` (e.g. `"mood[]"`), which unlike the
+    /// `_mood` name Postgres usually gives it, is always correct.
     pub const fn with_name(name: &'static str) -> Self {
         Self(PgType::DeclareWithName(UStr::Static(name)))
     }
@@ -313,6 +334,10 @@ impl PgType {
             2287 => PgType::RecordArray,
             2950 => PgType::Uuid,
             2951 => PgType::UuidArray,
+            3614 => PgType::Tsvector,
+            3643 => PgType::TsvectorArray,
+            3615 => PgType::Tsquery,
+            3645 => PgType::TsqueryArray,
             3802 => PgType::Jsonb,
             3807 => PgType::JsonbArray,
             3904 => PgType::Int4Range,
@@ -327,6 +352,18 @@ impl PgType {
             3913 => PgType::DateRangeArray,
             3926 => PgType::Int8Range,
             3927 => PgType::Int8RangeArray,
+            4451 => PgType::Int4MultiRange,
+            6150 => PgType::Int4MultiRangeArray,
+            4532 => PgType::NumMultiRange,
+            6151 => PgType::NumMultiRangeArray,
+            4533 => PgType::TsMultiRange,
+            6152 => PgType::TsMultiRangeArray,
+            4534 => PgType::TstzMultiRange,
+            6153 => PgType::TstzMultiRangeArray,
+            4535 => PgType::DateMultiRange,
+            6155 => PgType::DateMultiRangeArray,
+            4536 => PgType::Int8MultiRange,
+            6157 => PgType::Int8MultiRangeArray,
             4072 => PgType::Jsonpath,
             4073 => PgType::JsonpathArray,
 
@@ -421,6 +458,10 @@ impl PgType {
             PgType::RecordArray => Oid(2287),
             PgType::Uuid => Oid(2950),
             PgType::UuidArray => Oid(2951),
+            PgType::Tsvector => Oid(3614),
+            PgType::TsvectorArray => Oid(3643),
+            PgType::Tsquery => Oid(3615),
+            PgType::TsqueryArray => Oid(3645),
             PgType::Jsonb => Oid(3802),
             PgType::JsonbArray => Oid(3807),
             PgType::Int4Range => Oid(3904),
@@ -435,6 +476,18 @@ impl PgType {
             PgType::DateRangeArray => Oid(3913),
             PgType::Int8Range => Oid(3926),
             PgType::Int8RangeArray => Oid(3927),
+            PgType::Int4MultiRange => Oid(4451),
+            PgType::Int4MultiRangeArray => Oid(6150),
+            PgType::NumMultiRange => Oid(4532),
+            PgType::NumMultiRangeArray => Oid(6151),
+            PgType::TsMultiRange => Oid(4533),
+            PgType::TsMultiRangeArray => Oid(6152),
+            PgType::TstzMultiRange => Oid(4534),
+            PgType::TstzMultiRangeArray => Oid(6153),
+            PgType::DateMultiRange => Oid(4535),
+            PgType::DateMultiRangeArray => Oid(6155),
+            PgType::Int8MultiRange => Oid(4536),
+            PgType::Int8MultiRangeArray => Oid(6157),
             PgType::Jsonpath => Oid(4072),
             PgType::JsonpathArray => Oid(4073),
             PgType::Custom(ty) => ty.oid,
@@ -521,6 +574,10 @@ impl PgType {
             PgType::RecordArray => "RECORD[]",
             PgType::Uuid => "UUID",
             PgType::UuidArray => "UUID[]",
+            PgType::Tsvector => "TSVECTOR",
+            PgType::TsvectorArray => "TSVECTOR[]",
+            PgType::Tsquery => "TSQUERY",
+            PgType::TsqueryArray => "TSQUERY[]",
             PgType::Jsonb => "JSONB",
             PgType::JsonbArray => "JSONB[]",
             PgType::Int4Range => "INT4RANGE",
@@ -535,6 +592,18 @@ impl PgType {
             PgType::DateRangeArray => "DATERANGE[]",
             PgType::Int8Range => "INT8RANGE",
             PgType::Int8RangeArray => "INT8RANGE[]",
+            PgType::Int4MultiRange => "INT4MULTIRANGE",
+            PgType::Int4MultiRangeArray => "INT4MULTIRANGE[]",
+            PgType::NumMultiRange => "NUMMULTIRANGE",
+            PgType::NumMultiRangeArray => "NUMMULTIRANGE[]",
+            PgType::TsMultiRange => "TSMULTIRANGE",
+            PgType::TsMultiRangeArray => "TSMULTIRANGE[]",
+            PgType::TstzMultiRange => "TSTZMULTIRANGE",
+            PgType::TstzMultiRangeArray => "TSTZMULTIRANGE[]",
+            PgType::DateMultiRange => "DATEMULTIRANGE",
+            PgType::DateMultiRangeArray => "DATEMULTIRANGE[]",
+            PgType::Int8MultiRange => "INT8MULTIRANGE",
+            PgType::Int8MultiRangeArray => "INT8MULTIRANGE[]",
             PgType::Jsonpath => "JSONPATH",
             PgType::JsonpathArray => "JSONPATH[]",
             PgType::Money => "MONEY",
@@ -621,6 +690,10 @@ impl PgType {
             PgType::RecordArray => "_record",
             PgType::Uuid => "uuid",
             PgType::UuidArray => "_uuid",
+            PgType::Tsvector => "tsvector",
+            PgType::TsvectorArray => "_tsvector",
+            PgType::Tsquery => "tsquery",
+            PgType::TsqueryArray => "_tsquery",
             PgType::Jsonb => "jsonb",
             PgType::JsonbArray => "_jsonb",
             PgType::Int4Range => "int4range",
@@ -635,6 +708,18 @@ impl PgType {
             PgType::DateRangeArray => "_daterange",
             PgType::Int8Range => "int8range",
             PgType::Int8RangeArray => "_int8range",
+            PgType::Int4MultiRange => "int4multirange",
+            PgType::Int4MultiRangeArray => "_int4multirange",
+            PgType::NumMultiRange => "nummultirange",
+            PgType::NumMultiRangeArray => "_nummultirange",
+            PgType::TsMultiRange => "tsmultirange",
+            PgType::TsMultiRangeArray => "_tsmultirange",
+            PgType::TstzMultiRange => "tstzmultirange",
+            PgType::TstzMultiRangeArray => "_tstzmultirange",
+            PgType::DateMultiRange => "datemultirange",
+            PgType::DateMultiRangeArray => "_datemultirange",
+            PgType::Int8MultiRange => "int8multirange",
+            PgType::Int8MultiRangeArray => "_int8multirange",
             PgType::Jsonpath => "jsonpath",
             PgType::JsonpathArray => "_jsonpath",
             PgType::Money => "money",
@@ -721,6 +806,10 @@ impl PgType {
             PgType::RecordArray => &PgTypeKind::Array(PgTypeInfo(PgType::Record)),
             PgType::Uuid => &PgTypeKind::Simple,
             PgType::UuidArray => &PgTypeKind::Array(PgTypeInfo(PgType::Uuid)),
+            PgType::Tsvector => &PgTypeKind::Simple,
+            PgType::TsvectorArray => &PgTypeKind::Array(PgTypeInfo(PgType::Tsvector)),
+            PgType::Tsquery => &PgTypeKind::Simple,
+            PgType::TsqueryArray => &PgTypeKind::Array(PgTypeInfo(PgType::Tsquery)),
             PgType::Jsonb => &PgTypeKind::Simple,
             PgType::JsonbArray => &PgTypeKind::Array(PgTypeInfo(PgType::Jsonb)),
             PgType::Int4Range => &PgTypeKind::Range(PgTypeInfo::INT4),
@@ -735,6 +824,18 @@ impl PgType {
             PgType::DateRangeArray => &PgTypeKind::Array(PgTypeInfo(PgType::DateRange)),
             PgType::Int8Range => &PgTypeKind::Range(PgTypeInfo::INT8),
             PgType::Int8RangeArray => &PgTypeKind::Array(PgTypeInfo(PgType::Int8Range)),
+            PgType::Int4MultiRange => &PgTypeKind::MultiRange(PgTypeInfo::INT4_RANGE),
+            PgType::Int4MultiRangeArray => &PgTypeKind::Array(PgTypeInfo(PgType::Int4MultiRange)),
+            PgType::NumMultiRange => &PgTypeKind::MultiRange(PgTypeInfo::NUM_RANGE),
+            PgType::NumMultiRangeArray => &PgTypeKind::Array(PgTypeInfo(PgType::NumMultiRange)),
+            PgType::TsMultiRange => &PgTypeKind::MultiRange(PgTypeInfo::TS_RANGE),
+            PgType::TsMultiRangeArray => &PgTypeKind::Array(PgTypeInfo(PgType::TsMultiRange)),
+            PgType::TstzMultiRange => &PgTypeKind::MultiRange(PgTypeInfo::TSTZ_RANGE),
+            PgType::TstzMultiRangeArray => &PgTypeKind::Array(PgTypeInfo(PgType::TstzMultiRange)),
+            PgType::DateMultiRange => &PgTypeKind::MultiRange(PgTypeInfo::DATE_RANGE),
+            PgType::DateMultiRangeArray => &PgTypeKind::Array(PgTypeInfo(PgType::DateMultiRange)),
+            PgType::Int8MultiRange => &PgTypeKind::MultiRange(PgTypeInfo::INT8_RANGE),
+            PgType::Int8MultiRangeArray => &PgTypeKind::Array(PgTypeInfo(PgType::Int8MultiRange)),
             PgType::Jsonpath => &PgTypeKind::Simple,
             PgType::JsonpathArray => &PgTypeKind::Array(PgTypeInfo(PgType::Jsonpath)),
             PgType::Money => &PgTypeKind::Simple,
@@ -834,6 +935,10 @@ impl PgType {
             PgType::RecordArray => Some(Cow::Owned(PgTypeInfo(PgType::Record))),
             PgType::Uuid => None,
             PgType::UuidArray => Some(Cow::Owned(PgTypeInfo(PgType::Uuid))),
+            PgType::Tsvector => None,
+            PgType::TsvectorArray => Some(Cow::Owned(PgTypeInfo(PgType::Tsvector))),
+            PgType::Tsquery => None,
+            PgType::TsqueryArray => Some(Cow::Owned(PgTypeInfo(PgType::Tsquery))),
             PgType::Jsonb => None,
             PgType::JsonbArray => Some(Cow::Owned(PgTypeInfo(PgType::Jsonb))),
             PgType::Int4Range => None,
@@ -848,6 +953,18 @@ impl PgType {
             PgType::DateRangeArray => Some(Cow::Owned(PgTypeInfo(PgType::DateRange))),
             PgType::Int8Range => None,
             PgType::Int8RangeArray => Some(Cow::Owned(PgTypeInfo(PgType::Int8Range))),
+            PgType::Int4MultiRange => None,
+            PgType::Int4MultiRangeArray => Some(Cow::Owned(PgTypeInfo(PgType::Int4MultiRange))),
+            PgType::NumMultiRange => None,
+            PgType::NumMultiRangeArray => Some(Cow::Owned(PgTypeInfo(PgType::NumMultiRange))),
+            PgType::TsMultiRange => None,
+            PgType::TsMultiRangeArray => Some(Cow::Owned(PgTypeInfo(PgType::TsMultiRange))),
+            PgType::TstzMultiRange => None,
+            PgType::TstzMultiRangeArray => Some(Cow::Owned(PgTypeInfo(PgType::TstzMultiRange))),
+            PgType::DateMultiRange => None,
+            PgType::DateMultiRangeArray => Some(Cow::Owned(PgTypeInfo(PgType::DateMultiRange))),
+            PgType::Int8MultiRange => None,
+            PgType::Int8MultiRangeArray => Some(Cow::Owned(PgTypeInfo(PgType::Int8MultiRange))),
             PgType::Jsonpath => None,
             PgType::JsonpathArray => Some(Cow::Owned(PgTypeInfo(PgType::Jsonpath))),
             // There is no `UnknownArray`
@@ -862,6 +979,7 @@ impl PgType {
                 PgTypeKind::Array(ref elem_type_info) => Some(Cow::Borrowed(elem_type_info)),
                 PgTypeKind::Enum(_) => None,
                 PgTypeKind::Range(_) => None,
+                PgTypeKind::MultiRange(_) => None,
             },
             PgType::DeclareWithOid(oid) => {
                 unreachable!("(bug) use of unresolved type declaration [oid={}]", oid.0);
@@ -906,6 +1024,17 @@ impl PgTypeInfo {
     pub(crate) const UUID: Self = Self(PgType::Uuid);
     pub(crate) const UUID_ARRAY: Self = Self(PgType::UuidArray);
 
+    //
+    // text search types
+    // https://www.postgresql.org/docs/current/datatype-textsearch.html
+    //
+
+    pub(crate) const TSVECTOR: Self = Self(PgType::Tsvector);
+    pub(crate) const TSVECTOR_ARRAY: Self = Self(PgType::TsvectorArray);
+
+    pub(crate) const TSQUERY: Self = Self(PgType::Tsquery);
+    pub(crate) const TSQUERY_ARRAY: Self = Self(PgType::TsqueryArray);
+
     // record
     pub(crate) const RECORD: Self = Self(PgType::Record);
     pub(crate) const RECORD_ARRAY: Self = Self(PgType::RecordArray);
@@ -1102,6 +1231,29 @@ impl PgTypeInfo {
     pub(crate) const INT8_RANGE: Self = Self(PgType::Int8Range);
     pub(crate) const INT8_RANGE_ARRAY: Self = Self(PgType::Int8RangeArray);
 
+    //
+    // multirange types
+    // https://www.postgresql.org/docs/current/rangetypes.html
+    //
+
+    pub(crate) const INT4_MULTIRANGE: Self = Self(PgType::Int4MultiRange);
+    pub(crate) const INT4_MULTIRANGE_ARRAY: Self = Self(PgType::Int4MultiRangeArray);
+
+    pub(crate) const NUM_MULTIRANGE: Self = Self(PgType::NumMultiRange);
+    pub(crate) const NUM_MULTIRANGE_ARRAY: Self = Self(PgType::NumMultiRangeArray);
+
+    pub(crate) const TS_MULTIRANGE: Self = Self(PgType::TsMultiRange);
+    pub(crate) const TS_MULTIRANGE_ARRAY: Self = Self(PgType::TsMultiRangeArray);
+
+    pub(crate) const TSTZ_MULTIRANGE: Self = Self(PgType::TstzMultiRange);
+    pub(crate) const TSTZ_MULTIRANGE_ARRAY: Self = Self(PgType::TstzMultiRangeArray);
+
+    pub(crate) const DATE_MULTIRANGE: Self = Self(PgType::DateMultiRange);
+    pub(crate) const DATE_MULTIRANGE_ARRAY: Self = Self(PgType::DateMultiRangeArray);
+
+    pub(crate) const INT8_MULTIRANGE: Self = Self(PgType::Int8MultiRange);
+    pub(crate) const INT8_MULTIRANGE_ARRAY: Self = Self(PgType::Int8MultiRangeArray);
+
     //
     // pseudo types
     // https://www.postgresql.org/docs/9.3/datatype-pseudo.html
@@ -1130,6 +1282,11 @@ impl PartialEq<PgType> for PgType {
             // This only occurs in the TEXT protocol with custom types
             // Just opt-out of type checking here
             true
+        } else if let Some(matches) = self
+            .array_of_name_eq(other)
+            .or_else(|| other.array_of_name_eq(self))
+        {
+            matches
         } else {
             // Otherwise, perform a match on the name
             self.name().eq_ignore_ascii_case(other.name())
@@ -1137,6 +1294,29 @@ impl PartialEq<PgType> for PgType {
     }
 }
 
+impl PgType {
+    // a type declared as `with_name("element[]")` is equal to an array of `element`;
+    // `None` if `self` is not declared that way or `other` is not a resolved type
+    fn array_of_name_eq(&self, other: &PgType) -> Option<bool> {
+        let element_name = match self {
+            PgType::DeclareWithName(name) => name.strip_suffix("[]")?,
+            _ => return None,
+        };
+
+        if matches!(
+            other,
+            PgType::DeclareWithName(_) | PgType::DeclareWithOid(_)
+        ) {
+            return None;
+        }
+
+        Some(match other.kind() {
+            PgTypeKind::Array(element) => element.name().eq_ignore_ascii_case(element_name),
+            _ => false,
+        })
+    }
+}
+
 #[cfg(feature = "any")]
 impl From<PgTypeInfo> for crate::any::AnyTypeInfo {
     #[inline]
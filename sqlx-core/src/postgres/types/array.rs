@@ -219,10 +219,11 @@ where
                 // NOTE: Nearly *all* types use ',' as the sequence delimiter. Yes, there is one
                 //       that does not. The BOX (not PostGIS) type uses ';' as a delimiter.
 
-                // TODO: When we add support for BOX we need to figure out some way to make the
-                //       delimiter selection
-
-                let delimiter = ',';
+                let delimiter = if matches!(element_type_info.0, PgType::Box) {
+                    ';'
+                } else {
+                    ','
+                };
                 let mut done = false;
                 let mut in_quotes = false;
                 let mut in_escape = false;
@@ -0,0 +1,454 @@
+//! Geometric types.
+//!
+//! See https://www.postgresql.org/docs/current/datatype-geometric.html
+use std::cmp;
+use std::mem;
+
+use byteorder::{NetworkEndian, ReadBytesExt};
+
+use crate::decode::Decode;
+use crate::encode::{Encode, IsNull};
+use crate::error::BoxDynError;
+use crate::postgres::{
+    PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueFormat, PgValueRef, Postgres,
+};
+use crate::types::Type;
+
+/// A point on a plane (`POINT`).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PgPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// An infinite line (`LINE`), represented by the equation `Ax + By + C = 0`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PgLine {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+}
+
+/// A finite line segment (`LSEG`).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PgLSeg {
+    pub start: PgPoint,
+    pub end: PgPoint,
+}
+
+/// A rectangular box (`BOX`), represented by two opposite corners.
+///
+/// Postgres always returns the upper right corner first, reordering the corners if needed.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PgBox {
+    pub upper_right: PgPoint,
+    pub lower_left: PgPoint,
+}
+
+/// An open or closed path (`PATH`).
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PgPath {
+    /// A closed path joins its last point back to its first.
+    pub closed: bool,
+    pub points: Vec<PgPoint>,
+}
+
+/// A polygon (`POLYGON`), which is similar to a closed path.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PgPolygon {
+    pub points: Vec<PgPoint>,
+}
+
+/// A circle (`CIRCLE`).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PgCircle {
+    pub center: PgPoint,
+    pub radius: f64,
+}
+
+macro_rules! impl_type {
+    ($ty:ty, $type_info:ident, $array_type_info:ident) => {
+        impl Type<Postgres> for $ty {
+            fn type_info() -> PgTypeInfo {
+                PgTypeInfo::$type_info
+            }
+        }
+
+        impl PgHasArrayType for $ty {
+            fn array_type_info() -> PgTypeInfo {
+                PgTypeInfo::$array_type_info
+            }
+        }
+    };
+}
+
+impl_type!(PgPoint, POINT, POINT_ARRAY);
+impl_type!(PgLine, LINE, LINE_ARRAY);
+impl_type!(PgLSeg, LSEG, LSEG_ARRAY);
+impl_type!(PgBox, BOX, BOX_ARRAY);
+impl_type!(PgPath, PATH, PATH_ARRAY);
+impl_type!(PgPolygon, POLYGON, POLYGON_ARRAY);
+impl_type!(PgCircle, CIRCLE, CIRCLE_ARRAY);
+
+// https://github.com/postgres/postgres/blob/master/src/backend/utils/adt/geo_ops.c
+// every type is sent as a sequence of float8s, with a point count for paths and polygons
+
+impl PgPoint {
+    fn write(&self, buf: &mut PgArgumentBuffer) {
+        buf.extend(&self.x.to_be_bytes());
+        buf.extend(&self.y.to_be_bytes());
+    }
+
+    fn read(buf: &mut &[u8]) -> Result<Self, BoxDynError> {
+        Ok(Self {
+            x: buf.read_f64::<NetworkEndian>()?,
+            y: buf.read_f64::<NetworkEndian>()?,
+        })
+    }
+
+    fn from_floats(floats: &[f64]) -> Self {
+        Self {
+            x: floats[0],
+            y: floats[1],
+        }
+    }
+}
+
+impl Encode<'_, Postgres> for PgPoint {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        self.write(buf);
+
+        IsNull::No
+    }
+
+    fn size_hint(&self) -> usize {
+        2 * mem::size_of::<f64>()
+    }
+}
+
+impl Decode<'_, Postgres> for PgPoint {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        match value.format() {
+            PgValueFormat::Binary => PgPoint::read(&mut value.as_bytes()?),
+
+            // (x,y)
+            PgValueFormat::Text => Ok(PgPoint::from_floats(&parse_floats(
+                value.as_str()?,
+                Some(2),
+            )?)),
+        }
+    }
+}
+
+impl Encode<'_, Postgres> for PgLine {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        buf.extend(&self.a.to_be_bytes());
+        buf.extend(&self.b.to_be_bytes());
+        buf.extend(&self.c.to_be_bytes());
+
+        IsNull::No
+    }
+
+    fn size_hint(&self) -> usize {
+        3 * mem::size_of::<f64>()
+    }
+}
+
+impl Decode<'_, Postgres> for PgLine {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+
+                Ok(PgLine {
+                    a: buf.read_f64::<NetworkEndian>()?,
+                    b: buf.read_f64::<NetworkEndian>()?,
+                    c: buf.read_f64::<NetworkEndian>()?,
+                })
+            }
+
+            // {A,B,C}
+            PgValueFormat::Text => {
+                let floats = parse_floats(value.as_str()?, Some(3))?;
+
+                Ok(PgLine {
+                    a: floats[0],
+                    b: floats[1],
+                    c: floats[2],
+                })
+            }
+        }
+    }
+}
+
+impl Encode<'_, Postgres> for PgLSeg {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        self.start.write(buf);
+        self.end.write(buf);
+
+        IsNull::No
+    }
+
+    fn size_hint(&self) -> usize {
+        4 * mem::size_of::<f64>()
+    }
+}
+
+impl Decode<'_, Postgres> for PgLSeg {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+
+                Ok(PgLSeg {
+                    start: PgPoint::read(&mut buf)?,
+                    end: PgPoint::read(&mut buf)?,
+                })
+            }
+
+            // [(x1,y1),(x2,y2)]
+            PgValueFormat::Text => {
+                let floats = parse_floats(value.as_str()?, Some(4))?;
+
+                Ok(PgLSeg {
+                    start: PgPoint::from_floats(&floats[..2]),
+                    end: PgPoint::from_floats(&floats[2..]),
+                })
+            }
+        }
+    }
+}
+
+impl Encode<'_, Postgres> for PgBox {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        self.upper_right.write(buf);
+        self.lower_left.write(buf);
+
+        IsNull::No
+    }
+
+    fn size_hint(&self) -> usize {
+        4 * mem::size_of::<f64>()
+    }
+}
+
+impl Decode<'_, Postgres> for PgBox {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+
+                Ok(PgBox {
+                    upper_right: PgPoint::read(&mut buf)?,
+                    lower_left: PgPoint::read(&mut buf)?,
+                })
+            }
+
+            // (x1,y1),(x2,y2)
+            PgValueFormat::Text => {
+                let floats = parse_floats(value.as_str()?, Some(4))?;
+
+                Ok(PgBox {
+                    upper_right: PgPoint::from_floats(&floats[..2]),
+                    lower_left: PgPoint::from_floats(&floats[2..]),
+                })
+            }
+        }
+    }
+}
+
+impl Encode<'_, Postgres> for PgPath {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        buf.push(self.closed as u8);
+        write_points(&self.points, buf);
+
+        IsNull::No
+    }
+
+    fn size_hint(&self) -> usize {
+        1 + mem::size_of::<i32>() + self.points.len() * 2 * mem::size_of::<f64>()
+    }
+}
+
+impl Decode<'_, Postgres> for PgPath {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+                let closed = buf.read_u8()? != 0;
+
+                Ok(PgPath {
+                    closed,
+                    points: read_points(&mut buf)?,
+                })
+            }
+
+            // [(x1,y1),...] if open, ((x1,y1),...) if closed
+            PgValueFormat::Text => {
+                let s = value.as_str()?;
+
+                Ok(PgPath {
+                    closed: !s.trim_start().starts_with('['),
+                    points: parse_points(s)?,
+                })
+            }
+        }
+    }
+}
+
+impl Encode<'_, Postgres> for PgPolygon {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        write_points(&self.points, buf);
+
+        IsNull::No
+    }
+
+    fn size_hint(&self) -> usize {
+        mem::size_of::<i32>() + self.points.len() * 2 * mem::size_of::<f64>()
+    }
+}
+
+impl Decode<'_, Postgres> for PgPolygon {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        match value.format() {
+            PgValueFormat::Binary => Ok(PgPolygon {
+                points: read_points(&mut value.as_bytes()?)?,
+            }),
+
+            // ((x1,y1),...)
+            PgValueFormat::Text => Ok(PgPolygon {
+                points: parse_points(value.as_str()?)?,
+            }),
+        }
+    }
+}
+
+impl Encode<'_, Postgres> for PgCircle {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        self.center.write(buf);
+        buf.extend(&self.radius.to_be_bytes());
+
+        IsNull::No
+    }
+
+    fn size_hint(&self) -> usize {
+        3 * mem::size_of::<f64>()
+    }
+}
+
+impl Decode<'_, Postgres> for PgCircle {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        match value.format() {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+
+                Ok(PgCircle {
+                    center: PgPoint::read(&mut buf)?,
+                    radius: buf.read_f64::<NetworkEndian>()?,
+                })
+            }
+
+            // <(x,y),r>
+            PgValueFormat::Text => {
+                let floats = parse_floats(value.as_str()?, Some(3))?;
+
+                Ok(PgCircle {
+                    center: PgPoint::from_floats(&floats[..2]),
+                    radius: floats[2],
+                })
+            }
+        }
+    }
+}
+
+fn write_points(points: &[PgPoint], buf: &mut PgArgumentBuffer) {
+    buf.extend(&(points.len() as i32).to_be_bytes());
+
+    for point in points {
+        point.write(buf);
+    }
+}
+
+fn read_points(buf: &mut &[u8]) -> Result<Vec<PgPoint>, BoxDynError> {
+    let len = buf.read_i32::<NetworkEndian>()?;
+
+    if len < 0 {
+        return Err(format!("negative number of points: {}", len).into());
+    }
+
+    // don't trust the count for the allocation
+    let mut points = Vec::with_capacity(cmp::min(len as usize, buf.len() / 16));
+
+    for _ in 0..len {
+        points.push(PgPoint::read(buf)?);
+    }
+
+    Ok(points)
+}
+
+fn parse_points(s: &str) -> Result<Vec<PgPoint>, BoxDynError> {
+    let floats = parse_floats(s, None)?;
+
+    if floats.len() % 2 != 0 {
+        return Err(format!("odd number of coordinates in {:?}", s).into());
+    }
+
+    Ok(floats.chunks(2).map(PgPoint::from_floats).collect())
+}
+
+/// Parse the numbers out of the text form of a geometric type, ignoring the punctuation.
+fn parse_floats(s: &str, expected: Option<usize>) -> Result<Vec<f64>, BoxDynError> {
+    let floats = s
+        .split(|c: char| "()[]{}<>,".contains(c) || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<f64>, _>>()?;
+
+    match expected {
+        Some(expected) if floats.len() != expected => Err(format!(
+            "expected {} numbers, got {} in {:?}",
+            expected,
+            floats.len(),
+            s
+        )
+        .into()),
+
+        _ => Ok(floats),
+    }
+}
+
+#[test]
+fn test_parse_geometry_text() {
+    assert_eq!(parse_floats("(1.5,-2)", Some(2)).unwrap(), [1.5, -2.0]);
+    assert_eq!(
+        parse_floats("<(1,2),Infinity>", Some(3)).unwrap(),
+        [1.0, 2.0, f64::INFINITY]
+    );
+    assert!(parse_floats("(1,2)", Some(3)).is_err());
+
+    assert_eq!(
+        parse_points("[(0,0),(1,1),(2,0)]").unwrap(),
+        [
+            PgPoint { x: 0.0, y: 0.0 },
+            PgPoint { x: 1.0, y: 1.0 },
+            PgPoint { x: 2.0, y: 0.0 },
+        ]
+    );
+    assert!(parse_points("((0,0),(1))").is_err());
+}
+
+#[test]
+fn test_read_points() {
+    let mut buf = PgArgumentBuffer::default();
+    write_points(&[PgPoint { x: 1.0, y: 2.0 }], &mut buf);
+
+    let mut bytes = &buf[..];
+    assert_eq!(
+        read_points(&mut bytes).unwrap(),
+        [PgPoint { x: 1.0, y: 2.0 }]
+    );
+
+    // the count says there are more points than were sent
+    let mut truncated = &[0, 0, 0, 2][..];
+    assert!(read_points(&mut truncated).is_err());
+}
@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::ops::{Deref, DerefMut};
+use std::str::Chars;
+
+use bytes::Buf;
+
+use crate::decode::Decode;
+use crate::encode::{Encode, IsNull};
+use crate::error::BoxDynError;
+use crate::postgres::{
+    PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueFormat, PgValueRef, Postgres,
+};
+use crate::types::Type;
+
+/// Key/value pairs (`hstore`) in Postgres.
+///
+/// See https://www.postgresql.org/docs/current/hstore.html
+///
+/// Keys are unique and always present; values may be `NULL`, which is represented as `None`.
+///
+/// ```rust
+/// # use sqlx_core::postgres::types::PgHStore;
+/// let mut store = PgHStore::default();
+/// store.insert("color".to_owned(), Some("blue".to_owned()));
+/// store.insert("size".to_owned(), None);
+///
+/// assert_eq!(store["color"].as_deref(), Some("blue"));
+/// ```
+///
+/// ### Note: Extension Required
+/// The `hstore` extension is not enabled by default in Postgres. You will need to do so explicitly:
+///
+/// ```ignore
+/// CREATE EXTENSION IF NOT EXISTS "hstore";
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PgHStore(pub BTreeMap<String, Option<String>>);
+
+impl Deref for PgHStore {
+    type Target = BTreeMap<String, Option<String>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for PgHStore {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<BTreeMap<String, Option<String>>> for PgHStore {
+    fn from(map: BTreeMap<String, Option<String>>) -> Self {
+        Self(map)
+    }
+}
+
+impl From<PgHStore> for BTreeMap<String, Option<String>> {
+    fn from(store: PgHStore) -> Self {
+        store.0
+    }
+}
+
+impl<K, V> FromIterator<(K, Option<V>)> for PgHStore
+where
+    K: Into<String>,
+    V: Into<String>,
+{
+    fn from_iter<I: IntoIterator<Item = (K, Option<V>)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(key, value)| (key.into(), value.map(Into::into)))
+                .collect(),
+        )
+    }
+}
+
+impl IntoIterator for PgHStore {
+    type Item = (String, Option<String>);
+    type IntoIter = std::collections::btree_map::IntoIter<String, Option<String>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl Type<Postgres> for PgHStore {
+    fn type_info() -> PgTypeInfo {
+        // Since `hstore` is enabled by an extension, it does not have a stable OID.
+        PgTypeInfo::with_name("hstore")
+    }
+}
+
+impl PgHasArrayType for PgHStore {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_hstore")
+    }
+}
+
+impl Encode<'_, Postgres> for PgHStore {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        // https://github.com/postgres/postgres/blob/master/contrib/hstore/hstore_io.c (hstore_send)
+        buf.extend(&(self.0.len() as i32).to_be_bytes());
+
+        for (key, value) in &self.0 {
+            buf.extend(&(key.len() as i32).to_be_bytes());
+            buf.extend(key.as_bytes());
+
+            match value {
+                Some(value) => {
+                    buf.extend(&(value.len() as i32).to_be_bytes());
+                    buf.extend(value.as_bytes());
+                }
+
+                None => {
+                    buf.extend(&(-1_i32).to_be_bytes());
+                }
+            }
+        }
+
+        IsNull::No
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for PgHStore {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        match value.format() {
+            PgValueFormat::Binary => decode_binary(value.as_bytes()?),
+            PgValueFormat::Text => decode_text(value.as_str()?),
+        }
+    }
+}
+
+fn decode_binary(mut buf: &[u8]) -> Result<PgHStore, BoxDynError> {
+    let count = read_len(&mut buf)?.ok_or("hstore: negative number of pairs")?;
+    let mut map = BTreeMap::new();
+
+    for _ in 0..count {
+        let key = read_str(&mut buf)?.ok_or("hstore: NULL key")?;
+        let value = read_str(&mut buf)?;
+
+        map.insert(key, value);
+    }
+
+    Ok(PgHStore(map))
+}
+
+// a length prefix; `None` if negative
+fn read_len(buf: &mut &[u8]) -> Result<Option<usize>, BoxDynError> {
+    if buf.remaining() < 4 {
+        return Err("hstore: unexpected end of data".into());
+    }
+
+    let len = buf.get_i32();
+
+    Ok((len >= 0).then_some(len as usize))
+}
+
+fn read_str(buf: &mut &[u8]) -> Result<Option<String>, BoxDynError> {
+    let len = match read_len(buf)? {
+        Some(len) => len,
+        None => return Ok(None),
+    };
+
+    if buf.remaining() < len {
+        return Err("hstore: unexpected end of data".into());
+    }
+
+    let s = std::str::from_utf8(&buf[..len])?.to_owned();
+    buf.advance(len);
+
+    Ok(Some(s))
+}
+
+// "a"=>"1", "b"=>NULL
+fn decode_text(s: &str) -> Result<PgHStore, BoxDynError> {
+    let mut chars = s.chars().peekable();
+    let mut map = BTreeMap::new();
+
+    loop {
+        skip_whitespace(&mut chars);
+
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let (key, _) = read_token(&mut chars)?;
+
+        skip_whitespace(&mut chars);
+
+        if chars.next() != Some('=') || chars.next() != Some('>') {
+            return Err(format!("hstore: expected `=>` after key {:?}", key).into());
+        }
+
+        skip_whitespace(&mut chars);
+
+        let (value, quoted) = read_token(&mut chars)?;
+        let value = (quoted || !value.eq_ignore_ascii_case("NULL")).then_some(value);
+
+        map.insert(key, value);
+
+        skip_whitespace(&mut chars);
+
+        match chars.next() {
+            Some(',') | None => {}
+            Some(c) => return Err(format!("hstore: unexpected character {:?}", c).into()),
+        }
+    }
+
+    Ok(PgHStore(map))
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars<'_>>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+// returns the token and whether it was quoted
+fn read_token(chars: &mut Peekable<Chars<'_>>) -> Result<(String, bool), BoxDynError> {
+    let mut token = String::new();
+
+    if chars.next_if_eq(&'"').is_some() {
+        loop {
+            match chars.next() {
+                Some('"') => return Ok((token, true)),
+                Some('\\') => token.push(chars.next().ok_or("hstore: unterminated string")?),
+                Some(c) => token.push(c),
+                None => return Err("hstore: unterminated string".into()),
+            }
+        }
+    }
+
+    while let Some(c) = chars.next_if(|&c| !c.is_whitespace() && c != '=' && c != ',') {
+        token.push(c);
+    }
+
+    if token.is_empty() {
+        return Err("hstore: expected a key or value".into());
+    }
+
+    Ok((token, false))
+}
+
+#[test]
+fn test_decode_hstore_binary() {
+    let buf =
+        b"\x00\x00\x00\x02\x00\x00\x00\x01a\x00\x00\x00\x011\x00\x00\x00\x01b\xff\xff\xff\xff";
+    let store = decode_binary(buf).unwrap();
+
+    assert_eq!(store["a"].as_deref(), Some("1"));
+    assert_eq!(store["b"], None);
+
+    assert!(decode_binary(&buf[..10]).is_err());
+}
+
+#[test]
+fn test_decode_hstore_text() {
+    let store = decode_text(r#""a"=>"1", "b"=>NULL, "c d"=>"x\"y", e => "NULL""#).unwrap();
+
+    assert_eq!(
+        store,
+        PgHStore::from_iter([
+            ("a", Some("1")),
+            ("b", None),
+            ("c d", Some("x\"y")),
+            ("e", Some("NULL")),
+        ])
+    );
+
+    assert!(decode_text("").unwrap().is_empty());
+    assert!(decode_text(r#""a"=>"1" "b""#).is_err());
+}
@@ -0,0 +1,295 @@
+use std::ops::{Deref, DerefMut};
+
+use bytes::Buf;
+
+use crate::decode::Decode;
+use crate::encode::{Encode, IsNull};
+use crate::error::BoxDynError;
+use crate::postgres::type_info::PgTypeKind;
+use crate::postgres::types::range::range_compatible;
+use crate::postgres::types::PgRange;
+use crate::postgres::{
+    PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueFormat, PgValueRef, Postgres,
+};
+use crate::types::Type;
+
+/// An ordered list of non-overlapping ranges (`INT4MULTIRANGE`, `TSTZMULTIRANGE`, etc.).
+///
+/// Postgres sorts the ranges, merges any that overlap or touch, and drops empty ones, so a
+/// multirange read back may not have the same ranges as the one that was written.
+///
+/// ### Note: Requires Postgres 14+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PgMultiRange<T>(pub Vec<PgRange<T>>);
+
+impl<T> Default for PgMultiRange<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T> Deref for PgMultiRange<T> {
+    type Target = Vec<PgRange<T>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for PgMultiRange<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> From<Vec<PgRange<T>>> for PgMultiRange<T> {
+    fn from(ranges: Vec<PgRange<T>>) -> Self {
+        Self(ranges)
+    }
+}
+
+impl<T, R: Into<PgRange<T>>> FromIterator<R> for PgMultiRange<T> {
+    fn from_iter<I: IntoIterator<Item = R>>(iter: I) -> Self {
+        Self(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T> IntoIterator for PgMultiRange<T> {
+    type Item = PgRange<T>;
+    type IntoIter = std::vec::IntoIter<PgRange<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+macro_rules! impl_type {
+    ($(#[$meta:meta])* $ty:ty, $type_info:ident, $array_type_info:ident) => {
+        $(#[$meta])*
+        impl Type<Postgres> for PgMultiRange<$ty> {
+            fn type_info() -> PgTypeInfo {
+                PgTypeInfo::$type_info
+            }
+
+            fn compatible(ty: &PgTypeInfo) -> bool {
+                multirange_compatible::<$ty>(ty)
+            }
+        }
+
+        $(#[$meta])*
+        impl PgHasArrayType for PgMultiRange<$ty> {
+            fn array_type_info() -> PgTypeInfo {
+                PgTypeInfo::$array_type_info
+            }
+        }
+    };
+}
+
+impl_type!(i32, INT4_MULTIRANGE, INT4_MULTIRANGE_ARRAY);
+impl_type!(i64, INT8_MULTIRANGE, INT8_MULTIRANGE_ARRAY);
+
+impl_type!(
+    #[cfg(feature = "bigdecimal")]
+    bigdecimal::BigDecimal,
+    NUM_MULTIRANGE,
+    NUM_MULTIRANGE_ARRAY
+);
+
+impl_type!(
+    #[cfg(feature = "decimal")]
+    rust_decimal::Decimal,
+    NUM_MULTIRANGE,
+    NUM_MULTIRANGE_ARRAY
+);
+
+impl_type!(
+    #[cfg(feature = "chrono")]
+    chrono::NaiveDate,
+    DATE_MULTIRANGE,
+    DATE_MULTIRANGE_ARRAY
+);
+
+impl_type!(
+    #[cfg(feature = "chrono")]
+    chrono::NaiveDateTime,
+    TS_MULTIRANGE,
+    TS_MULTIRANGE_ARRAY
+);
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> Type<Postgres> for PgMultiRange<chrono::DateTime<Tz>> {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::TSTZ_MULTIRANGE
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        multirange_compatible::<chrono::DateTime<Tz>>(ty)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> PgHasArrayType for PgMultiRange<chrono::DateTime<Tz>> {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::TSTZ_MULTIRANGE_ARRAY
+    }
+}
+
+impl_type!(
+    #[cfg(feature = "time")]
+    time::Date,
+    DATE_MULTIRANGE,
+    DATE_MULTIRANGE_ARRAY
+);
+
+impl_type!(
+    #[cfg(feature = "time")]
+    time::PrimitiveDateTime,
+    TS_MULTIRANGE,
+    TS_MULTIRANGE_ARRAY
+);
+
+impl_type!(
+    #[cfg(feature = "time")]
+    time::OffsetDateTime,
+    TSTZ_MULTIRANGE,
+    TSTZ_MULTIRANGE_ARRAY
+);
+
+impl<'q, T> Encode<'q, Postgres> for PgMultiRange<T>
+where
+    T: Encode<'q, Postgres>,
+{
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        // https://github.com/postgres/postgres/blob/REL_14_0/src/backend/utils/adt/multirangetypes.c#L360
+
+        buf.extend(&(self.0.len() as i32).to_be_bytes());
+
+        // each range is length-prefixed, in the same format as a lone range
+        for range in &self.0 {
+            buf.encode(range);
+        }
+
+        IsNull::No
+    }
+}
+
+impl<'r, T> Decode<'r, Postgres> for PgMultiRange<T>
+where
+    T: Type<Postgres> + for<'a> Decode<'a, Postgres>,
+{
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let range_ty = if let PgTypeKind::MultiRange(range) = value.type_info.0.kind() {
+            range.clone()
+        } else {
+            return Err(format!("unexpected non-multirange type {}", value.type_info).into());
+        };
+
+        match value.format {
+            PgValueFormat::Binary => {
+                let mut buf = value.as_bytes()?;
+
+                if buf.remaining() < 4 {
+                    return Err("multirange: unexpected end of data".into());
+                }
+
+                let count = buf.get_i32();
+                let mut ranges = Vec::with_capacity(count.clamp(0, 64) as usize);
+
+                for _ in 0..count {
+                    ranges.push(PgRange::decode(PgValueRef::get(
+                        &mut buf,
+                        PgValueFormat::Binary,
+                        range_ty.clone(),
+                    ))?);
+                }
+
+                Ok(PgMultiRange(ranges))
+            }
+
+            // {[1,3),[5,7)}
+            PgValueFormat::Text => split_ranges(value.as_str()?)?
+                .into_iter()
+                .map(|range| {
+                    PgRange::decode(PgValueRef {
+                        value: Some(range.as_bytes()),
+                        row: None,
+                        type_info: range_ty.clone(),
+                        format: PgValueFormat::Text,
+                    })
+                })
+                .collect::<Result<_, _>>()
+                .map(PgMultiRange),
+        }
+    }
+}
+
+/// Split the text form of a multirange into the text forms of its ranges.
+fn split_ranges(s: &str) -> Result<Vec<&str>, BoxDynError> {
+    let inner = s
+        .trim()
+        .strip_prefix('{')
+        .and_then(|s| s.strip_suffix('}'))
+        .ok_or_else(|| format!("expected a multirange, got {:?}", s))?;
+
+    let mut ranges = Vec::new();
+    let mut start = None;
+    let mut in_quotes = false;
+    let mut in_escape = false;
+
+    for (i, ch) in inner.char_indices() {
+        match ch {
+            _ if in_escape => in_escape = false,
+            '\\' => in_escape = true,
+            '"' => in_quotes = !in_quotes,
+            _ if in_quotes => {}
+
+            '[' | '(' if start.is_none() => start = Some(i),
+
+            ']' | ')' => {
+                let begin = start
+                    .take()
+                    .ok_or_else(|| format!("unbalanced range bound in {:?}", s))?;
+
+                ranges.push(&inner[begin..=i]);
+            }
+
+            ',' | ' ' if start.is_none() => {}
+            _ if start.is_some() => {}
+
+            _ => return Err(format!("unexpected {:?} in multirange {:?}", ch, s).into()),
+        }
+    }
+
+    if start.is_some() || in_quotes {
+        return Err(format!("unterminated range in {:?}", s).into());
+    }
+
+    Ok(ranges)
+}
+
+fn multirange_compatible<E: Type<Postgres>>(ty: &PgTypeInfo) -> bool {
+    // we require the declared type to be a _multirange_ of ranges whose element type
+    // is acceptable
+    if let PgTypeKind::MultiRange(range) = &ty.kind() {
+        return range_compatible::<E>(range);
+    }
+
+    false
+}
+
+#[test]
+fn test_split_ranges() {
+    assert_eq!(split_ranges("{}").unwrap(), Vec::<&str>::new());
+    assert_eq!(
+        split_ranges("{[1,3),[5,7)}").unwrap(),
+        vec!["[1,3)", "[5,7)"]
+    );
+    assert_eq!(
+        split_ranges(r#"{["2021-01-01 00:00:00+00","2021-01-02 00:00:00+00")}"#).unwrap(),
+        vec![r#"["2021-01-01 00:00:00+00","2021-01-02 00:00:00+00")"#]
+    );
+    assert_eq!(split_ranges("{(,5]}").unwrap(), vec!["(,5]"]);
+
+    assert!(split_ranges("[1,3)").is_err());
+    assert!(split_ranges("{[1,3}").is_err());
+}
@@ -513,7 +513,7 @@ where
     }
 }
 
-fn range_compatible<E: Type<Postgres>>(ty: &PgTypeInfo) -> bool {
+pub(super) fn range_compatible<E: Type<Postgres>>(ty: &PgTypeInfo) -> bool {
     // we require the declared type to be a _range_ with an
     // element type that is acceptable
     if let PgTypeKind::Range(element) = &ty.kind() {
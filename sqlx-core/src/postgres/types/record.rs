@@ -103,8 +103,15 @@ impl<'r> PgRecordDecoder<'r> {
         match self.fmt {
             PgValueFormat::Binary => {
                 let element_type_oid = Oid(self.buf.get_u32());
-                let element_type_opt = match self.typ.0.kind() {
-                    PgTypeKind::Simple if self.typ.0 == PgType::Record => {
+                // a domain over a composite is decoded as the composite itself
+                let mut typ = &self.typ;
+
+                while let PgTypeKind::Domain(base) = typ.0.kind() {
+                    typ = base;
+                }
+
+                let element_type_opt = match typ.0.kind() {
+                    PgTypeKind::Simple if typ.0 == PgType::Record => {
                         PgTypeInfo::try_from_oid(element_type_oid)
                     }
 
@@ -0,0 +1,609 @@
+use std::fmt::{self, Display, Formatter, Write};
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+use bitflags::bitflags;
+use bytes::Buf;
+
+use crate::decode::Decode;
+use crate::encode::{Encode, IsNull};
+use crate::error::{BoxDynError, Error};
+use crate::postgres::types::tsvector::{read_cstr, read_lexeme, write_lexeme};
+use crate::postgres::types::PgTsWeight;
+use crate::postgres::{
+    PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueFormat, PgValueRef, Postgres,
+};
+use crate::types::Type;
+
+// item types
+const QI_VAL: u8 = 1;
+const QI_OPR: u8 = 2;
+
+// operators
+const OP_NOT: u8 = 1;
+const OP_AND: u8 = 2;
+const OP_OR: u8 = 3;
+const OP_PHRASE: u8 = 4;
+
+/// A full-text search query (`tsquery`) in Postgres.
+///
+/// See https://www.postgresql.org/docs/current/datatype-textsearch.html#DATATYPE-TSQUERY
+///
+/// The text form is parsed the same way as a cast to `tsquery`, so lexemes are used as written;
+/// use `to_tsquery()` in SQL to normalize words instead.
+///
+/// ```rust
+/// # use sqlx_core::postgres::types::PgTsQuery;
+/// let query: PgTsQuery = "fat & (rat | cat:*)".parse().unwrap();
+///
+/// assert_eq!(
+///     query,
+///     PgTsQuery::And(
+///         Box::new(PgTsQuery::lexeme("fat")),
+///         Box::new(PgTsQuery::Or(
+///             Box::new(PgTsQuery::lexeme("rat")),
+///             Box::new(PgTsQuery::prefix("cat")),
+///         )),
+///     )
+/// );
+/// assert_eq!(query.to_string(), "'fat' & ( 'rat' | 'cat':* )");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub enum PgTsQuery {
+    /// A query without any lexemes, such as one made up entirely of stop words, which matches
+    /// nothing.
+    ///
+    /// An empty operand of an operator is dropped when the query is sent to Postgres, as
+    /// `Not(Empty)` is itself empty and `And(Empty, x)` is just `x`.
+    #[default]
+    Empty,
+
+    /// Matches documents containing a lexeme.
+    Lexeme {
+        lexeme: String,
+
+        /// Only match occurrences with one of these weights; empty to match any weight.
+        weights: PgTsWeights,
+
+        /// Match any lexeme starting with `lexeme` (`:*`).
+        prefix: bool,
+    },
+
+    /// `!`
+    Not(Box<PgTsQuery>),
+
+    /// `&`
+    And(Box<PgTsQuery>, Box<PgTsQuery>),
+
+    /// `|`
+    Or(Box<PgTsQuery>, Box<PgTsQuery>),
+
+    /// `<N>`: the right query matches `distance` positions after the left one.
+    /// `<->` is a distance of `1`.
+    Phrase {
+        left: Box<PgTsQuery>,
+        right: Box<PgTsQuery>,
+        distance: u16,
+    },
+}
+
+bitflags! {
+    /// The set of weights a lexeme in a [`PgTsQuery`] is restricted to.
+    #[derive(Default)]
+    pub struct PgTsWeights: u8 {
+        const A = 1 << 3;
+        const B = 1 << 2;
+        const C = 1 << 1;
+        const D = 1;
+    }
+}
+
+impl From<PgTsWeight> for PgTsWeights {
+    fn from(weight: PgTsWeight) -> Self {
+        match weight {
+            PgTsWeight::A => PgTsWeights::A,
+            PgTsWeight::B => PgTsWeights::B,
+            PgTsWeight::C => PgTsWeights::C,
+            PgTsWeight::D => PgTsWeights::D,
+        }
+    }
+}
+
+impl PgTsQuery {
+    /// A query matching a lexeme with any weight.
+    pub fn lexeme(lexeme: impl Into<String>) -> Self {
+        PgTsQuery::Lexeme {
+            lexeme: lexeme.into(),
+            weights: PgTsWeights::empty(),
+            prefix: false,
+        }
+    }
+
+    /// A query matching any lexeme starting with `prefix`.
+    pub fn prefix(prefix: impl Into<String>) -> Self {
+        PgTsQuery::Lexeme {
+            lexeme: prefix.into(),
+            weights: PgTsWeights::empty(),
+            prefix: true,
+        }
+    }
+
+    /// Returns `true` if there are no lexemes in this query.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            PgTsQuery::Empty => true,
+            PgTsQuery::Lexeme { .. } => false,
+            PgTsQuery::Not(query) => query.is_empty(),
+            PgTsQuery::And(left, right) | PgTsQuery::Or(left, right) => {
+                left.is_empty() && right.is_empty()
+            }
+            PgTsQuery::Phrase { left, right, .. } => left.is_empty() && right.is_empty(),
+        }
+    }
+
+    // the operator, priority (as used by Postgres to parenthesize) and operands of an
+    // operator whose operands are both non-empty
+    fn operator(&self) -> Option<(u8, u8, &PgTsQuery, &PgTsQuery)> {
+        let (op, priority, left, right) = match self {
+            PgTsQuery::And(left, right) => (OP_AND, 2, left, right),
+            PgTsQuery::Or(left, right) => (OP_OR, 1, left, right),
+            PgTsQuery::Phrase { left, right, .. } => (OP_PHRASE, 3, left, right),
+            _ => return None,
+        };
+
+        Some((op, priority, left, right))
+    }
+
+    // this query without any empty operands
+    fn reduced(&self) -> &PgTsQuery {
+        match self.operator() {
+            Some((_, _, left, right)) if left.is_empty() => right.reduced(),
+            Some((_, _, left, right)) if right.is_empty() => left.reduced(),
+            _ => self,
+        }
+    }
+
+    fn write_items(&self, buf: &mut Vec<u8>, count: &mut i32) {
+        let query = self.reduced();
+
+        *count += 1;
+
+        match query {
+            PgTsQuery::Lexeme {
+                lexeme,
+                weights,
+                prefix,
+            } => {
+                buf.push(QI_VAL);
+                buf.push(weights.bits());
+                buf.push(*prefix as u8);
+                buf.extend(lexeme.as_bytes());
+                buf.push(0);
+            }
+
+            PgTsQuery::Not(query) => {
+                buf.push(QI_OPR);
+                buf.push(OP_NOT);
+
+                query.write_items(buf, count);
+            }
+
+            _ => {
+                let (op, _, left, right) = query.operator().unwrap();
+
+                buf.push(QI_OPR);
+                buf.push(op);
+
+                if let PgTsQuery::Phrase { distance, .. } = query {
+                    buf.extend(&distance.to_be_bytes());
+                }
+
+                // the operator is followed by its right operand, and then its left
+                right.write_items(buf, count);
+                left.write_items(buf, count);
+            }
+        }
+    }
+
+    // https://github.com/postgres/postgres/blob/master/src/backend/utils/adt/tsquery.c (infix)
+    fn write_infix(
+        &self,
+        f: &mut Formatter<'_>,
+        parent_priority: u8,
+        right_of_phrase: bool,
+    ) -> fmt::Result {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let query = self.reduced();
+
+        match query {
+            PgTsQuery::Empty => Ok(()),
+
+            PgTsQuery::Lexeme {
+                lexeme,
+                weights,
+                prefix,
+            } => {
+                write_lexeme(f, lexeme)?;
+
+                if *prefix || !weights.is_empty() {
+                    f.write_char(':')?;
+                }
+
+                if *prefix {
+                    f.write_char('*')?;
+                }
+
+                for (weight, c) in [
+                    (PgTsWeights::A, 'A'),
+                    (PgTsWeights::B, 'B'),
+                    (PgTsWeights::C, 'C'),
+                    (PgTsWeights::D, 'D'),
+                ] {
+                    if weights.contains(weight) {
+                        f.write_char(c)?;
+                    }
+                }
+
+                Ok(())
+            }
+
+            PgTsQuery::Not(query) => {
+                f.write_char('!')?;
+                query.write_infix(f, 4, false)
+            }
+
+            _ => {
+                let (op, priority, left, right) = query.operator().unwrap();
+                let parenthesize =
+                    priority < parent_priority || (op == OP_PHRASE && right_of_phrase);
+
+                if parenthesize {
+                    f.write_str("( ")?;
+                }
+
+                left.write_infix(f, priority, false)?;
+
+                match query {
+                    PgTsQuery::And(..) => f.write_str(" & ")?,
+                    PgTsQuery::Or(..) => f.write_str(" | ")?,
+                    PgTsQuery::Phrase { distance: 1, .. } => f.write_str(" <-> ")?,
+                    PgTsQuery::Phrase { distance, .. } => write!(f, " <{}> ", distance)?,
+                    _ => unreachable!(),
+                }
+
+                right.write_infix(f, priority, op == OP_PHRASE)?;
+
+                if parenthesize {
+                    f.write_str(" )")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Type<Postgres> for PgTsQuery {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::TSQUERY
+    }
+}
+
+impl PgHasArrayType for PgTsQuery {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::TSQUERY_ARRAY
+    }
+}
+
+impl Encode<'_, Postgres> for PgTsQuery {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        // https://github.com/postgres/postgres/blob/master/src/backend/utils/adt/tsquery.c (tsquerysend)
+        let mut items = Vec::new();
+        let mut count = 0;
+
+        if !self.is_empty() {
+            self.write_items(&mut items, &mut count);
+        }
+
+        buf.extend(&count.to_be_bytes());
+        buf.extend(items);
+
+        IsNull::No
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for PgTsQuery {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        match value.format() {
+            PgValueFormat::Binary => decode_binary(value.as_bytes()?),
+            PgValueFormat::Text => decode_text(value.as_str()?),
+        }
+    }
+}
+
+fn decode_binary(mut buf: &[u8]) -> Result<PgTsQuery, BoxDynError> {
+    if buf.remaining() < 4 {
+        return Err("tsquery: unexpected end of data".into());
+    }
+
+    let count = buf.get_i32();
+
+    if count == 0 {
+        return Ok(PgTsQuery::Empty);
+    }
+
+    let query = read_item(&mut buf)?;
+
+    if !buf.is_empty() {
+        return Err("tsquery: unexpected trailing data".into());
+    }
+
+    Ok(query)
+}
+
+fn read_item(buf: &mut &[u8]) -> Result<PgTsQuery, BoxDynError> {
+    if buf.remaining() < 2 {
+        return Err("tsquery: unexpected end of data".into());
+    }
+
+    match (buf.get_u8(), buf.get_u8()) {
+        (QI_VAL, weights) => {
+            if buf.remaining() < 1 {
+                return Err("tsquery: unexpected end of data".into());
+            }
+
+            let prefix = buf.get_u8() != 0;
+            let lexeme = read_cstr(buf)?;
+
+            Ok(PgTsQuery::Lexeme {
+                lexeme,
+                weights: PgTsWeights::from_bits_truncate(weights),
+                prefix,
+            })
+        }
+
+        (QI_OPR, OP_NOT) => Ok(PgTsQuery::Not(Box::new(read_item(buf)?))),
+
+        (QI_OPR, op @ (OP_AND | OP_OR | OP_PHRASE)) => {
+            let distance = if op == OP_PHRASE {
+                if buf.remaining() < 2 {
+                    return Err("tsquery: unexpected end of data".into());
+                }
+
+                buf.get_u16()
+            } else {
+                0
+            };
+
+            let right = Box::new(read_item(buf)?);
+            let left = Box::new(read_item(buf)?);
+
+            Ok(match op {
+                OP_AND => PgTsQuery::And(left, right),
+                OP_OR => PgTsQuery::Or(left, right),
+                _ => PgTsQuery::Phrase {
+                    left,
+                    right,
+                    distance,
+                },
+            })
+        }
+
+        (ty, op) => Err(format!("tsquery: unknown item {}/{}", ty, op).into()),
+    }
+}
+
+impl Display for PgTsQuery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.write_infix(f, 0, false)
+    }
+}
+
+impl FromStr for PgTsQuery {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        decode_text(s).map_err(Error::Decode)
+    }
+}
+
+// 'fat' & ( 'rat':AB | !'cat':* ) <-> 'mat'
+fn decode_text(s: &str) -> Result<PgTsQuery, BoxDynError> {
+    let mut parser = Parser {
+        chars: s.chars().peekable(),
+    };
+
+    if parser.peek().is_none() {
+        return Ok(PgTsQuery::Empty);
+    }
+
+    let query = parser.parse_or()?;
+
+    match parser.peek() {
+        None => Ok(query),
+        Some(c) => Err(format!("tsquery: unexpected character {:?} in {:?}", c, s).into()),
+    }
+}
+
+// a recursive descent parser; from loosest to tightest binding, the operators are
+// `|`, `&`, `<N>` and `!`, and the binary operators are all left-associative
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    // the next character that isn't whitespace
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        self.chars.peek().copied()
+    }
+
+    fn parse_or(&mut self) -> Result<PgTsQuery, BoxDynError> {
+        let mut query = self.parse_and()?;
+
+        while self.peek() == Some('|') {
+            self.chars.next();
+            query = PgTsQuery::Or(Box::new(query), Box::new(self.parse_and()?));
+        }
+
+        Ok(query)
+    }
+
+    fn parse_and(&mut self) -> Result<PgTsQuery, BoxDynError> {
+        let mut query = self.parse_phrase()?;
+
+        while self.peek() == Some('&') {
+            self.chars.next();
+            query = PgTsQuery::And(Box::new(query), Box::new(self.parse_phrase()?));
+        }
+
+        Ok(query)
+    }
+
+    fn parse_phrase(&mut self) -> Result<PgTsQuery, BoxDynError> {
+        let mut query = self.parse_not()?;
+
+        while self.peek() == Some('<') {
+            self.chars.next();
+
+            let distance = if self.chars.next_if_eq(&'-').is_some() {
+                1
+            } else {
+                let mut digits = String::new();
+
+                while let Some(c) = self.chars.next_if(char::is_ascii_digit) {
+                    digits.push(c);
+                }
+
+                digits
+                    .parse()
+                    .map_err(|_| "tsquery: invalid distance in phrase operator")?
+            };
+
+            if self.chars.next() != Some('>') {
+                return Err("tsquery: expected `>` to end phrase operator".into());
+            }
+
+            query = PgTsQuery::Phrase {
+                left: Box::new(query),
+                right: Box::new(self.parse_not()?),
+                distance,
+            };
+        }
+
+        Ok(query)
+    }
+
+    fn parse_not(&mut self) -> Result<PgTsQuery, BoxDynError> {
+        match self.peek() {
+            Some('!') => {
+                self.chars.next();
+
+                Ok(PgTsQuery::Not(Box::new(self.parse_not()?)))
+            }
+
+            Some('(') => {
+                self.chars.next();
+
+                let query = self.parse_or()?;
+
+                if self.peek() != Some(')') {
+                    return Err("tsquery: expected `)`".into());
+                }
+
+                self.chars.next();
+
+                Ok(query)
+            }
+
+            Some(_) => self.parse_lexeme(),
+
+            None => Err("tsquery: unexpected end of input".into()),
+        }
+    }
+
+    fn parse_lexeme(&mut self) -> Result<PgTsQuery, BoxDynError> {
+        let lexeme = read_lexeme(&mut self.chars, &['!', '&', '|', '(', ')', '<', ':'])
+            .map_err(|e| format!("tsquery: {}", e))?;
+
+        let mut weights = PgTsWeights::empty();
+        let mut prefix = false;
+
+        if self.chars.next_if_eq(&':').is_some() {
+            while let Some(c) = self.chars.peek().copied() {
+                if c == '*' {
+                    prefix = true;
+                } else if let Some(weight) = PgTsWeight::from_char(c) {
+                    weights |= weight.into();
+                } else {
+                    break;
+                }
+
+                self.chars.next();
+            }
+        }
+
+        Ok(PgTsQuery::Lexeme {
+            lexeme,
+            weights,
+            prefix,
+        })
+    }
+}
+
+#[test]
+fn test_tsquery_binary() {
+    // !'a':*B <3> 'b'
+    let buf = b"\x00\x00\x00\x04\x02\x04\x00\x03\x01\x00\x00b\x00\x02\x01\x01\x04\x01a\x00";
+    let query = decode_binary(buf).unwrap();
+
+    assert_eq!(
+        query,
+        PgTsQuery::Phrase {
+            left: Box::new(PgTsQuery::Not(Box::new(PgTsQuery::Lexeme {
+                lexeme: "a".into(),
+                weights: PgTsWeights::B,
+                prefix: true,
+            }))),
+            right: Box::new(PgTsQuery::lexeme("b")),
+            distance: 3,
+        }
+    );
+
+    let mut encoded = PgArgumentBuffer::default();
+    let _ = query.encode_by_ref(&mut encoded);
+
+    assert_eq!(&**encoded, &buf[..]);
+
+    assert!(decode_binary(&buf[..12]).is_err());
+    assert_eq!(decode_binary(b"\0\0\0\0").unwrap(), PgTsQuery::Empty);
+}
+
+#[test]
+fn test_tsquery_text() {
+    let query: PgTsQuery = r"!(a & b) | 'it''s':*ab <-> (c <2> d) & e\&f"
+        .parse()
+        .unwrap();
+
+    assert_eq!(
+        query.to_string(),
+        r"!( 'a' & 'b' ) | 'it''s':*AB <-> ( 'c' <2> 'd' ) & 'e&f'"
+    );
+
+    assert_eq!(query.to_string().parse::<PgTsQuery>().unwrap(), query);
+
+    assert_eq!(
+        PgTsQuery::And(Box::new(PgTsQuery::Empty), Box::new(PgTsQuery::lexeme("a"))).to_string(),
+        "'a'"
+    );
+
+    assert_eq!("  ".parse::<PgTsQuery>().unwrap(), PgTsQuery::Empty);
+    assert!("a &".parse::<PgTsQuery>().is_err());
+    assert!("(a | b".parse::<PgTsQuery>().is_err());
+    assert!("a b".parse::<PgTsQuery>().is_err());
+    assert!("a <x> b".parse::<PgTsQuery>().is_err());
+}
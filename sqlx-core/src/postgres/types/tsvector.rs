@@ -0,0 +1,446 @@
+use std::cmp;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter, Write};
+use std::iter::Peekable;
+use std::ops::{Deref, DerefMut};
+use std::str::{Chars, FromStr};
+
+use bytes::Buf;
+
+use crate::decode::Decode;
+use crate::encode::{Encode, IsNull};
+use crate::error::{BoxDynError, Error};
+use crate::postgres::{
+    PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueFormat, PgValueRef, Postgres,
+};
+use crate::types::Type;
+
+// the largest position Postgres can store; anything larger is clamped to it
+const MAX_POSITION: u16 = (1 << 14) - 1;
+
+/// A document prepared for full-text search (`tsvector`) in Postgres.
+///
+/// See https://www.postgresql.org/docs/current/datatype-textsearch.html#DATATYPE-TSVECTOR
+///
+/// Maps each distinct lexeme to the positions it occurs at, which may be empty. Lexemes are kept
+/// in the same order Postgres sorts them in.
+///
+/// ```rust
+/// # use sqlx_core::postgres::types::{PgTsPosition, PgTsVector, PgTsWeight};
+/// let vector: PgTsVector = "'fat':2 'cat':3A,1".parse().unwrap();
+///
+/// assert_eq!(
+///     vector["cat"],
+///     [PgTsPosition::new(1), PgTsPosition::new(3).with_weight(PgTsWeight::A)]
+/// );
+/// assert_eq!(vector.to_string(), "'cat':1,3A 'fat':2");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PgTsVector(pub BTreeMap<String, Vec<PgTsPosition>>);
+
+/// Where a lexeme occurs in a [`PgTsVector`], and how important that occurrence is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PgTsPosition {
+    /// The position, from `1` to `16383`.
+    pub position: u16,
+
+    pub weight: PgTsWeight,
+}
+
+/// The weight of a lexeme, used to rank matches; `A` is the most important.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum PgTsWeight {
+    A,
+    B,
+    C,
+
+    /// The default weight, which is not shown in the text form.
+    #[default]
+    D,
+}
+
+impl PgTsPosition {
+    /// A position with the default weight.
+    pub fn new(position: u16) -> Self {
+        Self {
+            position,
+            weight: PgTsWeight::D,
+        }
+    }
+
+    pub fn with_weight(mut self, weight: PgTsWeight) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    // the packed `WordEntryPos`
+    fn to_bits(self) -> u16 {
+        (self.weight.to_bits() << 14) | cmp::min(self.position, MAX_POSITION)
+    }
+
+    fn from_bits(bits: u16) -> Self {
+        Self {
+            position: bits & MAX_POSITION,
+            weight: PgTsWeight::from_bits(bits >> 14),
+        }
+    }
+}
+
+impl PgTsWeight {
+    fn to_bits(self) -> u16 {
+        match self {
+            PgTsWeight::A => 3,
+            PgTsWeight::B => 2,
+            PgTsWeight::C => 1,
+            PgTsWeight::D => 0,
+        }
+    }
+
+    fn from_bits(bits: u16) -> Self {
+        match bits & 3 {
+            3 => PgTsWeight::A,
+            2 => PgTsWeight::B,
+            1 => PgTsWeight::C,
+            _ => PgTsWeight::D,
+        }
+    }
+
+    pub(super) fn from_char(c: char) -> Option<Self> {
+        match c.to_ascii_uppercase() {
+            'A' => Some(PgTsWeight::A),
+            'B' => Some(PgTsWeight::B),
+            'C' => Some(PgTsWeight::C),
+            'D' => Some(PgTsWeight::D),
+            _ => None,
+        }
+    }
+}
+
+impl Deref for PgTsVector {
+    type Target = BTreeMap<String, Vec<PgTsPosition>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for PgTsVector {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<BTreeMap<String, Vec<PgTsPosition>>> for PgTsVector {
+    fn from(map: BTreeMap<String, Vec<PgTsPosition>>) -> Self {
+        Self(map)
+    }
+}
+
+impl From<PgTsVector> for BTreeMap<String, Vec<PgTsPosition>> {
+    fn from(vector: PgTsVector) -> Self {
+        vector.0
+    }
+}
+
+impl<K: Into<String>> FromIterator<(K, Vec<PgTsPosition>)> for PgTsVector {
+    fn from_iter<I: IntoIterator<Item = (K, Vec<PgTsPosition>)>>(iter: I) -> Self {
+        let mut map = BTreeMap::<String, Vec<PgTsPosition>>::new();
+
+        for (lexeme, positions) in iter {
+            map.entry(lexeme.into()).or_default().extend(positions);
+        }
+
+        Self(map)
+    }
+}
+
+impl IntoIterator for PgTsVector {
+    type Item = (String, Vec<PgTsPosition>);
+    type IntoIter = std::collections::btree_map::IntoIter<String, Vec<PgTsPosition>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl Type<Postgres> for PgTsVector {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::TSVECTOR
+    }
+}
+
+impl PgHasArrayType for PgTsVector {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::TSVECTOR_ARRAY
+    }
+}
+
+impl Encode<'_, Postgres> for PgTsVector {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        // https://github.com/postgres/postgres/blob/master/src/backend/utils/adt/tsvector.c (tsvectorsend)
+        buf.extend(&(self.0.len() as i32).to_be_bytes());
+
+        for (lexeme, positions) in &self.0 {
+            buf.extend(lexeme.as_bytes());
+            buf.push(0);
+
+            // the server rejects positions that are out of order
+            let positions = normalize_positions(positions.clone());
+
+            buf.extend(&(positions.len() as u16).to_be_bytes());
+
+            for position in positions {
+                buf.extend(&position.to_bits().to_be_bytes());
+            }
+        }
+
+        IsNull::No
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for PgTsVector {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        match value.format() {
+            PgValueFormat::Binary => decode_binary(value.as_bytes()?),
+            PgValueFormat::Text => decode_text(value.as_str()?),
+        }
+    }
+}
+
+fn decode_binary(mut buf: &[u8]) -> Result<PgTsVector, BoxDynError> {
+    if buf.remaining() < 4 {
+        return Err("tsvector: unexpected end of data".into());
+    }
+
+    let count = buf.get_i32();
+    let mut map = BTreeMap::new();
+
+    for _ in 0..count {
+        let lexeme = read_cstr(&mut buf)?;
+
+        if buf.remaining() < 2 {
+            return Err("tsvector: unexpected end of data".into());
+        }
+
+        let npos = buf.get_u16() as usize;
+
+        if buf.remaining() < npos * 2 {
+            return Err("tsvector: unexpected end of data".into());
+        }
+
+        let positions = (0..npos)
+            .map(|_| PgTsPosition::from_bits(buf.get_u16()))
+            .collect();
+
+        map.insert(lexeme, positions);
+    }
+
+    Ok(PgTsVector(map))
+}
+
+/// Read a NUL-terminated string, as sent by `tsvectorsend` and `tsquerysend`.
+pub(super) fn read_cstr(buf: &mut &[u8]) -> Result<String, BoxDynError> {
+    let end = memchr::memchr(0, buf).ok_or("unexpected end of data")?;
+    let s = std::str::from_utf8(&buf[..end])?.to_owned();
+
+    buf.advance(end + 1);
+
+    Ok(s)
+}
+
+/// Write a lexeme in the quoted form Postgres outputs.
+pub(super) fn write_lexeme(f: &mut Formatter<'_>, lexeme: &str) -> fmt::Result {
+    f.write_char('\'')?;
+
+    for c in lexeme.chars() {
+        if c == '\'' || c == '\\' {
+            f.write_char(c)?;
+        }
+
+        f.write_char(c)?;
+    }
+
+    f.write_char('\'')
+}
+
+/// Read a lexeme, which may be quoted, up to any character in `delimiters` or whitespace.
+pub(super) fn read_lexeme(
+    chars: &mut Peekable<Chars<'_>>,
+    delimiters: &[char],
+) -> Result<String, BoxDynError> {
+    let mut lexeme = String::new();
+
+    if chars.next_if_eq(&'\'').is_some() {
+        loop {
+            match chars.next() {
+                Some('\'') if chars.next_if_eq(&'\'').is_some() => lexeme.push('\''),
+                Some('\'') => break,
+                Some('\\') => lexeme.push(chars.next().ok_or("unterminated lexeme")?),
+                Some(c) => lexeme.push(c),
+                None => return Err("unterminated lexeme".into()),
+            }
+        }
+    } else {
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !delimiters.contains(c)) {
+            if c == '\\' {
+                lexeme.push(chars.next().ok_or("unterminated lexeme")?);
+            } else {
+                lexeme.push(c);
+            }
+        }
+    }
+
+    if lexeme.is_empty() {
+        return Err("expected a lexeme".into());
+    }
+
+    Ok(lexeme)
+}
+
+// sort and deduplicate positions as Postgres does, keeping the highest weight for each
+fn normalize_positions(mut positions: Vec<PgTsPosition>) -> Vec<PgTsPosition> {
+    positions.sort_by_key(|p| (cmp::min(p.position, MAX_POSITION), p.weight.to_bits()));
+    positions.reverse();
+    positions.dedup_by_key(|p| cmp::min(p.position, MAX_POSITION));
+    positions.reverse();
+    positions
+}
+
+impl Display for PgTsVector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, (lexeme, positions)) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_char(' ')?;
+            }
+
+            write_lexeme(f, lexeme)?;
+
+            for (j, position) in positions.iter().enumerate() {
+                f.write_char(if j == 0 { ':' } else { ',' })?;
+
+                write!(f, "{}", position.position)?;
+
+                if position.weight != PgTsWeight::D {
+                    write!(f, "{:?}", position.weight)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for PgTsVector {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        decode_text(s).map_err(Error::Decode)
+    }
+}
+
+// 'a':1A,2 b
+fn decode_text(s: &str) -> Result<PgTsVector, BoxDynError> {
+    let mut chars = s.chars().peekable();
+    let mut map = BTreeMap::<String, Vec<PgTsPosition>>::new();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let lexeme = read_lexeme(&mut chars, &[':'])?;
+        let positions = map.entry(lexeme).or_default();
+
+        if chars.next_if_eq(&':').is_none() {
+            continue;
+        }
+
+        loop {
+            let mut digits = String::new();
+
+            while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                digits.push(c);
+            }
+
+            let position = digits
+                .parse::<u32>()
+                .map_err(|_| format!("tsvector: invalid position in {:?}", s))?;
+
+            if position == 0 {
+                return Err(format!("tsvector: invalid position in {:?}", s).into());
+            }
+
+            let weight = chars.peek().and_then(|&c| PgTsWeight::from_char(c)).map_or(
+                PgTsWeight::D,
+                |weight| {
+                    chars.next();
+                    weight
+                },
+            );
+
+            positions.push(PgTsPosition {
+                position: cmp::min(position, MAX_POSITION.into()) as u16,
+                weight,
+            });
+
+            if chars.next_if_eq(&',').is_none() {
+                break;
+            }
+        }
+
+        match chars.peek() {
+            Some(c) if !c.is_whitespace() => {
+                return Err(format!("tsvector: unexpected character {:?}", c).into());
+            }
+
+            _ => {}
+        }
+    }
+
+    for positions in map.values_mut() {
+        *positions = normalize_positions(std::mem::take(positions));
+    }
+
+    Ok(PgTsVector(map))
+}
+
+#[test]
+fn test_decode_tsvector_binary() {
+    let buf = b"\x00\x00\x00\x02ab\x00\x00\x02\xc0\x01\x00\x03c\x00\x00\x00";
+    let vector = decode_binary(buf).unwrap();
+
+    assert_eq!(
+        vector,
+        PgTsVector::from_iter([
+            (
+                "ab",
+                vec![
+                    PgTsPosition::new(1).with_weight(PgTsWeight::A),
+                    PgTsPosition::new(3)
+                ]
+            ),
+            ("c", vec![]),
+        ])
+    );
+
+    assert!(decode_binary(&buf[..8]).is_err());
+}
+
+#[test]
+fn test_tsvector_text() {
+    let vector: PgTsVector = r"a:3,1A b\ c 'it''s':2C,2 a:1".parse().unwrap();
+
+    assert_eq!(vector["a"].len(), 2);
+    assert_eq!(
+        vector["a"][0],
+        PgTsPosition::new(1).with_weight(PgTsWeight::A)
+    );
+    assert_eq!(vector.to_string(), r"'a':1A,3 'b c' 'it''s':2C");
+
+    assert!("".parse::<PgTsVector>().unwrap().is_empty());
+    assert!("a:".parse::<PgTsVector>().is_err());
+    assert!("a:1X".parse::<PgTsVector>().is_err());
+    assert!("'a".parse::<PgTsVector>().is_err());
+}
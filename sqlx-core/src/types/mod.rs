@@ -164,6 +164,15 @@ pub use json::{Json, JsonRawValue, JsonValue};
 /// }
 /// ```
 ///
+/// ### Arrays (PostgreSQL)
+///
+/// For PostgreSQL, `PgHasArrayType` is also implemented for transparent types, enumerations
+/// with a `type_name`, domains and records, so `Vec<T>` and `&[T]` can be bound and decoded. The
+/// array type is resolved at runtime as `<type_name>[]`.
+///
+/// * `#[sqlx(no_pg_array)]` on the type definition: don't generate the `PgHasArrayType` impl,
+///   e.g. to write one by hand.
+///
 pub trait Type<DB: Database> {
     /// Returns the canonical SQL type for this Rust type.
     ///
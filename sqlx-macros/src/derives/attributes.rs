@@ -62,6 +62,7 @@ pub enum RenameAll {
 
 pub struct SqlxContainerAttributes {
     pub transparent: bool,
+    pub no_pg_array: bool,
     pub type_name: Option<TypeName>,
     pub rename_all: Option<RenameAll>,
     pub repr: Option<Ident>,
@@ -75,6 +76,7 @@ pub struct SqlxChildAttributes {
 
 pub fn parse_container_attributes(input: &[Attribute]) -> syn::Result<SqlxContainerAttributes> {
     let mut transparent = None;
+    let mut no_pg_array = None;
     let mut repr = None;
     let mut type_name = None;
     let mut rename_all = None;
@@ -95,6 +97,10 @@ pub fn parse_container_attributes(input: &[Attribute]) -> syn::Result<SqlxContai
                                 try_set!(transparent, true, value)
                             }
 
+                            Meta::Path(p) if p.is_ident("no_pg_array") => {
+                                try_set!(no_pg_array, true, value)
+                            }
+
                             Meta::NameValue(MetaNameValue {
                                 path,
                                 lit: Lit::Str(val),
@@ -169,6 +175,7 @@ pub fn parse_container_attributes(input: &[Attribute]) -> syn::Result<SqlxContai
 
     Ok(SqlxContainerAttributes {
         transparent: transparent.unwrap_or(false),
+        no_pg_array: no_pg_array.unwrap_or(false),
         repr,
         type_name,
         rename_all,
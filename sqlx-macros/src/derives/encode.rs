@@ -224,13 +224,17 @@ fn expand_derive_encode_struct(
 
         let (impl_generics, _, where_clause) = generics.split_for_impl();
 
-        let writes = fields.iter().map(|field| -> Stmt {
-            let id = &field.ident;
-
-            parse_quote!(
-                encoder.encode(&self. #id);
-            )
-        });
+        // `PgRecordEncoder` and `PgCopyRowEncoder` are both driven field by field
+        let writes: Vec<Stmt> = fields
+            .iter()
+            .map(|field| {
+                let id = &field.ident;
+
+                parse_quote!(
+                    encoder.encode(&self. #id);
+                )
+            })
+            .collect();
 
         let sizes = fields.iter().map(|field| -> Expr {
             let id = &field.ident;
@@ -264,6 +268,15 @@ fn expand_derive_encode_struct(
                         + #(#sizes)+* // sum of the size hints for each column
                 }
             }
+
+            #[automatically_derived]
+            impl #impl_generics ::sqlx::postgres::PgCopyRow for #ident #ty_generics
+            #where_clause
+            {
+                fn encode_row(&self, encoder: &mut ::sqlx::postgres::PgCopyRowEncoder<'_>) {
+                    #(#writes)*
+                }
+            }
         ));
     }
 
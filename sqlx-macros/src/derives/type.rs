@@ -77,7 +77,7 @@ fn expand_derive_has_sql_type_transparent(
             .push(parse_quote!(#ty: ::sqlx::postgres::PgHasArrayType));
         let (array_impl_generics, _, array_where_clause) = array_generics.split_for_impl();
 
-        return Ok(quote!(
+        let mut tts = quote!(
             #[automatically_derived]
             impl #impl_generics ::sqlx::Type< DB > for #ident #ty_generics #where_clause {
                 fn type_info() -> DB::TypeInfo {
@@ -88,17 +88,25 @@ fn expand_derive_has_sql_type_transparent(
                     <#ty as ::sqlx::Type<DB>>::compatible(ty)
                 }
             }
-            #[automatically_derived]
-            #[cfg(feature = "postgres")]
-            impl #array_impl_generics ::sqlx::postgres::PgHasArrayType for #ident #ty_generics
-            #array_where_clause {
-                fn array_type_info() -> ::sqlx::postgres::PgTypeInfo {
-                    <#ty as ::sqlx::postgres::PgHasArrayType>::array_type_info()
+        );
+
+        if !attr.no_pg_array {
+            tts.extend(quote!(
+                #[automatically_derived]
+                #[cfg(feature = "postgres")]
+                impl #array_impl_generics ::sqlx::postgres::PgHasArrayType for #ident #ty_generics
+                #array_where_clause {
+                    fn array_type_info() -> ::sqlx::postgres::PgTypeInfo {
+                        <#ty as ::sqlx::postgres::PgHasArrayType>::array_type_info()
+                    }
                 }
-            }
-        ));
+            ));
+        }
+
+        return Ok(tts);
     }
 
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
     let mut tts = TokenStream::new();
 
     if cfg!(feature = "postgres") {
@@ -106,12 +114,17 @@ fn expand_derive_has_sql_type_transparent(
 
         tts.extend(quote!(
             #[automatically_derived]
-            impl ::sqlx::Type<::sqlx::postgres::Postgres> for #ident #ty_generics {
+            impl #impl_generics ::sqlx::Type<::sqlx::postgres::Postgres> for #ident #ty_generics
+            #where_clause {
                 fn type_info() -> ::sqlx::postgres::PgTypeInfo {
                     ::sqlx::postgres::PgTypeInfo::with_name(#ty_name)
                 }
             }
         ));
+
+        if !attr.no_pg_array {
+            tts.extend(expand_pg_array_type(input, attr.type_name.as_ref()));
+        }
     }
 
     Ok(tts)
@@ -178,6 +191,10 @@ fn expand_derive_has_sql_type_strong_enum(
                 }
             }
         ));
+
+        if !attributes.no_pg_array {
+            tts.extend(expand_pg_array_type(input, attributes.type_name.as_ref()));
+        }
     }
 
     if cfg!(feature = "sqlite") {
@@ -205,6 +222,7 @@ fn expand_derive_has_sql_type_struct(
     let attributes = check_struct_attributes(input, fields)?;
 
     let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
     let mut tts = TokenStream::new();
 
     if cfg!(feature = "postgres") {
@@ -212,17 +230,42 @@ fn expand_derive_has_sql_type_struct(
 
         tts.extend(quote!(
             #[automatically_derived]
-            impl ::sqlx::Type<::sqlx::Postgres> for #ident {
+            impl #impl_generics ::sqlx::Type<::sqlx::Postgres> for #ident #ty_generics #where_clause {
                 fn type_info() -> ::sqlx::postgres::PgTypeInfo {
                     ::sqlx::postgres::PgTypeInfo::with_name(#ty_name)
                 }
             }
         ));
+
+        if !attributes.no_pg_array {
+            tts.extend(expand_pg_array_type(input, attributes.type_name.as_ref()));
+        }
     }
 
     Ok(tts)
 }
 
+/// Postgres names the array of a type `_name` (most of the time), but also accepts `name[]`,
+/// which we can resolve at runtime like any other type name.
+fn expand_pg_array_type(input: &DeriveInput, explicit_name: Option<&TypeName>) -> TokenStream {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let array_name = format!(
+        "{}[]",
+        explicit_name.map_or_else(|| ident.to_string(), |tn| tn.val.clone())
+    );
+
+    quote!(
+        #[automatically_derived]
+        impl #impl_generics ::sqlx::postgres::PgHasArrayType for #ident #ty_generics #where_clause {
+            fn array_type_info() -> ::sqlx::postgres::PgTypeInfo {
+                ::sqlx::postgres::PgTypeInfo::with_name(#array_name)
+            }
+        }
+    )
+}
+
 fn type_name(ident: &Ident, explicit_name: Option<&TypeName>) -> TokenStream {
     explicit_name.map(|tn| tn.get()).unwrap_or_else(|| {
         let s = ident.to_string();
@@ -36,9 +36,9 @@ pub use native_tls;
     not(feature = "_rt-async-std"),
 ))]
 pub use tokio::{
-    self, fs, io::AsyncRead, io::AsyncReadExt, io::AsyncWrite, io::AsyncWriteExt, io::ReadBuf,
-    net::TcpStream, runtime::Handle, sync::Mutex as AsyncMutex, task::spawn, task::yield_now,
-    time::sleep, time::timeout,
+    self, fs, io::AsyncRead, io::AsyncReadExt, io::AsyncSeek, io::AsyncWrite, io::AsyncWriteExt,
+    io::ReadBuf, net::TcpStream, runtime::Handle, sync::Mutex as AsyncMutex, task::spawn,
+    task::yield_now, time::sleep, time::timeout,
 };
 
 #[cfg(all(
@@ -142,8 +142,9 @@ macro_rules! blocking {
 ))]
 pub use async_std::{
     self, fs, future::timeout, io::prelude::ReadExt as AsyncReadExt,
-    io::prelude::WriteExt as AsyncWriteExt, io::Read as AsyncRead, io::Write as AsyncWrite,
-    net::TcpStream, sync::Mutex as AsyncMutex, task::sleep, task::spawn, task::yield_now,
+    io::prelude::WriteExt as AsyncWriteExt, io::Read as AsyncRead, io::Seek as AsyncSeek,
+    io::Write as AsyncWrite, net::TcpStream, sync::Mutex as AsyncMutex, task::sleep, task::spawn,
+    task::yield_now,
 };
 
 #[cfg(all(
@@ -206,9 +206,14 @@
 ///
 /// In Postgres, we patch up this inference by analyzing `EXPLAIN VERBOSE` output (which is not
 /// well documented, is highly dependent on the query plan that Postgres generates, and may differ
-/// between releases) to find columns that are the result of left/right/full outer joins. This
-/// analysis errs on the side of producing false positives (marking columns nullable that are not
-/// in practice) but there are likely edge cases that it does not cover yet.
+/// between releases) to find columns that are the result of left/right/full outer joins, at any
+/// depth of the plan. This analysis errs on the side of producing false positives (marking columns
+/// nullable that are not in practice) but there are likely edge cases that it does not cover yet.
+///
+/// The same analysis also recognizes a few expressions that can never be `NULL`: `count(...)`,
+/// window functions like `row_number()`, and `COALESCE(...)` with a constant or a `NOT NULL`
+/// column from outside of any outer join as one of its arguments. On Postgres 11 and older, this
+/// is only done for queries without bind parameters.
 ///
 /// Using `?` as an override we can fix this for columns we know to be nullable in practice:
 ///
@@ -210,3 +210,310 @@ async fn test_pool_callbacks() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[sqlx_macros::test]
+async fn pool_should_recycle_connections_within_min_connections() -> anyhow::Result<()> {
+    let pool = AnyPoolOptions::new()
+        .max_connections(2)
+        .min_connections(2)
+        .connect(&dotenv::var("DATABASE_URL")?)
+        .await?;
+
+    // Give `min_connections` maintenance time to open both connections.
+    sqlx_rt::sleep(Duration::from_millis(200)).await;
+    assert_eq!(pool.num_idle(), 2);
+
+    pool.recycle_connections();
+    sqlx_rt::sleep(Duration::from_millis(200)).await;
+
+    // Both connections are closed, then replaced to satisfy `min_connections`.
+    let metrics = pool.metrics();
+    assert_eq!(
+        metrics.connections_closed_by(sqlx::pool::CloseReason::Recycled),
+        2
+    );
+    assert_eq!(pool.size(), 2);
+
+    pool.close().await;
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn pool_should_report_metrics() -> anyhow::Result<()> {
+    #[derive(Default)]
+    struct Observer {
+        opened: AtomicUsize,
+        released: AtomicUsize,
+    }
+
+    impl sqlx::pool::PoolMetricsObserver for Observer {
+        fn connection_opened(&self) {
+            self.opened.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn connection_released(&self, _meta: sqlx::pool::PoolConnectionMetadata) {
+            self.released.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let observer = Arc::new(Observer::default());
+
+    let pool = AnyPoolOptions::new()
+        .max_connections(1)
+        .acquire_timeout(Duration::from_millis(500))
+        .metrics_observer(observer.clone())
+        .connect(&dotenv::var("DATABASE_URL")?)
+        .await?;
+
+    let conn = pool.acquire().await?;
+
+    // The only connection is checked out so this must time out.
+    assert!(matches!(
+        pool.acquire().await,
+        Err(sqlx::Error::PoolTimedOut)
+    ));
+
+    // Giving up before `acquire_timeout` elapses is counted as cancelled.
+    assert!(sqlx_rt::timeout(Duration::from_millis(50), pool.acquire())
+        .await
+        .is_err());
+
+    drop(conn);
+
+    // Give the connection time to be returned to the pool.
+    sqlx_rt::sleep(Duration::from_millis(100)).await;
+
+    let metrics = pool.metrics();
+
+    // `connect()` acquires a connection to test the configuration.
+    assert_eq!(metrics.acquire_started, 4);
+    assert_eq!(metrics.acquire_finished, 2);
+    assert_eq!(metrics.acquire_timed_out, 1);
+    assert_eq!(metrics.acquire_cancelled, 1);
+    assert_eq!(metrics.acquire_waiting(), 0);
+    assert_eq!(metrics.acquire_wait.count, 2);
+    assert_eq!(metrics.connections_opened, 1);
+    assert_eq!(metrics.connections_closed(), 0);
+
+    assert_eq!(observer.opened.load(Ordering::SeqCst), 1);
+    assert_eq!(observer.released.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        metrics.connections_released,
+        observer.released.load(Ordering::SeqCst) as u64
+    );
+
+    pool.close().await;
+
+    let metrics = pool.metrics();
+
+    assert_eq!(
+        metrics.connections_closed_by(sqlx::pool::CloseReason::PoolClosed),
+        1
+    );
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn routed_pool_should_send_reads_to_replica() -> anyhow::Result<()> {
+    use sqlx::pool::RoutedPool;
+
+    let url = dotenv::var("DATABASE_URL")?;
+
+    let primary = AnyPoolOptions::new().connect(&url).await?;
+    let replica = AnyPoolOptions::new().connect(&url).await?;
+
+    let routed = RoutedPool::new(primary.clone(), vec![replica.clone()]);
+
+    let primary_acquires = primary.metrics().acquire_started;
+    let replica_acquires = replica.metrics().acquire_started;
+
+    let _: (i32,) = sqlx::query_as("SELECT 1").fetch_one(&routed).await?;
+
+    assert_eq!(primary.metrics().acquire_started, primary_acquires);
+    assert_eq!(replica.metrics().acquire_started, replica_acquires + 1);
+
+    // Transactions always go to the primary.
+    let tx = routed.begin().await?;
+    tx.rollback().await?;
+
+    assert_eq!(primary.metrics().acquire_started, primary_acquires + 1);
+    assert_eq!(replica.metrics().acquire_started, replica_acquires + 1);
+
+    routed.close().await;
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn pool_should_resize_at_runtime() -> anyhow::Result<()> {
+    let pool = AnyPoolOptions::new()
+        .max_connections(2)
+        .acquire_timeout(Duration::from_millis(500))
+        .connect(&dotenv::var("DATABASE_URL")?)
+        .await?;
+
+    let conn1 = pool.acquire().await?;
+    let conn2 = pool.acquire().await?;
+    assert_eq!(pool.size(), 2);
+
+    pool.set_max_connections(1);
+
+    drop(conn1);
+    drop(conn2);
+
+    // Give the connections time to be returned to the pool.
+    sqlx_rt::sleep(Duration::from_millis(100)).await;
+
+    // One of the connections should have been closed instead of being returned.
+    assert_eq!(pool.size(), 1);
+
+    let conn1 = pool.acquire().await?;
+    assert!(matches!(
+        pool.acquire().await,
+        Err(sqlx::Error::PoolTimedOut)
+    ));
+
+    pool.set_max_connections(3);
+
+    let conn2 = pool.acquire().await?;
+    let conn3 = pool.acquire().await?;
+    assert_eq!(pool.size(), 3);
+
+    drop((conn1, conn2, conn3));
+
+    // Connections should now be reaped almost immediately.
+    pool.set_idle_timeout(Duration::from_millis(1));
+    pool.set_min_connections(1);
+
+    sqlx_rt::sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(pool.size(), 1);
+
+    pool.close().await;
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn pool_should_close_while_retiring_permits() -> anyhow::Result<()> {
+    let pool = AnyPoolOptions::new()
+        .max_connections(2)
+        .connect(&dotenv::var("DATABASE_URL")?)
+        .await?;
+
+    let conn1 = pool.acquire().await?;
+    let conn2 = pool.acquire().await?;
+
+    // Both permits are checked out, so retiring one has to wait.
+    pool.set_max_connections(1);
+    sqlx_rt::sleep(Duration::from_millis(100)).await;
+
+    let close = sqlx_rt::spawn({
+        let pool = pool.clone();
+        async move { pool.close().await }
+    });
+
+    sqlx_rt::sleep(Duration::from_millis(100)).await;
+    drop((conn1, conn2));
+
+    assert!(sqlx_rt::timeout(Duration::from_secs(5), close)
+        .await
+        .is_ok());
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn pool_should_use_options_provider() -> anyhow::Result<()> {
+    let url = dotenv::var("DATABASE_URL")?;
+    let provided = Arc::new(AtomicUsize::new(0));
+
+    let pool = AnyPoolOptions::new()
+        .max_connections(1)
+        .connect_with_provider({
+            let provided = provided.clone();
+            move || {
+                let provided = provided.clone();
+                let url = url.clone();
+
+                Box::pin(async move {
+                    provided.fetch_add(1, Ordering::SeqCst);
+                    url.parse::<AnyConnectOptions>()
+                })
+            }
+        })
+        .await?;
+
+    // The options used to create the pool also open the first connection.
+    assert_eq!(provided.load(Ordering::SeqCst), 1);
+
+    let _ = pool.acquire().await?;
+    sqlx_rt::sleep(Duration::from_millis(100)).await;
+
+    // The idle connection is reused.
+    assert_eq!(provided.load(Ordering::SeqCst), 1);
+
+    pool.recycle_connections();
+    sqlx_rt::sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(pool.size(), 0);
+
+    let _ = pool.acquire().await?;
+
+    assert_eq!(provided.load(Ordering::SeqCst), 2);
+    assert_eq!(
+        pool.metrics()
+            .connections_closed_by(sqlx::pool::CloseReason::Recycled),
+        1
+    );
+
+    pool.close().await;
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn pool_should_detect_leaked_connections() -> anyhow::Result<()> {
+    #[derive(Default)]
+    struct Observer {
+        leaked: AtomicUsize,
+    }
+
+    impl sqlx::pool::PoolMetricsObserver for Observer {
+        fn connection_leaked(&self, checkout: &sqlx::pool::CheckedOutConnection) {
+            assert!(checkout.backtrace().is_some());
+            self.leaked.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let observer = Arc::new(Observer::default());
+
+    let pool = AnyPoolOptions::new()
+        .leak_detection_threshold(Duration::from_millis(50))
+        .metrics_observer(observer.clone())
+        .connect(&dotenv::var("DATABASE_URL")?)
+        .await?;
+
+    let conn = pool.acquire().await?;
+
+    let checked_out = pool.checked_out();
+    assert_eq!(checked_out.len(), 1);
+    assert!(checked_out[0].backtrace().is_some());
+
+    sqlx_rt::sleep(Duration::from_millis(200)).await;
+
+    // Reported exactly once, no matter how long the connection is held.
+    assert_eq!(observer.leaked.load(Ordering::SeqCst), 1);
+    assert_eq!(pool.metrics().connections_leaked, 1);
+    assert!(pool.checked_out()[0].age() >= Duration::from_millis(200));
+
+    drop(conn);
+
+    assert!(pool.checked_out().is_empty());
+
+    pool.close().await;
+
+    Ok(())
+}
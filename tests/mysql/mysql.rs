@@ -446,3 +446,53 @@ async fn it_can_work_with_transactions() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[sqlx_macros::test]
+async fn it_resets_connections_on_release() -> anyhow::Result<()> {
+    let pool = MySqlPoolOptions::new()
+        .max_connections(1)
+        .reset_on_release(true)
+        .connect(&dotenv::var("DATABASE_URL")?)
+        .await?;
+
+    let mut conn = pool.acquire().await?;
+
+    conn.execute("SET @sqlx_reset_test = 1, time_zone = '+01:00', sql_mode = ''")
+        .await?;
+
+    let _: (i64,) = sqlx::query_as("SELECT ?")
+        .bind(1_i64)
+        .fetch_one(&mut conn)
+        .await?;
+    assert_eq!(conn.cached_statements_size(), 1);
+
+    drop(conn);
+
+    let mut conn = pool.acquire().await?;
+
+    // `COM_RESET_CONNECTION` deallocated the prepared statement so the cache was cleared
+    assert_eq!(conn.cached_statements_size(), 0);
+
+    let value: Option<i64> = sqlx::query_scalar("SELECT @sqlx_reset_test")
+        .fetch_one(&mut conn)
+        .await?;
+    assert_eq!(value, None);
+
+    // the session is configured again after the reset
+    let (time_zone, sql_mode): (String, String) = sqlx::query_as("SELECT @@time_zone, @@sql_mode")
+        .fetch_one(&mut conn)
+        .await?;
+    assert_eq!(time_zone, "+00:00");
+    assert!(sql_mode.contains("PIPES_AS_CONCAT"));
+    assert!(sql_mode.contains("NO_ENGINE_SUBSTITUTION"));
+
+    let concat: String = sqlx::query_scalar("SELECT 'a' || 'b'")
+        .fetch_one(&mut conn)
+        .await?;
+    assert_eq!(concat, "ab");
+
+    drop(conn);
+    pool.close().await;
+
+    Ok(())
+}
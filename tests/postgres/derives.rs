@@ -374,6 +374,129 @@ SELECT $1 = ROW('fuzzy dice', 42, 199)::inventory_item, $1
     Ok(())
 }
 
+// Separate from `mood`, which `test_enum_type` drops and recreates
+#[derive(PartialEq, Debug, sqlx::Type)]
+#[sqlx(type_name = "mood_array_test")]
+#[sqlx(rename_all = "lowercase")]
+enum ArrayMood {
+    Ok,
+    Happy,
+    Sad,
+}
+
+// Domains over custom types
+#[derive(PartialEq, Debug, sqlx::Type)]
+#[sqlx(type_name = "cheerful_mood")]
+struct CheerfulMood(ArrayMood);
+
+#[derive(PartialEq, Debug, sqlx::Type)]
+#[sqlx(type_name = "priced_item")]
+struct PricedItem(InventoryItem);
+
+#[derive(PartialEq, Debug, sqlx::Type)]
+#[sqlx(type_name = "cheerful_mood")]
+struct GenericCheerfulMood<T>(T);
+
+#[sqlx_macros::test]
+async fn test_custom_type_arrays() -> anyhow::Result<()> {
+    let mut conn = new::<Postgres>().await?;
+
+    conn.execute(
+        r#"
+DROP TYPE IF EXISTS mood_array_test CASCADE;
+CREATE TYPE mood_array_test AS ENUM ( 'ok', 'happy', 'sad' );
+
+DROP DOMAIN IF EXISTS cheerful_mood;
+CREATE DOMAIN cheerful_mood AS mood_array_test CHECK (value <> 'sad');
+
+DROP DOMAIN IF EXISTS priced_item;
+CREATE DOMAIN priced_item AS inventory_item CHECK ((value).price IS NOT NULL);
+    "#,
+    )
+    .await?;
+
+    // the new types are resolved by name on first use
+    conn.close().await?;
+    let mut conn = new::<Postgres>().await?;
+
+    let moods = vec![ArrayMood::Happy, ArrayMood::Sad];
+
+    let rec: (bool, Vec<ArrayMood>) =
+        sqlx::query_as("SELECT $1 = '{happy,sad}'::mood_array_test[], $1")
+            .bind(&moods)
+            .fetch_one(&mut conn)
+            .await?;
+
+    assert!(rec.0);
+    assert_eq!(rec.1, moods);
+
+    let items = vec![InventoryItem {
+        name: "fuzzy dice".to_owned(),
+        supplier_id: None,
+        price: Some(199),
+    }];
+
+    let rec: (bool, Vec<InventoryItem>) =
+        sqlx::query_as("SELECT $1 = ARRAY[ROW('fuzzy dice', NULL, 199)::inventory_item], $1")
+            .bind(&items)
+            .fetch_one(&mut conn)
+            .await?;
+
+    assert!(rec.0);
+    assert_eq!(rec.1, items);
+
+    let cheerful = vec![CheerfulMood(ArrayMood::Ok), CheerfulMood(ArrayMood::Happy)];
+
+    let rec: Vec<CheerfulMood> = sqlx::query_scalar("SELECT $1")
+        .bind(&cheerful)
+        .fetch_one(&mut conn)
+        .await?;
+
+    assert_eq!(rec, cheerful);
+
+    let generic = vec![GenericCheerfulMood(ArrayMood::Happy)];
+
+    let rec: Vec<GenericCheerfulMood<ArrayMood>> = sqlx::query_scalar("SELECT $1")
+        .bind(&generic)
+        .fetch_one(&mut conn)
+        .await?;
+
+    assert_eq!(rec, generic);
+
+    let priced: Vec<PricedItem> = sqlx::query_scalar(
+        "SELECT ARRAY[ROW('fuzzy dice', NULL, 199)::inventory_item::priced_item]",
+    )
+    .fetch_one(&mut conn)
+    .await?;
+
+    assert_eq!(priced, [PricedItem(items.into_iter().next().unwrap())]);
+
+    Ok(())
+}
+
+#[cfg(feature = "macros")]
+#[sqlx_macros::test]
+async fn test_custom_type_arrays_with_macros() -> anyhow::Result<()> {
+    let mut conn = new::<Postgres>().await?;
+
+    let items = vec![InventoryItem {
+        name: "fuzzy dice".to_owned(),
+        supplier_id: Some(42),
+        price: Some(199),
+    }];
+
+    let rec = sqlx::query!(
+        r#"SELECT $1::inventory_item[] as "items!: Vec<InventoryItem>""#,
+        &items as &[InventoryItem]
+    )
+    .fetch_one(&mut conn)
+    .await?;
+
+    assert_eq!(rec.items, items);
+
+    Ok(())
+}
+
 #[cfg(feature = "macros")]
 #[sqlx_macros::test]
 async fn test_from_row() -> anyhow::Result<()> {
@@ -96,6 +96,93 @@ async fn test_void() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[sqlx_macros::test]
+async fn test_hstore() -> anyhow::Result<()> {
+    let mut conn = new::<Postgres>().await?;
+
+    let rec = sqlx::query!(r#"SELECT 'a=>1, b=>NULL'::hstore as "store!""#)
+        .fetch_one(&mut conn)
+        .await?;
+
+    let store: sqlx::postgres::types::PgHStore = rec.store;
+
+    assert_eq!(store["a"].as_deref(), Some("1"));
+    assert_eq!(store["b"], None);
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn test_geometric_types() -> anyhow::Result<()> {
+    use sqlx::postgres::types::{PgCircle, PgPoint, PgPolygon};
+
+    let mut conn = new::<Postgres>().await?;
+
+    let rec = sqlx::query!(
+        r#"SELECT point(1, 2) as "point!", '<(0,0),5>'::circle as "circle!", '((0,0),(1,1),(1,0))'::polygon as "polygon!""#
+    )
+    .fetch_one(&mut conn)
+    .await?;
+
+    let point: PgPoint = rec.point;
+    let circle: PgCircle = rec.circle;
+    let polygon: PgPolygon = rec.polygon;
+
+    assert_eq!(point, PgPoint { x: 1.0, y: 2.0 });
+    assert_eq!(circle.radius, 5.0);
+    assert_eq!(polygon.points.len(), 3);
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn test_text_search_types() -> anyhow::Result<()> {
+    use sqlx::postgres::types::{PgTsQuery, PgTsVector};
+
+    let mut conn = new::<Postgres>().await?;
+
+    let query: PgTsQuery = "cat & rat".parse()?;
+
+    let rec = sqlx::query!(
+        r#"SELECT to_tsvector('english', 'The fat cats ate the rats') as "vector!", $1::tsquery as "query!""#,
+        query
+    )
+    .fetch_one(&mut conn)
+    .await?;
+
+    let vector: PgTsVector = rec.vector;
+    let query: PgTsQuery = rec.query;
+
+    assert_eq!(
+        vector.keys().collect::<Vec<_>>(),
+        ["ate", "cat", "fat", "rat"]
+    );
+    assert_eq!(query.to_string(), "'cat' & 'rat'");
+
+    Ok(())
+}
+
+#[cfg(postgres_14)]
+#[sqlx_macros::test]
+async fn test_multirange() -> anyhow::Result<()> {
+    use sqlx::postgres::types::PgMultiRange;
+
+    let mut conn = new::<Postgres>().await?;
+
+    let rec = sqlx::query!(
+        r#"SELECT $1::int4multirange as "ranges!""#,
+        PgMultiRange::from_iter([1..3, 5..7])
+    )
+    .fetch_one(&mut conn)
+    .await?;
+
+    let ranges: PgMultiRange<i32> = rec.ranges;
+
+    assert_eq!(ranges, PgMultiRange::from_iter([1..3, 5..7]));
+
+    Ok(())
+}
+
 #[sqlx_macros::test]
 async fn test_query_file() -> anyhow::Result<()> {
     let mut conn = new::<Postgres>().await?;
@@ -420,6 +507,27 @@ async fn test_column_override_nullable() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[sqlx_macros::test]
+async fn test_infers_non_null_expressions() -> anyhow::Result<()> {
+    let mut conn = new::<Postgres>().await?;
+
+    // neither needs a `!` override
+    let record = sqlx::query!(
+        "select count(*) as count, coalesce(max(text), '') as max_text from tweet where owner_id = $1",
+        1_i64
+    )
+    .fetch_one(&mut conn)
+    .await?;
+
+    let count: i64 = record.count;
+    let max_text: String = record.max_text;
+
+    assert!(count >= 0);
+    assert!(count > 0 || max_text.is_empty());
+
+    Ok(())
+}
+
 async fn with_test_row<'a>(
     conn: &'a mut PgConnection,
 ) -> anyhow::Result<Transaction<'a, Postgres>> {
@@ -1,10 +1,12 @@
 use futures::{StreamExt, TryStreamExt};
 use sqlx::postgres::types::Oid;
 use sqlx::postgres::{
-    PgAdvisoryLock, PgConnectOptions, PgConnection, PgDatabaseError, PgErrorPosition, PgListener,
-    PgPoolOptions, PgRow, PgSeverity, Postgres,
+    PgAdvisoryLock, PgChannelBinding, PgConnectOptions, PgConnection, PgCursor, PgCursorDirection,
+    PgDatabaseError, PgErrorPosition, PgLargeObjectMode, PgListener, PgNotificationEvent,
+    PgNotificationHub, PgPipeline, PgPoolOptions, PgRow, PgSeverity, PgSslMode,
+    PgTargetSessionAttrs, Postgres,
 };
-use sqlx::{Column, Connection, Executor, Row, Statement, TypeInfo};
+use sqlx::{Column, Connection, Either, Executor, Row, Statement, TypeInfo};
 use sqlx_test::{new, pool, setup_if_needed};
 use std::env;
 use std::sync::Arc;
@@ -773,6 +775,41 @@ async fn it_sets_application_name() -> anyhow::Result<()> {
     Ok(())
 }
 
+// the test server authenticates with SCRAM over SSL
+#[cfg(postgres_14)]
+#[sqlx_macros::test]
+async fn it_connects_with_channel_binding() -> anyhow::Result<()> {
+    sqlx_test::setup_if_needed();
+
+    let options: PgConnectOptions = env::var("DATABASE_URL")?.parse()?;
+
+    let mut conn = PgConnection::connect_with(
+        &options
+            .clone()
+            .ssl_mode(PgSslMode::Require)
+            .channel_binding(PgChannelBinding::Require),
+    )
+    .await?;
+
+    let ssl: bool = sqlx::query_scalar("SELECT ssl FROM pg_stat_ssl WHERE pid = pg_backend_pid()")
+        .fetch_one(&mut conn)
+        .await?;
+
+    assert!(ssl);
+
+    // there is nothing to bind to without SSL
+    let res = PgConnection::connect_with(
+        &options
+            .ssl_mode(PgSslMode::Disable)
+            .channel_binding(PgChannelBinding::Require),
+    )
+    .await;
+
+    assert!(res.is_err());
+
+    Ok(())
+}
+
 #[sqlx_macros::test]
 async fn it_can_handle_parameter_status_message_issue_484() -> anyhow::Result<()> {
     new::<Postgres>().await?.execute("SET NAMES 'UTF8'").await?;
@@ -936,6 +973,117 @@ from (values (null)) vals(val)
     Ok(())
 }
 
+#[sqlx_macros::test]
+async fn test_describe_nested_outer_join_nullable() -> anyhow::Result<()> {
+    let mut conn = new::<Postgres>().await?;
+
+    // the join is below a sort and a window function, and the nullable side is a subquery
+    // language=PostgreSQL
+    let describe = conn
+        .describe(
+            "select tweet1.id, tweet2.text, tweet2.text || '!', coalesce(tweet2.text, 'none')
+    from tweet tweet1
+    left join (select * from tweet order by id limit 5) tweet2 on tweet2.id = tweet1.id
+    order by tweet1.created_at",
+        )
+        .await?;
+
+    assert_eq!(describe.nullable(0), Some(false));
+    assert_eq!(describe.nullable(1), Some(true));
+    assert_eq!(describe.nullable(2), Some(true));
+    assert_eq!(describe.nullable(3), Some(false));
+
+    // language=PostgreSQL
+    let describe = conn
+        .describe(
+            "select tweet1.id, tweet2.id
+    from tweet tweet1
+    inner join tweet tweet2 on tweet2.id = tweet1.id
+    left join tweet tweet3 on tweet3.id = tweet2.owner_id
+    where tweet3.id is distinct from $1",
+        )
+        .await?;
+
+    assert_eq!(describe.nullable(0), Some(false));
+    assert_eq!(describe.nullable(1), Some(false));
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn test_describe_expression_nullable() -> anyhow::Result<()> {
+    let mut conn = new::<Postgres>().await?;
+
+    // language=PostgreSQL
+    let describe = conn
+        .describe(
+            "select count(*), count(owner_id), sum(owner_id), coalesce(max(text), '')
+    from tweet",
+        )
+        .await?;
+
+    assert_eq!(describe.nullable(0), Some(false));
+    assert_eq!(describe.nullable(1), Some(false));
+    assert_eq!(describe.nullable(2), None);
+    assert_eq!(describe.nullable(3), Some(false));
+
+    // language=PostgreSQL
+    let describe = conn
+        .describe(
+            "select coalesce(owner_id, id), coalesce(owner_id, $1), coalesce($1, 0::int8),
+        row_number() over (order by id)
+    from tweet",
+        )
+        .await?;
+
+    assert_eq!(describe.nullable(0), Some(false));
+    assert_eq!(describe.nullable(1), None);
+    assert_eq!(describe.nullable(2), Some(false));
+    assert_eq!(describe.nullable(3), Some(false));
+
+    // describing a statement with parameters must not change the session
+    let plan_cache_mode: String = sqlx::query_scalar("SHOW plan_cache_mode")
+        .fetch_one(&mut conn)
+        .await?;
+
+    assert_eq!(plan_cache_mode, "auto");
+
+    // nor a setting local to the caller's transaction
+    let mut tx = conn.begin().await?;
+
+    tx.execute("SET LOCAL plan_cache_mode = force_custom_plan")
+        .await?;
+    tx.describe("select coalesce(owner_id, $1) from tweet")
+        .await?;
+
+    let plan_cache_mode: String = sqlx::query_scalar("SHOW plan_cache_mode")
+        .fetch_one(&mut *tx)
+        .await?;
+
+    assert_eq!(plan_cache_mode, "force_custom_plan");
+
+    tx.commit().await?;
+
+    let plan_cache_mode: String = sqlx::query_scalar("SHOW plan_cache_mode")
+        .fetch_one(&mut conn)
+        .await?;
+
+    assert_eq!(plan_cache_mode, "auto");
+
+    // columns named like special float values aren't mistaken for constants
+    conn.execute("CREATE TEMPORARY TABLE describe_nan (nan float8, inf float8)")
+        .await?;
+
+    // language=PostgreSQL
+    let describe = conn
+        .describe("select coalesce(nan, inf) from describe_nan")
+        .await?;
+
+    assert_ne!(describe.nullable(0), Some(false));
+
+    Ok(())
+}
+
 #[sqlx_macros::test]
 async fn test_listener_cleanup() -> anyhow::Result<()> {
     #[cfg(any(feature = "_rt-tokio", feature = "_rt-actix"))]
@@ -1018,6 +1166,72 @@ async fn test_pg_listener_allows_pool_to_close() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[sqlx_macros::test]
+async fn it_shares_a_listener_between_subscribers() -> anyhow::Result<()> {
+    #[cfg(any(feature = "_rt-tokio", feature = "_rt-actix"))]
+    use tokio::time::timeout;
+
+    #[cfg(feature = "_rt-async-std")]
+    use async_std::future::timeout;
+
+    setup_if_needed();
+
+    // the hub's connection is found by its name to kill it below
+    let options: PgConnectOptions = env::var("DATABASE_URL")?.parse()?;
+    let pool = PgPoolOptions::new()
+        .connect_lazy_with(options.application_name("it_shares_a_listener_between_subscribers"));
+
+    let mut notify_conn = new::<Postgres>().await?;
+
+    let hub = PgNotificationHub::connect_with(&pool).await?;
+
+    let mut first = hub.subscribe("hub_channel_a").await?;
+    let mut second = hub.subscribe("hub_channel_a").await?;
+    let mut other = hub.subscribe("hub_channel_b").await?;
+
+    notify_conn.execute("NOTIFY hub_channel_a, 'hello'").await?;
+
+    // every subscriber to the channel gets its own copy
+    for subscription in [&mut first, &mut second] {
+        match timeout(Duration::from_secs(5), subscription.next()).await? {
+            Some(PgNotificationEvent::Notification(notification)) => {
+                assert_eq!(notification.channel(), "hub_channel_a");
+                assert_eq!(notification.payload(), "hello");
+            }
+            event => panic!("expected a notification, got {:?}", event),
+        }
+    }
+
+    assert!(timeout(Duration::from_millis(100), other.next())
+        .await
+        .is_err());
+
+    // kill the hub's connection; subscribers are told that it was re-established
+    notify_conn
+        .execute(
+            "SELECT pg_terminate_backend(pid) FROM pg_stat_activity \
+             WHERE application_name = 'it_shares_a_listener_between_subscribers'",
+        )
+        .await?;
+
+    assert!(matches!(
+        timeout(Duration::from_secs(5), other.next()).await?,
+        Some(PgNotificationEvent::Reconnected)
+    ));
+
+    // and the channels are listened to again
+    notify_conn.execute("NOTIFY hub_channel_b, 'again'").await?;
+
+    match timeout(Duration::from_secs(5), other.next()).await? {
+        Some(PgNotificationEvent::Notification(notification)) => {
+            assert_eq!(notification.payload(), "again");
+        }
+        event => panic!("expected a notification, got {:?}", event),
+    }
+
+    Ok(())
+}
+
 #[sqlx_macros::test]
 async fn it_supports_domain_types_in_composite_domain_types() -> anyhow::Result<()> {
     // Only supported in Postgres 11+
@@ -1397,6 +1611,152 @@ async fn test_pg_server_num() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[sqlx_macros::test]
+async fn it_reads_and_writes_large_objects() -> anyhow::Result<()> {
+    use sqlx_rt::{AsyncReadExt, AsyncWriteExt};
+    use std::io::SeekFrom;
+
+    #[cfg(feature = "_rt-async-std")]
+    use async_std::io::prelude::SeekExt;
+    #[cfg(any(feature = "_rt-tokio", feature = "_rt-actix"))]
+    use tokio::io::AsyncSeekExt;
+
+    let mut conn = new::<Postgres>().await?;
+    let mut tx = conn.begin().await?;
+
+    let oid = tx.create_large_object().await?;
+
+    // several times the amount sent per query
+    let data: Vec<u8> = (0..3_000_000u32).map(|i| (i % 251) as u8).collect();
+
+    let mut object = tx
+        .open_large_object(oid, PgLargeObjectMode::ReadWrite)
+        .await?;
+    assert_eq!(object.oid(), oid);
+
+    object.write_all(&data).await?;
+    object.flush().await?;
+    object.truncate(2_500_000).await?;
+    object.close().await?;
+
+    let mut object = tx.open_large_object(oid, PgLargeObjectMode::Read).await?;
+
+    let mut read = Vec::new();
+    object.read_to_end(&mut read).await?;
+    assert_eq!(read, &data[..2_500_000]);
+
+    assert_eq!(object.seek(SeekFrom::Start(1000)).await?, 1000);
+
+    let mut buf = [0; 10];
+    object.read_exact(&mut buf).await?;
+    assert_eq!(buf, &data[1000..1010]);
+
+    assert_eq!(object.seek(SeekFrom::Current(-20)).await?, 990);
+    assert_eq!(object.seek(SeekFrom::End(-5)).await?, 2_499_995);
+
+    let mut rest = Vec::new();
+    object.read_to_end(&mut rest).await?;
+    assert_eq!(rest, &data[2_499_995..2_500_000]);
+
+    drop(object);
+
+    // every write is complete once it returns, without flushing
+    let mut object = tx.open_large_object(oid, PgLargeObjectMode::Write).await?;
+    object.write_all(&data).await?;
+    drop(object);
+
+    let written: Vec<u8> = sqlx::query_scalar("SELECT lo_get($1)")
+        .bind(oid)
+        .fetch_one(&mut tx)
+        .await?;
+    assert_eq!(written, data);
+
+    tx.unlink_large_object(oid).await?;
+
+    let exists: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pg_largeobject_metadata WHERE oid = $1)")
+            .bind(oid)
+            .fetch_one(&mut tx)
+            .await?;
+    assert!(!exists);
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_resumes_abandoned_large_object_reads() -> anyhow::Result<()> {
+    use sqlx_rt::{AsyncReadExt, AsyncWriteExt};
+    use std::io::SeekFrom;
+
+    #[cfg(feature = "_rt-async-std")]
+    use async_std::io::prelude::SeekExt;
+    use std::task::Poll;
+    #[cfg(any(feature = "_rt-tokio", feature = "_rt-actix"))]
+    use tokio::io::AsyncSeekExt;
+
+    let mut conn = new::<Postgres>().await?;
+    let mut tx = conn.begin().await?;
+
+    let oid = tx.create_large_object().await?;
+    let data: Vec<u8> = (0..3_000_000u32).map(|i| (i % 251) as u8).collect();
+
+    let mut object = tx
+        .open_large_object(oid, PgLargeObjectMode::ReadWrite)
+        .await?;
+
+    object.write_all(&data).await?;
+    object.seek(SeekFrom::Start(0)).await?;
+
+    // start a read into a large buffer and abandon it (unless it completes right away)
+    let mut large = vec![0; 1024 * 1024];
+    let mut read = Box::pin(object.read(&mut large));
+    let mut position = match futures::poll!(read.as_mut()) {
+        Poll::Ready(read) => read?,
+        Poll::Pending => 0,
+    };
+    drop(read);
+
+    // the data it fetched goes to the next reads, however small their buffers
+    let mut small = [0; 10];
+    object.read_exact(&mut small).await?;
+    assert_eq!(small, &data[position..position + 10]);
+    position += 10;
+
+    assert_eq!(
+        object.seek(SeekFrom::Current(5)).await?,
+        position as u64 + 5
+    );
+    position += 5;
+
+    object.read_exact(&mut small).await?;
+    assert_eq!(small, &data[position..position + 10]);
+    position += 10;
+
+    // and a write goes where the caller expects it to
+    let mut read = Box::pin(object.read(&mut large));
+    if let Poll::Ready(read) = futures::poll!(read.as_mut()) {
+        position += read?;
+    }
+    drop(read);
+
+    object.write_all(b"written").await?;
+    object.flush().await?;
+
+    object.seek(SeekFrom::Start(0)).await?;
+
+    let mut contents = Vec::new();
+    object.read_to_end(&mut contents).await?;
+
+    let mut expected = data.clone();
+    expected[position..position + 7].copy_from_slice(b"written");
+    assert_eq!(contents, expected);
+
+    drop(object);
+    tx.rollback().await?;
+
+    Ok(())
+}
+
 #[sqlx_macros::test]
 async fn it_can_copy_in() -> anyhow::Result<()> {
     let mut conn = new::<Postgres>().await?;
@@ -1493,6 +1853,82 @@ async fn it_can_copy_out() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[sqlx_macros::test]
+async fn it_can_copy_typed_rows() -> anyhow::Result<()> {
+    #[derive(Debug, PartialEq, sqlx::Encode, sqlx::FromRow)]
+    struct User {
+        id: i32,
+        name: Option<String>,
+    }
+
+    let mut conn = new::<Postgres>().await?;
+    conn.execute("CREATE TEMPORARY TABLE users (id INTEGER NOT NULL, name TEXT);")
+        .await?;
+
+    let mut copy = conn
+        .copy_in_raw("COPY users (id, name) FROM STDIN WITH (FORMAT binary);")
+        .await?;
+
+    copy.write_row((1_i32, "alice")).await?;
+    copy.write_row(User { id: 2, name: None }).await?;
+
+    for id in 3..10_000 {
+        copy.write_row((id, format!("user {}", id))).await?;
+    }
+
+    // the wrong number of fields is caught before anything is sent
+    assert!(copy.write_row((0_i32,)).await.is_err());
+
+    let rows = copy.finish().await?;
+    assert_eq!(rows, 9_999);
+
+    let users: Vec<User> = conn
+        .copy_out("SELECT id, name FROM users WHERE id < 4 ORDER BY id")
+        .await?
+        .try_collect()
+        .await?;
+
+    assert_eq!(
+        users,
+        vec![
+            User {
+                id: 1,
+                name: Some("alice".into())
+            },
+            User { id: 2, name: None },
+            User {
+                id: 3,
+                name: Some("user 3".into())
+            },
+        ]
+    );
+
+    let count: (i64,) = conn
+        .copy_out("SELECT count(*) FROM users")
+        .await?
+        .try_next()
+        .await?
+        .unwrap();
+    assert_eq!(count, (9_999,));
+
+    // the queries are only prepared to describe them
+    assert_eq!(conn.cached_statements_size(), 0);
+
+    let prepared: i64 = conn
+        .fetch_one("SELECT count(*) FROM pg_prepared_statements")
+        .await?
+        .get(0);
+    assert_eq!(prepared, 0);
+
+    // conn is safe for reuse
+    let value: i32 = sqlx::query_scalar("SELECT 1 + 1")
+        .fetch_one(&mut conn)
+        .await?;
+    assert_eq!(value, 2);
+
+    Ok(())
+}
+
 #[sqlx_macros::test]
 async fn it_encodes_custom_array_issue_1504() -> anyhow::Result<()> {
     use sqlx::encode::IsNull;
@@ -1714,6 +2150,95 @@ async fn test_advisory_locks() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[sqlx_macros::test]
+async fn test_advisory_xact_locks() -> anyhow::Result<()> {
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&dotenv::var("DATABASE_URL")?)
+        .await?;
+
+    let lock = PgAdvisoryLock::new("sqlx-postgres-tests-xact");
+
+    let mut tx1 = pool.begin().await?;
+    lock.acquire_xact(&mut tx1).await?;
+
+    let mut tx2 = pool.begin().await?;
+    assert!(!lock.try_acquire_xact(&mut tx2).await?);
+
+    // the lock is released when the transaction ends
+    tx1.commit().await?;
+    assert!(lock.try_acquire_xact(&mut tx2).await?);
+
+    tx2.rollback().await?;
+
+    let mut tx1 = pool.begin().await?;
+    assert!(lock.try_acquire_xact(&mut tx1).await?);
+
+    pool.close().await;
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn test_advisory_pooled_locks() -> anyhow::Result<()> {
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&dotenv::var("DATABASE_URL")?)
+        .await?;
+
+    let lock = PgAdvisoryLock::new("sqlx-postgres-tests-pooled");
+
+    let guard = lock
+        .acquire_pooled(&pool)
+        .await?
+        .keep_alive(Duration::from_millis(50));
+
+    // let the connection be pinged a few times
+    sqlx_rt::sleep(Duration::from_millis(200)).await;
+
+    assert!(lock.try_acquire_pooled(&pool).await?.is_none());
+
+    guard.release_now().await?;
+
+    let guard = lock
+        .try_acquire_pooled(&pool)
+        .await?
+        .expect("lock should have been released");
+
+    // a different connection can't take the lock while the guard holds it
+    let mut conn = pool.acquire().await?;
+    assert!(lock.try_acquire(&mut conn).await?.is_right());
+
+    guard.release_now().await?;
+
+    // dropping a guard with a keepalive releases the lock from the background task
+    let guard = lock
+        .acquire_pooled(&pool)
+        .await?
+        .keep_alive(Duration::from_millis(50));
+
+    drop(guard);
+
+    let mut acquired = false;
+
+    for _ in 0..50 {
+        if let Either::Left(guard) = lock.try_acquire(&mut conn).await? {
+            guard.release_now().await?;
+            acquired = true;
+            break;
+        }
+
+        sqlx_rt::sleep(Duration::from_millis(20)).await;
+    }
+
+    assert!(acquired);
+
+    drop(conn);
+    pool.close().await;
+
+    Ok(())
+}
+
 #[sqlx_macros::test]
 async fn test_postgres_bytea_hex_deserialization_errors() -> anyhow::Result<()> {
     let mut conn = new::<Postgres>().await?;
@@ -1726,3 +2251,459 @@ async fn test_postgres_bytea_hex_deserialization_errors() -> anyhow::Result<()>
     }
     Ok(())
 }
+
+#[sqlx_macros::test]
+async fn it_resets_connections_on_release() -> anyhow::Result<()> {
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .reset_on_release(true)
+        .connect(&dotenv::var("DATABASE_URL")?)
+        .await?;
+
+    let mut conn = pool.acquire().await?;
+
+    conn.execute("SET application_name = 'sqlx-reset-test'")
+        .await?;
+    conn.execute("CREATE TEMPORARY TABLE reset_test (id INT)")
+        .await?;
+
+    // populate the statement cache
+    let _: (i32,) = sqlx::query_as("SELECT $1::int4")
+        .bind(1_i32)
+        .fetch_one(&mut conn)
+        .await?;
+    assert_eq!(conn.cached_statements_size(), 1);
+
+    drop(conn);
+
+    let mut conn = pool.acquire().await?;
+
+    // `DISCARD ALL` deallocated the prepared statement so the cache was cleared
+    assert_eq!(conn.cached_statements_size(), 0);
+
+    let application_name: String = sqlx::query_scalar("SHOW application_name")
+        .fetch_one(&mut conn)
+        .await?;
+    assert_ne!(application_name, "sqlx-reset-test");
+
+    let temp_tables: i64 = sqlx::query_scalar(
+        "SELECT count(*) FROM pg_class WHERE relname = 'reset_test' AND relpersistence = 't'",
+    )
+    .fetch_one(&mut conn)
+    .await?;
+    assert_eq!(temp_tables, 0);
+
+    let _: (i32,) = sqlx::query_as("SELECT $1::int4")
+        .bind(1_i32)
+        .fetch_one(&mut conn)
+        .await?;
+
+    drop(conn);
+    pool.close().await;
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_checks_target_session_attrs() -> anyhow::Result<()> {
+    let options: PgConnectOptions = dotenv::var("DATABASE_URL")?.parse()?;
+
+    // the test database is a primary
+    let mut conn = PgConnection::connect_with(
+        &options
+            .clone()
+            .target_session_attrs(PgTargetSessionAttrs::ReadWrite),
+    )
+    .await?;
+    conn.ping().await?;
+
+    // and there are no standbys to fall back on
+    let mut conn = PgConnection::connect_with(
+        &options
+            .clone()
+            .target_session_attrs(PgTargetSessionAttrs::PreferStandby),
+    )
+    .await?;
+    conn.ping().await?;
+
+    let res = PgConnection::connect_with(
+        &options
+            .clone()
+            .target_session_attrs(PgTargetSessionAttrs::Standby),
+    )
+    .await;
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_cancels_queries_with_a_cancel_token() -> anyhow::Result<()> {
+    let mut conn = new::<Postgres>().await?;
+
+    let token = conn.cancel_token();
+
+    sqlx_rt::spawn(async move {
+        sqlx_rt::sleep(Duration::from_millis(200)).await;
+        token.cancel().await.unwrap();
+    });
+
+    let err = conn
+        .execute("SELECT pg_sleep(10)")
+        .await
+        .expect_err("expected the query to be cancelled");
+
+    let err = err
+        .into_database_error()
+        .expect("expected a database error");
+    assert_eq!(err.code().as_deref(), Some("57014"));
+
+    // the connection is still usable
+    let value: i32 = sqlx::query_scalar("SELECT 1").fetch_one(&mut conn).await?;
+    assert_eq!(value, 1);
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_cancels_queries_when_dropped() -> anyhow::Result<()> {
+    let mut conn = new::<Postgres>().await?;
+
+    let start = std::time::Instant::now();
+
+    let res = sqlx_rt::timeout(
+        Duration::from_millis(200),
+        conn.execute("SELECT pg_sleep(10)"),
+    )
+    .await;
+    assert!(res.is_err());
+
+    // the next query doesn't have to wait for the abandoned one to finish
+    let value: i32 = sqlx::query_scalar("SELECT 1").fetch_one(&mut conn).await?;
+    assert_eq!(value, 1);
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_drops_queries_outside_of_a_runtime() -> anyhow::Result<()> {
+    let mut conn = new::<Postgres>().await?;
+
+    // more rows than are received at once
+    let mut rows = conn.fetch("SELECT generate_series(1, 1000000)");
+    assert!(rows.try_next().await?.is_some());
+
+    // there is no runtime to send the cancel request from, which must not panic
+    std::thread::scope(|scope| scope.spawn(move || drop(rows)).join())
+        .expect("dropping the stream panicked");
+
+    let value: i32 = sqlx::query_scalar("SELECT 1").fetch_one(&mut conn).await?;
+    assert_eq!(value, 1);
+
+    // once every row has been read, there is nothing left to cancel
+    let mut rows = conn.fetch("SELECT generate_series(1, 3)");
+    for _ in 0..3 {
+        assert!(rows.try_next().await?.is_some());
+    }
+    drop(rows);
+
+    let value: i32 = sqlx::query_scalar("SELECT 2").fetch_one(&mut conn).await?;
+    assert_eq!(value, 2);
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_executes_a_pipeline() -> anyhow::Result<()> {
+    let mut conn = new::<Postgres>().await?;
+
+    conn.execute("CREATE TEMPORARY TABLE pipeline_test (id INT PRIMARY KEY)")
+        .await?;
+
+    let mut pipeline = PgPipeline::new();
+
+    for i in 0..100_i32 {
+        pipeline.push(sqlx::query("INSERT INTO pipeline_test (id) VALUES ($1)").bind(i));
+    }
+
+    pipeline.push(sqlx::query("SELECT id FROM pipeline_test WHERE id < $1").bind(3_i32));
+
+    let mut results = pipeline.fetch_many(&mut conn);
+    let mut rows = Vec::new();
+
+    while let Some((index, step)) = results.try_next().await? {
+        match step {
+            Either::Left(result) if index < 100 => assert_eq!(result.rows_affected(), 1),
+            Either::Left(result) => assert_eq!(result.rows_affected(), 3),
+            Either::Right(row) => {
+                assert_eq!(index, 100);
+                rows.push(row.try_get::<i32, _>(0)?);
+            }
+        }
+    }
+
+    drop(results);
+    assert_eq!(rows, vec![0, 1, 2]);
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_executes_a_pipeline_larger_than_the_statement_cache() -> anyhow::Result<()> {
+    sqlx_test::setup_if_needed();
+
+    let mut options: PgConnectOptions = env::var("DATABASE_URL")?.parse().unwrap();
+    options = options.statement_cache_capacity(2);
+
+    let mut conn = PgConnection::connect_with(&options).await?;
+
+    let sql: Vec<String> = (0..5).map(|i| format!("SELECT {}::int4 + $1", i)).collect();
+
+    // twice, so that the second pipeline starts with some of the statements cached
+    for _ in 0..2 {
+        let mut pipeline = PgPipeline::new();
+
+        for sql in &sql {
+            pipeline.push(sqlx::query(sql).bind(10_i32));
+        }
+
+        // the first statement is used again after the cache is full
+        pipeline.push(sqlx::query(&sql[0]).bind(20_i32));
+
+        let values: Vec<i32> = pipeline
+            .fetch_many(&mut conn)
+            .try_filter_map(|(_, step)| async move { Ok(step.right()) })
+            .map_ok(|row| row.get::<i32, _>(0))
+            .try_collect()
+            .await?;
+
+        assert_eq!(values, [10, 11, 12, 13, 14, 20]);
+        assert_eq!(conn.cached_statements_size(), 2);
+    }
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_reports_which_query_in_a_pipeline_failed() -> anyhow::Result<()> {
+    let mut conn = new::<Postgres>().await?;
+
+    conn.execute("CREATE TEMPORARY TABLE pipeline_test (id INT PRIMARY KEY)")
+        .await?;
+
+    let mut pipeline = PgPipeline::new();
+
+    for id in [1_i32, 2, 1, 3] {
+        pipeline.push(sqlx::query("INSERT INTO pipeline_test (id) VALUES ($1)").bind(id));
+    }
+
+    let mut results = pipeline.execute(&mut conn);
+
+    assert!(results.try_next().await?.is_some());
+    assert!(results.try_next().await?.is_some());
+
+    let err = results.try_next().await.unwrap_err();
+    assert_eq!(err.index(), 2);
+
+    let err = err.into_error().into_database_error().unwrap();
+    assert_eq!(err.code().as_deref(), Some("23505"));
+
+    drop(results);
+
+    // the pipeline ran in an implicit transaction, so nothing was inserted
+    let count: i64 = sqlx::query_scalar("SELECT count(*) FROM pipeline_test")
+        .fetch_one(&mut conn)
+        .await?;
+    assert_eq!(count, 0);
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_streams_logical_replication_changes() -> anyhow::Result<()> {
+    use sqlx::postgres::replication::{PgReplicationConnection, PgReplicationEvent};
+
+    let mut conn = new::<Postgres>().await?;
+
+    let wal_level: String = sqlx::query_scalar("SHOW wal_level")
+        .fetch_one(&mut conn)
+        .await?;
+
+    if wal_level != "logical" {
+        // logical replication needs `wal_level=logical`
+        return Ok(());
+    }
+
+    conn.execute(
+        r#"
+DROP TABLE IF EXISTS replication_test;
+DROP PUBLICATION IF EXISTS replication_test;
+CREATE TABLE replication_test (id INT PRIMARY KEY, name TEXT);
+CREATE PUBLICATION replication_test FOR TABLE replication_test;
+        "#,
+    )
+    .await?;
+
+    let mut replication = PgReplicationConnection::connect(&dotenv::var("DATABASE_URL")?).await?;
+    let slot = replication
+        .create_replication_slot("sqlx_replication_test", true)
+        .await?;
+
+    conn.execute(
+        r#"
+INSERT INTO replication_test (id, name) VALUES (1, 'one');
+UPDATE replication_test SET name = 'uno' WHERE id = 1;
+DELETE FROM replication_test WHERE id = 1;
+        "#,
+    )
+    .await?;
+
+    let mut stream = replication
+        .start_replication(
+            "sqlx_replication_test",
+            &["replication_test"],
+            slot.consistent_point(),
+        )
+        .await?;
+
+    // The statements above were sent as one simple query, so they ran in a single implicit
+    // transaction and all three changes arrive before its only `Commit`.
+    let mut changes = Vec::new();
+
+    loop {
+        match stream.recv().await?.expect("stream ended early") {
+            PgReplicationEvent::Relation(relation) => {
+                assert_eq!(relation.name(), "replication_test");
+                assert!(relation.is_key_column(0));
+            }
+
+            PgReplicationEvent::Insert { new, .. } => {
+                changes.push(format!(
+                    "insert {} {}",
+                    new.try_get::<i32, _>("id")?,
+                    new.try_get::<String, _>("name")?
+                ));
+            }
+
+            PgReplicationEvent::Update { new, .. } => {
+                changes.push(format!("update {}", new.try_get::<String, _>("name")?));
+            }
+
+            PgReplicationEvent::Delete { old, .. } => {
+                changes.push(format!("delete {}", old.try_get::<i32, _>("id")?));
+            }
+
+            PgReplicationEvent::Commit { end_lsn, .. } => {
+                assert!(end_lsn > slot.consistent_point());
+                break;
+            }
+
+            _ => {}
+        }
+    }
+
+    assert_eq!(changes, ["insert 1 one", "update uno", "delete 1"]);
+
+    let replication = stream.stop().await?;
+    replication.close().await?;
+
+    conn.execute("DROP PUBLICATION replication_test; DROP TABLE replication_test;")
+        .await?;
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn test_cursor() -> anyhow::Result<()> {
+    let mut conn = new::<Postgres>().await?;
+    let mut tx = conn.begin().await?;
+
+    let mut cursor = PgCursor::declare_scroll(
+        &mut tx,
+        sqlx::query("SELECT i FROM generate_series(1, $1) i").bind(10_i32),
+    )
+    .await?;
+
+    let ids = |rows: Vec<PgRow>| -> anyhow::Result<Vec<i32>> {
+        Ok(rows
+            .iter()
+            .map(|row| row.try_get(0))
+            .collect::<Result<_, _>>()?)
+    };
+
+    assert_eq!(ids(cursor.fetch_next(3).await?)?, [1, 2, 3]);
+    assert_eq!(cursor.seek(PgCursorDirection::Forward(4)).await?, 4);
+    assert_eq!(ids(cursor.fetch(PgCursorDirection::Next).await?)?, [8]);
+    assert_eq!(
+        ids(cursor.fetch(PgCursorDirection::Backward(2)).await?)?,
+        [7, 6]
+    );
+    assert_eq!(
+        ids(cursor.fetch(PgCursorDirection::Absolute(-1)).await?)?,
+        [10]
+    );
+    assert_eq!(ids(cursor.fetch_next(5).await?)?, Vec::<i32>::new());
+
+    let name = cursor.name().to_owned();
+    cursor.close().await?;
+
+    let open: i64 = sqlx::query_scalar("SELECT count(*) FROM pg_cursors WHERE name = $1")
+        .bind(&name)
+        .fetch_one(&mut tx)
+        .await?;
+
+    assert_eq!(open, 0);
+
+    // a cursor that isn't closed goes away with the transaction
+    let mut cursor = PgCursor::declare(&mut tx, sqlx::query("SELECT 1")).await?;
+    assert_eq!(
+        ids(cursor.fetch(PgCursorDirection::ForwardAll).await?)?,
+        [1]
+    );
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn test_fetch_in_batches() -> anyhow::Result<()> {
+    let mut conn = new::<Postgres>().await?;
+
+    let ids: Vec<i32> = sqlx::query("SELECT i FROM generate_series(1, $1) i")
+        .bind(1000_i32)
+        .fetch_in_batches(&mut conn, 100)
+        .map_ok(|row| row.get::<i32, _>(0))
+        .try_collect()
+        .await?;
+
+    assert_eq!(ids, (1..=1000).collect::<Vec<_>>());
+
+    // stop reading half way through a batch
+    let mut rows = sqlx::query("SELECT i FROM generate_series(1, 1000) i")
+        .fetch_in_batches(&mut conn, 100)
+        .take(150);
+
+    while let Some(row) = rows.next().await {
+        row?;
+    }
+
+    drop(rows);
+
+    let value: i32 = sqlx::query_scalar("SELECT 42").fetch_one(&mut conn).await?;
+    assert_eq!(value, 42);
+
+    // errors end the stream and leave the connection usable
+    let res: Result<Vec<_>, _> = sqlx::query("SELECT 1 / (i - 150) FROM generate_series(1, 200) i")
+        .fetch_in_batches(&mut conn, 100)
+        .try_collect()
+        .await;
+
+    assert!(res.is_err());
+
+    let value: i32 = sqlx::query_scalar("SELECT 42").fetch_one(&mut conn).await?;
+    assert_eq!(value, 42);
+
+    Ok(())
+}
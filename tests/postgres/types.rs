@@ -2,7 +2,9 @@ extern crate time_ as time;
 
 use std::ops::Bound;
 
-use sqlx::postgres::types::{Oid, PgInterval, PgMoney, PgRange};
+use sqlx::postgres::types::{
+    Oid, PgBox, PgCircle, PgInterval, PgLSeg, PgLine, PgMoney, PgPath, PgPoint, PgPolygon, PgRange,
+};
 use sqlx::postgres::Postgres;
 use sqlx_test::{test_decode_type, test_prepared_type, test_type};
 use std::str::FromStr;
@@ -513,6 +515,25 @@ test_type!(int4range<PgRange<i32>>(Postgres,
     "'[1,2]'::int4range" == PgRange::from((INC1, EXC3)),
 ));
 
+#[cfg(postgres_14)]
+test_type!(int4multirange<sqlx::postgres::types::PgMultiRange<i32>>(Postgres,
+    "'{}'::int4multirange" == sqlx::postgres::types::PgMultiRange::<i32>::default(),
+    "'{[1,2)}'::int4multirange" == sqlx::postgres::types::PgMultiRange::from_iter([(INC1, EXC2)]),
+    "'{(,2), [5,7]}'::int4multirange" ==
+        sqlx::postgres::types::PgMultiRange::from_iter([
+            (UNB, EXC2),
+            (Bound::Included(5), Bound::Excluded(8)),
+        ]),
+));
+
+#[cfg(postgres_14)]
+test_type!(int4multirange_vec<Vec<sqlx::postgres::types::PgMultiRange<i32>>>(Postgres,
+    "array['{[1,2)}', '{}']::int4multirange[]" == vec![
+        sqlx::postgres::types::PgMultiRange::from_iter([(INC1, EXC2)]),
+        sqlx::postgres::types::PgMultiRange::<i32>::default(),
+    ],
+));
+
 test_prepared_type!(interval<PgInterval>(
     Postgres,
     "INTERVAL '1h'"
@@ -547,6 +568,113 @@ test_prepared_type!(money_vec<Vec<PgMoney>>(Postgres,
     "array[123.45,420.00,666.66]::money[]" == vec![PgMoney(12345), PgMoney(42000), PgMoney(66666)],
 ));
 
+// the geometric types have no `=` operator (or an approximate one), so compare their text forms
+test_type!(point<PgPoint>(Postgres, "SELECT ({0}::text is not distinct from $1::text)::int4, {0}, $2",
+    "point(1.5, -2)" == PgPoint { x: 1.5, y: -2.0 },
+));
+
+test_type!(point_vec<Vec<PgPoint>>(Postgres, "SELECT ({0}::text is not distinct from $1::text)::int4, {0}, $2",
+    "array[point(1, 2), point(3, 4)]" == vec![PgPoint { x: 1.0, y: 2.0 }, PgPoint { x: 3.0, y: 4.0 }],
+));
+
+test_type!(line<PgLine>(Postgres,
+    "'{1,-1,0}'::line" == PgLine { a: 1.0, b: -1.0, c: 0.0 },
+));
+
+test_type!(lseg<PgLSeg>(Postgres,
+    "'[(0,0),(1,2)]'::lseg" == PgLSeg { start: PgPoint { x: 0.0, y: 0.0 }, end: PgPoint { x: 1.0, y: 2.0 } },
+));
+
+test_type!(box<PgBox>(Postgres,
+    "'((2,3),(0,1))'::box" == PgBox { upper_right: PgPoint { x: 2.0, y: 3.0 }, lower_left: PgPoint { x: 0.0, y: 1.0 } },
+));
+
+test_type!(box_vec<Vec<PgBox>>(Postgres, "SELECT ({0}::text is not distinct from $1::text)::int4, {0}, $2",
+    "array['((1,1),(0,0))'::box, '((3,3),(2,2))'::box]" == vec![
+        PgBox { upper_right: PgPoint { x: 1.0, y: 1.0 }, lower_left: PgPoint { x: 0.0, y: 0.0 } },
+        PgBox { upper_right: PgPoint { x: 3.0, y: 3.0 }, lower_left: PgPoint { x: 2.0, y: 2.0 } },
+    ],
+));
+
+test_type!(path<PgPath>(Postgres,
+    "'[(0,0),(1,1),(2,0)]'::path" == PgPath {
+        closed: false,
+        points: vec![PgPoint { x: 0.0, y: 0.0 }, PgPoint { x: 1.0, y: 1.0 }, PgPoint { x: 2.0, y: 0.0 }],
+    },
+    "'((0,0),(1,1))'::path" == PgPath {
+        closed: true,
+        points: vec![PgPoint { x: 0.0, y: 0.0 }, PgPoint { x: 1.0, y: 1.0 }],
+    },
+));
+
+test_type!(polygon<PgPolygon>(Postgres, "SELECT ({0}::text is not distinct from $1::text)::int4, {0}, $2",
+    "'((0,0),(0,1),(1,0))'::polygon" == PgPolygon {
+        points: vec![PgPoint { x: 0.0, y: 0.0 }, PgPoint { x: 0.0, y: 1.0 }, PgPoint { x: 1.0, y: 0.0 }],
+    },
+));
+
+test_type!(circle<PgCircle>(Postgres,
+    "'<(1,2),3>'::circle" == PgCircle { center: PgPoint { x: 1.0, y: 2.0 }, radius: 3.0 },
+));
+
+test_type!(circle_vec<Vec<PgCircle>>(Postgres, "SELECT ({0}::text is not distinct from $1::text)::int4, {0}, $2",
+    "array['<(0,0),1>'::circle]" == vec![PgCircle { center: PgPoint { x: 0.0, y: 0.0 }, radius: 1.0 }],
+));
+
+test_type!(hstore<sqlx::postgres::types::PgHStore>(Postgres,
+    "'a=>1, b=>NULL, \"c d\"=>\"x\\\"y\"'::hstore" == sqlx::postgres::types::PgHStore::from_iter([
+        ("a", Some("1")),
+        ("b", None),
+        ("c d", Some("x\"y")),
+    ]),
+    "''::hstore" == sqlx::postgres::types::PgHStore::default(),
+));
+
+test_type!(hstore_vec<Vec<sqlx::postgres::types::PgHStore>>(Postgres,
+    "array['a=>1'::hstore, 'b=>NULL'::hstore]" == vec![
+        sqlx::postgres::types::PgHStore::from_iter([("a", Some("1"))]),
+        sqlx::postgres::types::PgHStore::from_iter([("b", None::<&str>)]),
+    ],
+));
+
+test_type!(tsvector<sqlx::postgres::types::PgTsVector>(Postgres,
+    "''::tsvector" == sqlx::postgres::types::PgTsVector::default(),
+    "'a fat cat:3,1A sat:4B'::tsvector" == "'a' 'cat':1A,3 'fat' 'sat':4B"
+        .parse::<sqlx::postgres::types::PgTsVector>()
+        .unwrap(),
+    "to_tsvector('english', 'The cat''s whiskers')" == sqlx::postgres::types::PgTsVector::from_iter([
+        ("cat", vec![sqlx::postgres::types::PgTsPosition::new(2)]),
+        ("whisker", vec![sqlx::postgres::types::PgTsPosition::new(4)]),
+    ]),
+));
+
+test_type!(tsvector_vec<Vec<sqlx::postgres::types::PgTsVector>>(Postgres,
+    "array['a:1'::tsvector, ''::tsvector]" == vec![
+        "a:1".parse::<sqlx::postgres::types::PgTsVector>().unwrap(),
+        sqlx::postgres::types::PgTsVector::default(),
+    ],
+));
+
+test_type!(tsquery<sqlx::postgres::types::PgTsQuery>(Postgres,
+    "'fat & rat'::tsquery" == sqlx::postgres::types::PgTsQuery::And(
+        Box::new(sqlx::postgres::types::PgTsQuery::lexeme("fat")),
+        Box::new(sqlx::postgres::types::PgTsQuery::lexeme("rat")),
+    ),
+    "'!(a | b:*AB) <2> c & ''it''''s'''::tsquery" == "!(a | b:*AB) <2> c & 'it''s'"
+        .parse::<sqlx::postgres::types::PgTsQuery>()
+        .unwrap(),
+    "'a <-> (b <-> c)'::tsquery" == "'a' <-> ( 'b' <-> 'c' )"
+        .parse::<sqlx::postgres::types::PgTsQuery>()
+        .unwrap(),
+));
+
+test_type!(tsquery_vec<Vec<sqlx::postgres::types::PgTsQuery>>(Postgres,
+    "array['a'::tsquery, 'b & c'::tsquery]" == vec![
+        sqlx::postgres::types::PgTsQuery::lexeme("a"),
+        "b & c".parse::<sqlx::postgres::types::PgTsQuery>().unwrap(),
+    ],
+));
+
 // FIXME: needed to disable `ltree` tests in Postgres 9.6
 // but `PgLTree` should just fall back to text format
 #[cfg(postgres_14)]
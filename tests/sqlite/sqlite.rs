@@ -719,3 +719,38 @@ async fn concurrent_read_and_write() {
         write.await;
     }
 }
+
+#[sqlx_macros::test]
+async fn it_fails_fast_when_circuit_breaker_is_open() -> anyhow::Result<()> {
+    let options = SqliteConnectOptions::new().filename("/nonexistent/sqlx/circuit-breaker.db");
+
+    let pool: SqlitePool = SqlitePoolOptions::new()
+        .circuit_breaker_threshold(2)
+        .circuit_breaker_cooldown(std::time::Duration::from_millis(200))
+        .connect_lazy_with(options);
+
+    // The first failures are reported as-is.
+    for _ in 0..2 {
+        let error = pool.acquire().await.unwrap_err();
+        assert!(!matches!(error, sqlx::Error::PoolUnavailable), "{}", error);
+    }
+
+    // Then the circuit opens and attempts fail without trying to connect.
+    assert!(matches!(
+        pool.acquire().await,
+        Err(sqlx::Error::PoolUnavailable)
+    ));
+
+    // After the cooldown a single probe is allowed through, which fails and reopens the circuit.
+    sqlx_rt::sleep(std::time::Duration::from_millis(250)).await;
+
+    let error = pool.acquire().await.unwrap_err();
+    assert!(!matches!(error, sqlx::Error::PoolUnavailable), "{}", error);
+
+    assert!(matches!(
+        pool.acquire().await,
+        Err(sqlx::Error::PoolUnavailable)
+    ));
+
+    Ok(())
+}